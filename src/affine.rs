@@ -1,5 +1,56 @@
 //! An implementation of the Affine cipher.
 
+use crate::cipher::{Cipher, CipherError};
+use crate::formatting::Formatting;
+use crate::prelude::*;
+use crate::trace::Step;
+
+/// The key of an Affine cipher, for use through the [`Cipher`] trait.
+///
+/// Through the trait, letters of both cases are enciphered, and the case of
+/// the text is preserved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Affine {
+    /// The multiplicative key. Must be coprime with 26.
+    pub a: i32,
+    /// The additive key. Can be any integer.
+    pub b: i32,
+}
+
+impl Affine {
+    /// Creates an Affine key, checking that it can be inverted.
+    ///
+    /// # Returns
+    ///
+    /// The key, or an error if `a` is not coprime with 26.
+    pub fn new(a: i32, b: i32) -> Result<Self, CipherError> {
+        modular_inverse(a.rem_euclid(26), 26)
+            .map(|_| Affine { a, b })
+            .ok_or(CipherError::Affine("a must be coprime with 26"))
+    }
+}
+
+impl Cipher for Affine {
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let key = Affine::new(self.a, self.b)?;
+        Formatting::Preserve
+            .try_apply(plaintext, |letters| {
+                encrypt(&letters.to_ascii_lowercase(), key.a.rem_euclid(26), key.b)
+            })
+            .map_err(CipherError::Affine)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let key = Affine::new(self.a, self.b)?;
+        Formatting::Preserve
+            .try_apply(ciphertext, |letters| {
+                decrypt(&letters.to_ascii_lowercase(), key.a.rem_euclid(26), key.b)
+            })
+            .map_err(CipherError::Affine)
+    }
+}
+
 /// Encrypts a given plaintext using the Affine cipher.
 ///
 /// # Arguments
@@ -11,7 +62,7 @@
 /// # Returns
 ///
 /// * `Result<String, &'static str>` - Returns an Ok variant with encrypted text as a string,
-/// or an Err variant with a static string describing the error.
+///   or an Err variant with a static string describing the error.
 pub fn encrypt(plaintext: &str, a: i32, b: i32) -> Result<String, &'static str> {
     let mut ciphertext = String::new();
     for c in plaintext.chars() {
@@ -37,7 +88,7 @@ pub fn encrypt(plaintext: &str, a: i32, b: i32) -> Result<String, &'static str>
 /// # Returns
 ///
 /// * `Result<String, &'static str>` - Returns an Ok variant with decrypted text as a string,
-/// or an Err variant with a static string describing the error.
+///   or an Err variant with a static string describing the error.
 pub fn decrypt(ciphertext: &str, a: i32, b: i32) -> Result<String, &'static str> {
     let inverse_a = modular_inverse(a, 26).ok_or("Failed to find modular inverse")?;
     let mut plaintext = String::new();
@@ -53,6 +104,75 @@ pub fn decrypt(ciphertext: &str, a: i32, b: i32) -> Result<String, &'static str>
     Ok(plaintext)
 }
 
+/// Encrypts a given plaintext using the Affine cipher, recording every step.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+/// * `a` - The multiplicative key. Should be coprime with 26.
+/// * `b` - The additive key. Can be any integer.
+///
+/// # Returns
+///
+/// The encrypted text, and one step per character with the index `x` of the
+/// letter (`a` = 0) and the index `a * x + b` of the result, modulo 26.
+pub fn encrypt_traced(plaintext: &str, a: i32, b: i32) -> (String, Vec<Step>) {
+    plaintext
+        .chars()
+        .map(|c| {
+            if c.is_ascii_lowercase() {
+                let x = c as i32 - 'a' as i32;
+                let y = ((a * x + b) % 26 + 26) % 26;
+                let output = (y + 'a' as i32) as u8 as char;
+                let step = Step::new(c, output, "multiply-add")
+                    .detail("x", x)
+                    .detail("y", y);
+                (output, step)
+            } else {
+                (c, Step::new(c, c, "keep"))
+            }
+        })
+        .unzip()
+}
+
+/// Decrypts a given ciphertext using the Affine cipher, recording every step.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted.
+/// * `a` - The multiplicative key. Should be coprime with 26.
+/// * `b` - The additive key. Can be any integer.
+///
+/// # Returns
+///
+/// The decrypted text, and one step per character with the index `y` of the
+/// letter, the inverse of `a` and the index `a⁻¹ * (y - b)` of the result,
+/// modulo 26, or an error if `a` has no inverse.
+pub fn decrypt_traced(
+    ciphertext: &str,
+    a: i32,
+    b: i32,
+) -> Result<(String, Vec<Step>), &'static str> {
+    let inverse_a = modular_inverse(a, 26).ok_or("Failed to find modular inverse")?;
+    Ok(ciphertext
+        .chars()
+        .map(|c| {
+            if c.is_ascii_lowercase() {
+                let y = c as i32 - 'a' as i32;
+                let x = ((inverse_a * (y - b + 26)) % 26 + 26) % 26;
+                let output = (x + 'a' as i32) as u8 as char;
+                let step = Step::new(c, output, "inverse")
+                    .detail("y", y)
+                    .detail("inverse", inverse_a)
+                    .detail("x", x);
+                (output, step)
+            } else {
+                (c, Step::new(c, c, "keep"))
+            }
+        })
+        .unzip())
+}
+
 /// Finds the modular inverse of a given number modulo `m`.
 ///
 /// # Arguments
@@ -63,7 +183,7 @@ pub fn decrypt(ciphertext: &str, a: i32, b: i32) -> Result<String, &'static str>
 /// # Returns
 ///
 /// * `Option<i32>` - Returns a Some variant with the modular inverse if it exists,
-/// or a None variant if it doesn't exist.
+///   or a None variant if it doesn't exist.
 fn modular_inverse(a: i32, m: i32) -> Option<i32> {
     (1..m).find(|&i| (a * i) % m == 1)
 }
@@ -84,6 +204,30 @@ mod tests {
         assert_eq!(decrypt("dmgj", 3, 4).unwrap(), "rust");
     }
 
+    #[test]
+    fn test_affine_traced() {
+        let (ciphertext, steps) = encrypt_traced("hi!", 5, 8);
+        assert_eq!(ciphertext, encrypt("hi!", 5, 8).unwrap());
+        assert_eq!(
+            steps[0],
+            Step::new('h', 'r', "multiply-add")
+                .detail("x", 7)
+                .detail("y", 17)
+        );
+        assert_eq!(steps[2], Step::new('!', '!', "keep"));
+
+        let (plaintext, steps) = decrypt_traced(&ciphertext, 5, 8).unwrap();
+        assert_eq!(plaintext, "hi!");
+        assert_eq!(
+            steps[0],
+            Step::new('r', 'h', "inverse")
+                .detail("y", 17)
+                .detail("inverse", 21)
+                .detail("x", 7)
+        );
+        assert!(decrypt_traced("abc", 13, 1).is_err());
+    }
+
     #[test]
     fn test_affine_modular_inverse() {
         assert_eq!(modular_inverse(5, 26), Some(21));
@@ -0,0 +1,230 @@
+//! An implementation of the ITA2 (Baudot-Murray) teleprinter code.
+//!
+//! ITA2 encodes characters on five bits ("impulses"). Since 32 codes are not
+//! enough for letters and figures, two of them switch the meaning of the
+//! others: after `FIGURES_SHIFT`, codes are read as digits and punctuation until
+//! a `LETTERS_SHIFT` is received.
+//!
+//! Codes are represented as `u8` values in `0..32`, the first impulse being the
+//! most significant bit, so that `A` (impulses `11000`) is `0b11000`.
+
+use crate::prelude::*;
+use thiserror::Error;
+
+/// The number of distinct five-bit codes.
+pub const CODE_COUNT: usize = 32;
+
+/// The null code (`00000`), which prints nothing.
+pub const NULL: u8 = 0b00000;
+/// The carriage return code.
+pub const CARRIAGE_RETURN: u8 = 0b00010;
+/// The line feed code.
+pub const LINE_FEED: u8 = 0b01000;
+/// The space code.
+pub const SPACE: u8 = 0b00100;
+/// Switches to the figures case.
+pub const FIGURES_SHIFT: u8 = 0b11011;
+/// Switches to the letters case.
+pub const LETTERS_SHIFT: u8 = 0b11111;
+
+/// The letters case, indexed by code. `\0` marks the codes that are not letters.
+const LETTERS: [char; CODE_COUNT] = [
+    '\0', 'T', '\r', 'O', ' ', 'H', 'N', 'M', '\n', 'L', 'R', 'G', 'I', 'P', 'C', 'V', 'E', 'Z',
+    'D', 'B', 'S', 'Y', 'F', 'X', 'A', 'W', 'J', '\0', 'U', 'Q', 'K', '\0',
+];
+
+/// The figures case, indexed by code. `\0` marks the codes that have no international meaning.
+/// Code `D` is the "Who are you?" enquiry and code `J` rings the bell.
+const FIGURES: [char; CODE_COUNT] = [
+    '\0', '5', '\r', '9', ' ', '\0', ',', '.', '\n', ')', '4', '\0', '8', '0', ':', '=', '3', '+',
+    '\u{5}', '?', '\'', '6', '\0', '/', '-', '2', '\u{7}', '\0', '7', '1', '(', '\0',
+];
+
+/// The symbols used at Bletchley Park to write codes, indexed by code.
+///
+/// Letters stand for themselves, and the six other codes are written `/`
+/// (null), `9` (space), `3` (line feed), `4` (carriage return), `5` (figures
+/// shift) and `8` (letters shift).
+const BLETCHLEY: [char; CODE_COUNT] = [
+    '/', 'T', '4', 'O', '9', 'H', 'N', 'M', '3', 'L', 'R', 'G', 'I', 'P', 'C', 'V', 'E', 'Z', 'D',
+    'B', 'S', 'Y', 'F', 'X', 'A', 'W', 'J', '5', 'U', 'Q', 'K', '8',
+];
+
+/// Error type for the Baudot code.
+#[derive(Error, Debug, PartialEq)]
+pub enum BaudotError {
+    #[error("Character cannot be encoded in ITA2: {0}")]
+    UnsupportedChar(char),
+    #[error("Not a five-bit code: {0}")]
+    InvalidCode(u8),
+}
+
+/// The two cases of the teleprinter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Shift {
+    Letters,
+    Figures,
+}
+
+/// Encodes a text into ITA2 codes.
+///
+/// The encoder starts in the letters case and inserts shift codes whenever a
+/// character of the other case is needed. Lowercase letters are encoded as
+/// uppercase ones.
+///
+/// # Arguments
+///
+/// * `text` - The text to be encoded.
+///
+/// # Returns
+///
+/// The codes, or an error if a character has no ITA2 code.
+pub fn encode(text: &str) -> Result<Vec<u8>, BaudotError> {
+    let mut shift = Shift::Letters;
+    let mut codes = Vec::with_capacity(text.len());
+
+    for c in text.chars() {
+        let c = c.to_ascii_uppercase();
+        // Characters present in both cases don't need a shift.
+        let in_letters = LETTERS.iter().position(|&l| l == c && c != '\0');
+        let in_figures = FIGURES.iter().position(|&f| f == c && c != '\0');
+
+        let code = match (in_letters, in_figures) {
+            (Some(code), Some(_)) => code,
+            (Some(code), None) => {
+                if shift == Shift::Figures {
+                    codes.push(LETTERS_SHIFT);
+                    shift = Shift::Letters;
+                }
+                code
+            }
+            (None, Some(code)) => {
+                if shift == Shift::Letters {
+                    codes.push(FIGURES_SHIFT);
+                    shift = Shift::Figures;
+                }
+                code
+            }
+            (None, None) => return Err(BaudotError::UnsupportedChar(c)),
+        };
+        codes.push(code as u8);
+    }
+
+    Ok(codes)
+}
+
+/// Decodes ITA2 codes into a text.
+///
+/// The decoder starts in the letters case. Shift and null codes, as well as
+/// figures without an international meaning, produce no character.
+///
+/// # Arguments
+///
+/// * `codes` - The codes to be decoded.
+///
+/// # Returns
+///
+/// The decoded text, or an error if a value is not a five-bit code.
+pub fn decode(codes: &[u8]) -> Result<String, BaudotError> {
+    let mut shift = Shift::Letters;
+    let mut text = String::with_capacity(codes.len());
+
+    for &code in codes {
+        match code {
+            FIGURES_SHIFT => shift = Shift::Figures,
+            LETTERS_SHIFT => shift = Shift::Letters,
+            _ => {
+                let table = match shift {
+                    Shift::Letters => &LETTERS,
+                    Shift::Figures => &FIGURES,
+                };
+                let c = *table
+                    .get(code as usize)
+                    .ok_or(BaudotError::InvalidCode(code))?;
+                if c != '\0' {
+                    text.push(c);
+                }
+            }
+        }
+    }
+
+    Ok(text)
+}
+
+/// Writes codes in the Bletchley Park notation, one symbol per code.
+///
+/// # Arguments
+///
+/// * `codes` - The codes to be written.
+///
+/// # Returns
+///
+/// The codes in Bletchley notation, or an error if a value is not a five-bit code.
+pub fn to_bletchley(codes: &[u8]) -> Result<String, BaudotError> {
+    codes
+        .iter()
+        .map(|&code| {
+            BLETCHLEY
+                .get(code as usize)
+                .copied()
+                .ok_or(BaudotError::InvalidCode(code))
+        })
+        .collect()
+}
+
+/// Reads codes written in the Bletchley Park notation.
+///
+/// # Arguments
+///
+/// * `text` - The codes in Bletchley notation.
+///
+/// # Returns
+///
+/// The codes, or an error if a symbol is not part of the notation.
+pub fn from_bletchley(text: &str) -> Result<Vec<u8>, BaudotError> {
+    text.chars()
+        .map(|c| {
+            let c = c.to_ascii_uppercase();
+            BLETCHLEY
+                .iter()
+                .position(|&b| b == c)
+                .map(|code| code as u8)
+                .ok_or(BaudotError::UnsupportedChar(c))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_baudot_encode() {
+        assert_eq!(encode("A").unwrap(), vec![0b11000]);
+        assert_eq!(
+            encode("RY 1").unwrap(),
+            vec![0b01010, 0b10101, SPACE, FIGURES_SHIFT, 0b11101]
+        );
+        assert_eq!(
+            encode("1A").unwrap(),
+            vec![FIGURES_SHIFT, 0b11101, LETTERS_SHIFT, 0b11000]
+        );
+        assert_eq!(encode("A*"), Err(BaudotError::UnsupportedChar('*')));
+    }
+
+    #[test]
+    fn test_baudot_round_trip() {
+        let text = "MEET AT 10.30, PIER (7)?\r\n";
+        assert_eq!(decode(&encode(text).unwrap()).unwrap(), text);
+        assert_eq!(decode(&encode("hello").unwrap()).unwrap(), "HELLO");
+        assert_eq!(decode(&[32]), Err(BaudotError::InvalidCode(32)));
+    }
+
+    #[test]
+    fn test_baudot_bletchley() {
+        let codes = encode("HI 5").unwrap();
+        assert_eq!(to_bletchley(&codes).unwrap(), "HI95T");
+        assert_eq!(from_bletchley("HI95T").unwrap(), codes);
+        assert_eq!(from_bletchley("a!"), Err(BaudotError::UnsupportedChar('!')));
+    }
+}
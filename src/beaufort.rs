@@ -1 +1,162 @@
 //! An implementation of the Beaufort cipher
+//!
+//! The Beaufort cipher uses the same tableau as the Vigenère cipher, but
+//! subtracts the plaintext from the key instead of adding the key to the
+//! plaintext: `C = K - P (mod 26)`. As a consequence, the cipher is reciprocal,
+//! and the same operation is used to encrypt and decrypt.
+
+use crate::prelude::*;
+use crate::trace::Step;
+use thiserror::Error;
+
+const ALPHABET_SIZE: u8 = 26;
+
+/// Error type for the Beaufort cipher.
+#[derive(Error, Debug, PartialEq)]
+pub enum BeaufortError {
+    #[error("Key must contain at least one letter")]
+    EmptyKey,
+}
+
+/// Encrypts the provided plaintext using the Beaufort cipher and the given key.
+///
+/// Non-alphabetic characters are left unchanged and do not advance the key.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+/// * `key` - The key to use for encryption. Non-alphabetic characters are ignored.
+///
+/// # Returns
+///
+/// The encrypted text in uppercase, or an error if the key has no letters.
+pub fn encrypt(plaintext: &str, key: &str) -> Result<String, BeaufortError> {
+    encipher(plaintext, key)
+}
+
+/// Decrypts the provided ciphertext using the Beaufort cipher and the given key.
+///
+/// Since the cipher is reciprocal, this is the same operation as [`encrypt`].
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted.
+/// * `key` - The key used for encryption. Non-alphabetic characters are ignored.
+///
+/// # Returns
+///
+/// The decrypted text in uppercase, or an error if the key has no letters.
+pub fn decrypt(ciphertext: &str, key: &str) -> Result<String, BeaufortError> {
+    encipher(ciphertext, key)
+}
+
+/// Encrypts or decrypts a text using the Beaufort cipher, recording every step.
+///
+/// # Arguments
+///
+/// * `text` - The text to be transformed.
+/// * `key` - The key. Non-alphabetic characters are ignored.
+///
+/// # Returns
+///
+/// The transformed text in uppercase, and one step per character, or an error
+/// if the key has no letters.
+pub fn encrypt_traced(text: &str, key: &str) -> Result<(String, Vec<Step>), BeaufortError> {
+    let output = encipher(text, key)?;
+    let mut key_stream = key
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_uppercase())
+        .cycle();
+    let steps = text
+        .chars()
+        .zip(output.chars())
+        .map(|(c, o)| {
+            if c.is_ascii_alphabetic() {
+                let k = key_stream.next().unwrap_or('A');
+                Step::new(c, o, "subtract").detail("key", k)
+            } else {
+                Step::new(c, o, "keep")
+            }
+        })
+        .collect();
+    Ok((output, steps))
+}
+
+/// Substitutes a letter using the Beaufort tableau.
+///
+/// # Arguments
+///
+/// * `letter` - The index of the letter to substitute (`A` = 0).
+/// * `key` - The index of the key letter (`A` = 0).
+///
+/// # Returns
+///
+/// The index of the substituted letter, `key - letter (mod 26)`.
+pub fn substitute(letter: u8, key: u8) -> u8 {
+    (key % ALPHABET_SIZE + ALPHABET_SIZE - letter % ALPHABET_SIZE) % ALPHABET_SIZE
+}
+
+/// Core logic for the encryption/decryption process.
+fn encipher(text: &str, key: &str) -> Result<String, BeaufortError> {
+    let key: Vec<u8> = key
+        .bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|b| b.to_ascii_uppercase() - b'A')
+        .collect();
+    if key.is_empty() {
+        return Err(BeaufortError::EmptyKey);
+    }
+
+    let mut key_stream = key.iter().cycle();
+    Ok(text
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphabetic() {
+                let k = *key_stream.next().unwrap_or(&0);
+                let letter = c.to_ascii_uppercase() as u8 - b'A';
+                (substitute(letter, k) + b'A') as char
+            } else {
+                c
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beaufort_encrypt() {
+        assert_eq!(
+            encrypt("DEFENDTHEEASTWALLOFTHECASTLE", "FORTIFICATION").unwrap(),
+            "CKMPVCPVWPIWUJOGIUAPVWRIWUUK"
+        );
+        assert_eq!(encrypt("Hello, World", "key").unwrap(), "DANZQ, CWNNH");
+    }
+
+    #[test]
+    fn test_beaufort_reciprocal() {
+        let ciphertext = encrypt("defend the east wall", "FORTIFICATION").unwrap();
+        assert_eq!(
+            decrypt(&ciphertext, "FORTIFICATION").unwrap(),
+            "DEFEND THE EAST WALL"
+        );
+    }
+
+    #[test]
+    fn test_beaufort_traced() {
+        let (ciphertext, steps) = encrypt_traced("He, W", "key").unwrap();
+        assert_eq!(ciphertext, "DA, C");
+        assert_eq!(steps[0], Step::new('H', 'D', "subtract").detail("key", 'K'));
+        assert_eq!(steps[2], Step::new(',', ',', "keep"));
+        // The key does not advance on non-letters.
+        assert_eq!(steps[4], Step::new('W', 'C', "subtract").detail("key", 'Y'));
+    }
+
+    #[test]
+    fn test_beaufort_empty_key() {
+        assert_eq!(encrypt("HELLO", "123"), Err(BeaufortError::EmptyKey));
+    }
+}
@@ -1,13 +1,36 @@
 //! An implementation of the Caesar cipher.
 
+use crate::cipher::{Cipher, CipherError};
+use crate::prelude::*;
+use crate::trace::Step;
+
 /// Modes of operation for the Caesar cipher.
 ///
 /// The two modes of operation are `Encrypt` for encryption and `Decrypt` for decryption.
+#[derive(Clone, Copy)]
 enum Mode {
     Encrypt,
     Decrypt,
 }
 
+/// The key of a Caesar cipher, for use through the [`Cipher`] trait.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Caesar {
+    /// The shift value (number of positions each letter is moved in the alphabet).
+    pub shift: i32,
+}
+
+impl Cipher for Caesar {
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        Ok(encrypt(plaintext, self.shift))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        Ok(decrypt(ciphertext, self.shift))
+    }
+}
+
 /// Encrypts a given plaintext string using the Caesar cipher.
 ///
 /// # Arguments
@@ -50,17 +73,65 @@ pub fn decrypt(ciphertext: &str, key: i32) -> String {
 ///
 /// The processed version of the input string based on the mode.
 fn encipher(mode: Mode, data: &str, key: i32) -> String {
+    data.chars().map(|c| transform(c, &mode, key)).collect()
+}
+
+/// Encrypts a given plaintext string using the Caesar cipher, recording every step.
+///
+/// # Arguments
+///
+/// * `plaintext` - The input string to be encrypted.
+/// * `key` - The shift value (number of positions each letter is moved in the alphabet).
+///
+/// # Returns
+///
+/// The encrypted version of the input string, and one step per character.
+pub fn encrypt_traced(plaintext: &str, key: i32) -> (String, Vec<Step>) {
+    encipher_traced(Mode::Encrypt, plaintext, key)
+}
+
+/// Decrypts a given ciphertext string using the Caesar cipher, recording every step.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The encrypted input string to be decrypted.
+/// * `key` - The shift value (number of positions each letter is moved in the alphabet).
+///
+/// # Returns
+///
+/// The decrypted version of the input string, and one step per character.
+pub fn decrypt_traced(ciphertext: &str, key: i32) -> (String, Vec<Step>) {
+    encipher_traced(Mode::Decrypt, ciphertext, key)
+}
+
+/// Encrypts or decrypts a given string, recording the shift applied to every letter.
+fn encipher_traced(mode: Mode, data: &str, key: i32) -> (String, Vec<Step>) {
+    let shift = match mode {
+        Mode::Encrypt => key.rem_euclid(26),
+        Mode::Decrypt => (-key).rem_euclid(26),
+    };
     data.chars()
         .map(|c| {
-            if c.is_ascii_uppercase() {
-                process_char(c, &mode, key, 'A')
-            } else if c.is_ascii_lowercase() {
-                process_char(c, &mode, key, 'a')
+            let output = transform(c, &mode, key);
+            let step = if c.is_ascii_alphabetic() {
+                Step::new(c, output, "shift").detail("shift", shift)
             } else {
-                c
-            }
+                Step::new(c, output, "keep")
+            };
+            (output, step)
         })
-        .collect()
+        .unzip()
+}
+
+/// Transforms a character, leaving the non-alphabetic characters unchanged.
+fn transform(c: char, mode: &Mode, key: i32) -> char {
+    if c.is_ascii_uppercase() {
+        process_char(c, mode, key, 'A')
+    } else if c.is_ascii_lowercase() {
+        process_char(c, mode, key, 'a')
+    } else {
+        c
+    }
 }
 
 /// Transforms a character based on the mode and key.
@@ -124,4 +195,20 @@ mod tests {
         let decrypted = decrypt(&encrypted, 3);
         assert_eq!(decrypted, plaintext);
     }
+
+    #[test]
+    fn test_caesar_traced() {
+        let (ciphertext, steps) = encrypt_traced("Hi!", 3);
+        assert_eq!(ciphertext, encrypt("Hi!", 3));
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[0], Step::new('H', 'K', "shift").detail("shift", 3));
+        assert_eq!(steps[2], Step::new('!', '!', "keep"));
+
+        let (plaintext, steps) = decrypt_traced("Khoor", 3);
+        assert_eq!(plaintext, "Hello");
+        assert_eq!(
+            steps[0].details,
+            vec![("shift".to_string(), "23".to_string())]
+        );
+    }
 }
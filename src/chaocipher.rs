@@ -0,0 +1,275 @@
+//! An implementation of the Chaocipher.
+//!
+//! The Chaocipher was invented by John F. Byrne in 1918, and its algorithm was
+//! only made public in 2010. It uses two alphabets: the left one for the
+//! ciphertext and the right one for the plaintext. A plaintext letter is found
+//! in the right alphabet and replaced with the letter at the same position in
+//! the left alphabet.
+//!
+//! Unlike in static ciphers such as [`crate::vigenere`] or [`crate::porta`], both
+//! alphabets are permuted after every letter. Position 0 is called the *zenith*
+//! and position 13 the *nadir*:
+//!
+//! * the left alphabet is rotated to bring the ciphertext letter to the zenith,
+//!   then the letter at position 1 is moved to the nadir;
+//! * the right alphabet is rotated to bring the letter following the plaintext
+//!   letter to the zenith, then the letter at position 2 is moved to the nadir.
+
+use crate::keyed_alphabet::KeyedAlphabet;
+use crate::prelude::*;
+use crate::trace::Step;
+use thiserror::Error;
+
+const ALPHABET_SIZE: usize = 26;
+
+/// The position of the nadir, opposite the zenith (position 0).
+const NADIR: usize = ALPHABET_SIZE / 2;
+
+/// Error type for the Chaocipher.
+#[derive(Error, Debug, PartialEq)]
+pub enum ChaocipherError {
+    #[error("Alphabets must be permutations of the 26 letters")]
+    InvalidAlphabet,
+}
+
+/// Represents the mode of operation: encryption or decryption.
+#[derive(Clone, Copy)]
+enum Mode {
+    Encrypt,
+    Decrypt,
+}
+
+/// A Chaocipher key: the starting left and right alphabets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chaocipher {
+    left: KeyedAlphabet,
+    right: KeyedAlphabet,
+}
+
+impl Chaocipher {
+    /// Creates a Chaocipher from its starting alphabets.
+    ///
+    /// # Arguments
+    ///
+    /// * `left` - The starting left (ciphertext) alphabet, a permutation of the 26 letters.
+    /// * `right` - The starting right (plaintext) alphabet, a permutation of the 26 letters.
+    ///
+    /// # Returns
+    ///
+    /// The cipher, or an error if an alphabet is not a permutation of the letters.
+    pub fn new(left: &str, right: &str) -> Result<Self, ChaocipherError> {
+        let alphabet = |letters| {
+            KeyedAlphabet::from_permutation(letters).ok_or(ChaocipherError::InvalidAlphabet)
+        };
+        Ok(Chaocipher {
+            left: alphabet(left)?,
+            right: alphabet(right)?,
+        })
+    }
+
+    /// Creates a Chaocipher whose starting alphabets are keyed with keywords.
+    ///
+    /// # Arguments
+    ///
+    /// * `left_keyword` - The keyword of the left (ciphertext) alphabet.
+    /// * `right_keyword` - The keyword of the right (plaintext) alphabet.
+    pub fn keyed(left_keyword: &str, right_keyword: &str) -> Self {
+        Chaocipher {
+            left: KeyedAlphabet::new(left_keyword),
+            right: KeyedAlphabet::new(right_keyword),
+        }
+    }
+
+    /// Returns the starting left (ciphertext) alphabet.
+    pub fn left(&self) -> &KeyedAlphabet {
+        &self.left
+    }
+
+    /// Returns the starting right (plaintext) alphabet.
+    pub fn right(&self) -> &KeyedAlphabet {
+        &self.right
+    }
+
+    /// Encrypts the provided plaintext.
+    ///
+    /// Non-alphabetic characters are left unchanged and do not permute the alphabets.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The text to be encrypted.
+    ///
+    /// # Returns
+    ///
+    /// The encrypted text, in uppercase.
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        self.encipher(plaintext, Mode::Encrypt, None)
+    }
+
+    /// Decrypts the provided ciphertext.
+    ///
+    /// Non-alphabetic characters are left unchanged and do not permute the alphabets.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The text to be decrypted.
+    ///
+    /// # Returns
+    ///
+    /// The decrypted text, in uppercase.
+    pub fn decrypt(&self, ciphertext: &str) -> String {
+        self.encipher(ciphertext, Mode::Decrypt, None)
+    }
+
+    /// Encrypts the provided plaintext, recording every step.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The text to be encrypted.
+    ///
+    /// # Returns
+    ///
+    /// The encrypted text, and one step per letter with the position of the
+    /// letter and both alphabets before they are permuted.
+    pub fn encrypt_traced(&self, plaintext: &str) -> (String, Vec<Step>) {
+        let mut steps = Vec::new();
+        let ciphertext = self.encipher(plaintext, Mode::Encrypt, Some(&mut steps));
+        (ciphertext, steps)
+    }
+
+    /// Decrypts the provided ciphertext, recording every step.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The text to be decrypted.
+    ///
+    /// # Returns
+    ///
+    /// The decrypted text, and one step per letter with the position of the
+    /// letter and both alphabets before they are permuted.
+    pub fn decrypt_traced(&self, ciphertext: &str) -> (String, Vec<Step>) {
+        let mut steps = Vec::new();
+        let plaintext = self.encipher(ciphertext, Mode::Decrypt, Some(&mut steps));
+        (plaintext, steps)
+    }
+
+    /// Core logic for the encryption/decryption process, recording the steps in `steps` if given.
+    fn encipher(&self, text: &str, mode: Mode, mut steps: Option<&mut Vec<Step>>) -> String {
+        let mut left: Vec<char> = self.left.chars().collect();
+        let mut right: Vec<char> = self.right.chars().collect();
+
+        text.chars()
+            .map(|c| {
+                if !c.is_ascii_alphabetic() {
+                    return c;
+                }
+
+                let c = c.to_ascii_uppercase();
+                let (source, target) = match mode {
+                    Mode::Encrypt => (&right, &left),
+                    Mode::Decrypt => (&left, &right),
+                };
+                let idx = source.iter().position(|&l| l == c).unwrap_or_default();
+                let output = target[idx];
+                if let Some(steps) = steps.as_deref_mut() {
+                    steps.push(
+                        Step::new(c, output, "substitute")
+                            .detail("position", idx)
+                            .detail("left", left.iter().collect::<String>())
+                            .detail("right", right.iter().collect::<String>()),
+                    );
+                }
+
+                permute(&mut left, idx, 1);
+                permute(&mut right, idx + 1, 2);
+                output
+            })
+            .collect()
+    }
+}
+
+/// Permutes an alphabet: brings the letter at `zenith` to position 0, then
+/// moves the letter at position `extract` to the nadir.
+fn permute(alphabet: &mut [char], zenith: usize, extract: usize) {
+    alphabet.rotate_left(zenith % ALPHABET_SIZE);
+    alphabet[extract..=NADIR].rotate_left(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exhibit() -> Chaocipher {
+        Chaocipher::new("HXUCZVAMDSLKPEFJRIGTWOBNYQ", "PTLNBQDEOYSFAVZKGJRIHWXUMC").unwrap()
+    }
+
+    #[test]
+    fn test_chaocipher_encrypt() {
+        assert_eq!(
+            exhibit().encrypt("WELLDONEISBETTERTHANWELLSAID"),
+            "OAHQHCNYNXTSZJRRHJBYHQKSOUJY"
+        );
+    }
+
+    #[test]
+    fn test_chaocipher_decrypt() {
+        assert_eq!(
+            exhibit().decrypt("OAHQHCNYNXTSZJRRHJBYHQKSOUJY"),
+            "WELLDONEISBETTERTHANWELLSAID"
+        );
+    }
+
+    #[test]
+    fn test_chaocipher_traced() {
+        let (ciphertext, steps) = exhibit().encrypt_traced("WELL");
+        assert_eq!(ciphertext, "OAHQ");
+        assert_eq!(
+            steps[0],
+            Step::new('W', 'O', "substitute")
+                .detail("position", 21)
+                .detail("left", "HXUCZVAMDSLKPEFJRIGTWOBNYQ")
+                .detail("right", "PTLNBQDEOYSFAVZKGJRIHWXUMC")
+        );
+        assert_eq!(exhibit().decrypt_traced(&ciphertext).0, "WELL");
+    }
+
+    #[test]
+    fn test_chaocipher_keyed() {
+        let chaocipher = Chaocipher::keyed("BYRNE", "SILENTYEARS");
+        assert_eq!(chaocipher.left().to_string(), "BYRNEACDFGHIJKLMOPQSTUVWXZ");
+
+        let ciphertext = chaocipher.encrypt("Attack at dawn!");
+        assert_eq!(ciphertext.len(), 15);
+        assert!(ciphertext.ends_with('!'));
+        assert_eq!(chaocipher.decrypt(&ciphertext), "ATTACK AT DAWN!");
+
+        // Repeated letters do not give repeated ciphertext letters.
+        let ciphertext = chaocipher.encrypt("AAAAAAAA");
+        assert_ne!(ciphertext, "A".repeat(8));
+        assert_ne!(&ciphertext[..4], &ciphertext[4..]);
+    }
+
+    #[test]
+    fn test_chaocipher_invalid() {
+        assert_eq!(
+            Chaocipher::new("ABC", "PTLNBQDEOYSFAVZKGJRIHWXUMC"),
+            Err(ChaocipherError::InvalidAlphabet)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_chaocipher_serde() {
+        let chaocipher = Chaocipher::keyed("LEFT", "RIGHT");
+        let json = serde_json::to_string(&chaocipher).unwrap();
+        assert_eq!(
+            json,
+            r#"{"left":"LEFTABCDGHIJKMNOPQRSUVWXYZ","right":"RIGHTABCDEFJKLMNOPQSUVWXYZ"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Chaocipher>(&json).unwrap(),
+            chaocipher
+        );
+        assert!(serde_json::from_str::<Chaocipher>(r#"{"left":"ABC","right":"ABC"}"#).is_err());
+    }
+}
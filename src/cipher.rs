@@ -0,0 +1,278 @@
+//! A common interface to the ciphers, and cipher configurations as data.
+//!
+//! Most ciphers of the crate are free functions taking their key as arguments.
+//! The [`Cipher`] trait gives them a common interface, so that a configured
+//! cipher can be stored, passed around and used without knowing which cipher
+//! it is. It is implemented by the key types of the ciphers, such as
+//! [`crate::caesar::Caesar`] or the settings of an Enigma machine,
+//! [`crate::enigma::EnigmaBuilder`].
+//!
+//! A [`CipherSpec`] describes a configured cipher as plain data. With the
+//! `serde` feature, it can be stored and loaded in any format supported by
+//! serde, as an object tagged with the name of the cipher:
+//!
+//! ```json
+//! {"cipher": "vigenere", "key": "LEMON"}
+//! ```
+//!
+//! and is then instantiated into a working cipher with [`CipherSpec::build`].
+//!
+//! Ciphers do not all work on the same kind of text: a Polybius square turns
+//! letters into digits, and a one-time pad turns text into bytes. Each cipher
+//! tells which [`TextKind`] it accepts and produces, so that ciphers can be
+//! chained safely, see [`crate::pipeline`].
+
+use crate::affine::Affine;
+use crate::caesar::Caesar;
+use crate::columnar::{Columnar, ColumnarError};
+use crate::enigma::{EnigmaBuilder, EnigmaError};
+#[cfg(feature = "std")]
+use crate::otp::PadRef;
+use crate::pipeline::DigitsToLetters;
+use crate::playfair::{Playfair, PlayfairError};
+use crate::polybius_square::PolybiusSquare;
+use crate::prelude::*;
+use crate::vigenere::{Vigenere, VigenereError};
+use core::fmt;
+use thiserror::Error;
+
+/// Error type for the ciphers used through the [`Cipher`] trait.
+#[derive(Error, Debug, PartialEq)]
+pub enum CipherError {
+    #[error("Invalid Affine key: {0}")]
+    Affine(&'static str),
+    #[error(transparent)]
+    Vigenere(#[from] VigenereError),
+    #[error(transparent)]
+    Playfair(#[from] PlayfairError),
+    #[error(transparent)]
+    Enigma(#[from] EnigmaError),
+    #[error(transparent)]
+    Columnar(#[from] ColumnarError),
+    #[error("One-time pad error: {0}")]
+    Pad(String),
+    #[error("Invalid text: {0}")]
+    InvalidText(&'static str),
+}
+
+/// The kinds of text read and written by the ciphers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum TextKind {
+    /// Text whose letters are enciphered, e.g. by the substitution ciphers.
+    Letters,
+    /// Digits, e.g. the coordinates written by a Polybius square.
+    Digits,
+    /// Bytes written in hexadecimal, e.g. by a one-time pad.
+    Hex,
+}
+
+impl fmt::Display for TextKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextKind::Letters => write!(f, "letters"),
+            TextKind::Digits => write!(f, "digits"),
+            TextKind::Hex => write!(f, "hexadecimal"),
+        }
+    }
+}
+
+/// A configured cipher, able to encrypt and decrypt text.
+pub trait Cipher {
+    /// Encrypts a text.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The text to be encrypted.
+    ///
+    /// # Returns
+    ///
+    /// The encrypted text, or an error if the key or the text is invalid.
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError>;
+
+    /// Decrypts a text.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The text to be decrypted.
+    ///
+    /// # Returns
+    ///
+    /// The decrypted text, or an error if the key or the text is invalid.
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError>;
+
+    /// Returns the kind of text written by the cipher, given the kind of text it reads.
+    ///
+    /// Decryption goes the other way: the cipher reads the kind of text it
+    /// writes when encrypting. The default is for ciphers working on letters.
+    ///
+    /// # Returns
+    ///
+    /// The kind of the ciphertext, or `None` if the cipher cannot read `input`.
+    fn output_kind(&self, input: TextKind) -> Option<TextKind> {
+        (input == TextKind::Letters).then_some(TextKind::Letters)
+    }
+}
+
+/// The configuration of a cipher, as plain data.
+///
+/// With the `serde` feature, a spec is serialized as an object whose `cipher`
+/// member names the cipher, next to the members of its key type, e.g.
+/// `{"cipher": "caesar", "shift": 3}` or `{"cipher": "affine", "a": 5, "b": 8}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "cipher", rename_all = "lowercase")
+)]
+pub enum CipherSpec {
+    Caesar(Caesar),
+    Affine(Affine),
+    Vigenere(Vigenere),
+    Playfair(Playfair),
+    Polybius(PolybiusSquare),
+    Columnar(Columnar),
+    Enigma(EnigmaBuilder),
+    /// A one-time pad. Each message must be encrypted with a new spec, whose
+    /// offset is past the bytes already used, see [`PadRef::next`].
+    #[cfg(feature = "std")]
+    Otp(PadRef),
+    /// The adapter of digits to letters, see [`DigitsToLetters`].
+    #[cfg_attr(feature = "serde", serde(rename = "to_letters"))]
+    ToLetters,
+}
+
+impl CipherSpec {
+    /// Validates the configuration and instantiates the cipher.
+    ///
+    /// # Returns
+    ///
+    /// The cipher, or an error if its key is invalid.
+    pub fn build(&self) -> Result<Box<dyn Cipher>, CipherError> {
+        Ok(match self {
+            CipherSpec::Caesar(caesar) => Box::new(*caesar),
+            CipherSpec::Affine(affine) => Box::new(Affine::new(affine.a, affine.b)?),
+            CipherSpec::Vigenere(vigenere) => Box::new(Vigenere::new(&vigenere.key)?),
+            CipherSpec::Playfair(playfair) => Box::new(playfair.clone()),
+            CipherSpec::Polybius(polybius) => Box::new(polybius.clone()),
+            CipherSpec::Columnar(columnar) => Box::new(Columnar::new(&columnar.key)?),
+            CipherSpec::Enigma(settings) => {
+                settings.clone().build()?;
+                Box::new(settings.clone())
+            }
+            #[cfg(feature = "std")]
+            CipherSpec::Otp(pad) => Box::new(pad.clone()),
+            CipherSpec::ToLetters => Box::new(DigitsToLetters),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enigma::{Model, Reflector, Rotor};
+
+    #[test]
+    fn test_cipher_spec_build() {
+        let caesar = CipherSpec::Caesar(Caesar { shift: 3 }).build().unwrap();
+        assert_eq!(caesar.encrypt("Hello!").unwrap(), "Khoor!");
+        assert_eq!(caesar.decrypt("Khoor!").unwrap(), "Hello!");
+
+        let vigenere = CipherSpec::Vigenere(Vigenere {
+            key: "LEMON".to_string(),
+        });
+        assert_eq!(
+            vigenere.build().unwrap().encrypt("Attack at dawn").unwrap(),
+            "LXFOPVEFRNHR"
+        );
+
+        let affine = CipherSpec::Affine(Affine { a: 5, b: 8 }).build().unwrap();
+        assert_eq!(affine.encrypt("Hello, World").unwrap(), "Rclla, Oaplx");
+        assert_eq!(affine.decrypt("Rclla, Oaplx").unwrap(), "Hello, World");
+
+        let playfair = CipherSpec::Playfair(Playfair::new("PLAYFAIR EXAMPLE"));
+        assert_eq!(
+            playfair.build().unwrap().encrypt("Hide the gold").unwrap(),
+            crate::playfair::encrypt("PLAYFAIR EXAMPLE", "Hide the gold").unwrap()
+        );
+
+        let enigma = CipherSpec::Enigma(
+            EnigmaBuilder::new(Model::EnigmaI)
+                .rotors(&[Rotor::I, Rotor::II, Rotor::III])
+                .reflector(Reflector::B),
+        );
+        // Every message starts from the configured positions.
+        let machine = enigma.build().unwrap();
+        assert_eq!(machine.encrypt("AAAAA").unwrap(), "BDZGO");
+        assert_eq!(machine.decrypt("BDZGO").unwrap(), "AAAAA");
+
+        let affine = CipherSpec::Affine(Affine { a: 13, b: 1 });
+        assert!(matches!(affine.build(), Err(CipherError::Affine(_))));
+        let vigenere = CipherSpec::Vigenere(Vigenere {
+            key: "123".to_string(),
+        });
+        assert!(matches!(
+            vigenere.build(),
+            Err(CipherError::Vigenere(VigenereError::EmptyKey))
+        ));
+        let enigma = CipherSpec::Enigma(EnigmaBuilder::new(Model::M4).reflector(Reflector::B));
+        assert!(matches!(enigma.build(), Err(CipherError::Enigma(_))));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_cipher_spec_serde() {
+        let spec: CipherSpec = serde_json::from_str(r#"{"cipher": "caesar", "shift": 3}"#).unwrap();
+        assert_eq!(spec, CipherSpec::Caesar(Caesar { shift: 3 }));
+        assert_eq!(
+            serde_json::to_string(&CipherSpec::Affine(Affine { a: 5, b: 8 })).unwrap(),
+            r#"{"cipher":"affine","a":5,"b":8}"#
+        );
+
+        let spec: CipherSpec = toml::from_str(
+            r#"
+            cipher = "enigma"
+            model = "EnigmaI"
+            rotors = ["II", "IV", "V"]
+            reflector = "B"
+            ring_settings = "02 21 12"
+            positions = "BLA"
+            plugboard = "AV BS CG DL FU HZ IN KM OW RX"
+            "#,
+        )
+        .unwrap();
+        let machine = spec.build().unwrap();
+        assert_eq!(
+            machine.decrypt("EDPUD NRGYS ZRCXN UYTPO MRMBO").unwrap(),
+            "AUFKLXABTEILUNGXVONXKURTI"
+        );
+        // Missing settings take their defaults.
+        let spec: CipherSpec = toml::from_str("cipher = \"enigma\"\nmodel = \"EnigmaI\"").unwrap();
+        assert_eq!(spec, CipherSpec::Enigma(EnigmaBuilder::new(Model::EnigmaI)));
+        let typo = "cipher = \"enigma\"\nmodel = \"EnigmaI\"\nring_setting = \"02 21 12\"";
+        assert!(toml::from_str::<CipherSpec>(typo).is_err());
+
+        let spec = CipherSpec::Playfair(Playfair::new("PLAYFAIR EXAMPLE"));
+        let json = serde_json::to_string(&spec).unwrap();
+        assert_eq!(
+            json,
+            r#"{"cipher":"playfair","square":{"letters":"PLAYFIREXMBCDGHKNOQSTUVWZ","alphabet":"MergeJ"}}"#
+        );
+        assert_eq!(serde_json::from_str::<CipherSpec>(&json).unwrap(), spec);
+        let invalid = r#"{"cipher":"playfair","square":{"letters":"PLAYFAIR"}}"#;
+        assert!(serde_json::from_str::<CipherSpec>(invalid).is_err());
+    }
+
+    #[cfg(all(feature = "serde", feature = "std"))]
+    #[test]
+    fn test_cipher_spec_serde_otp() {
+        let spec: CipherSpec =
+            serde_json::from_str(r#"{"cipher": "otp", "path": "pads/0042.bin", "offset": 512}"#)
+                .unwrap();
+        assert_eq!(spec, CipherSpec::Otp(PadRef::new("pads/0042.bin", 512)));
+    }
+}
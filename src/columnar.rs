@@ -0,0 +1,217 @@
+//! An implementation of the columnar transposition cipher.
+//!
+//! The text is written in rows under a keyword, then read column by column, in
+//! the alphabetical order of the letters of the keyword. With the keyword
+//! `ZEBRAS`, the columns are numbered 6 3 2 4 1 5 and read from 1 to 6:
+//!
+//! ```text
+//! Z E B R A S
+//! 6 3 2 4 1 5
+//! -----------
+//! W E A R E D
+//! I S C O V E
+//! R E D F L E
+//! E A T O N C
+//! E
+//! ```
+//!
+//! gives `EVLNACDTESEAROFODEECWIREE`. The last row may be incomplete, and the
+//! letters keep their frequencies, which is how transpositions are recognized.
+//! A transposition does not care what it moves, so it can also be applied to
+//! the digits of a Polybius square or of a straddling checkerboard.
+
+use crate::cipher::{Cipher, CipherError, TextKind};
+use crate::prelude::*;
+use thiserror::Error;
+
+/// Error type for the columnar transposition.
+#[derive(Error, Debug, PartialEq)]
+pub enum ColumnarError {
+    #[error("Key must contain at least one letter or digit")]
+    EmptyKey,
+}
+
+/// The key of a columnar transposition, for use through the [`Cipher`] trait.
+///
+/// Through the trait, any text can be transposed, and the kind of text is kept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Columnar {
+    /// The keyword. Only its letters and digits are used.
+    pub key: String,
+}
+
+impl Columnar {
+    /// Creates a columnar transposition key, checking that it has letters or digits.
+    ///
+    /// # Returns
+    ///
+    /// The key, or an error if it has no letters or digits.
+    pub fn new(key: &str) -> Result<Self, ColumnarError> {
+        ranks(key)?;
+        Ok(Columnar {
+            key: key.to_string(),
+        })
+    }
+}
+
+impl Cipher for Columnar {
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        Ok(encrypt(plaintext, &self.key)?)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        Ok(decrypt(ciphertext, &self.key)?)
+    }
+
+    fn output_kind(&self, input: TextKind) -> Option<TextKind> {
+        Some(input)
+    }
+}
+
+/// Encrypts a text using the columnar transposition.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted. Only its letters and digits are
+///   kept, letters in uppercase.
+/// * `key` - The keyword. Only its letters and digits are used, without regard to case.
+///
+/// # Returns
+///
+/// The encrypted text, or an error if the key is empty.
+pub fn encrypt(plaintext: &str, key: &str) -> Result<String, ColumnarError> {
+    let key = ranks(key)?;
+    Ok(transpose(&symbols(plaintext), &key).into_iter().collect())
+}
+
+/// Decrypts a text encrypted with the columnar transposition.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted. Only its letters and digits are
+///   kept, letters in uppercase.
+/// * `key` - The keyword. Only its letters and digits are used, without regard to case.
+///
+/// # Returns
+///
+/// The decrypted text, or an error if the key is empty.
+pub fn decrypt(ciphertext: &str, key: &str) -> Result<String, ColumnarError> {
+    let key = ranks(key)?;
+    Ok(untranspose(&symbols(ciphertext), &key)
+        .into_iter()
+        .collect())
+}
+
+/// Writes symbols in rows under the key and reads the columns in key order.
+///
+/// # Arguments
+///
+/// * `symbols` - The symbols to transpose.
+/// * `key` - The rank of every column, starting at 1, as returned by [`sequentialize`].
+pub fn transpose<T: Copy>(symbols: &[T], key: &[usize]) -> Vec<T> {
+    let width = key.len();
+    (1..=width)
+        .flat_map(|rank| {
+            let col = key.iter().position(|&r| r == rank).unwrap_or_default();
+            symbols.iter().skip(col).step_by(width).copied()
+        })
+        .collect()
+}
+
+/// Reverses [`transpose`].
+///
+/// # Arguments
+///
+/// * `symbols` - The transposed symbols.
+/// * `key` - The rank of every column, starting at 1, as returned by [`sequentialize`].
+pub fn untranspose<T: Copy + Default>(symbols: &[T], key: &[usize]) -> Vec<T> {
+    let width = key.len();
+    let mut table = vec![T::default(); symbols.len()];
+    let mut symbols = symbols.iter();
+
+    for rank in 1..=width {
+        let col = key.iter().position(|&r| r == rank).unwrap_or_default();
+        for idx in (col..table.len()).step_by(width) {
+            table[idx] = symbols.next().copied().unwrap_or_default();
+        }
+    }
+    table
+}
+
+/// Numbers the symbols in their natural order, starting at 1.
+///
+/// Equal symbols are numbered from left to right. For example, the letters of
+/// `HELLO` are numbered `2 1 3 4 5`.
+///
+/// # Arguments
+///
+/// * `symbols` - The symbols to sequentialize.
+///
+/// # Returns
+///
+/// The rank of each symbol.
+pub fn sequentialize<T: Ord>(symbols: &[T]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..symbols.len()).collect();
+    order.sort_by(|&a, &b| symbols[a].cmp(&symbols[b]));
+
+    let mut ranks = vec![0; symbols.len()];
+    for (rank, idx) in order.into_iter().enumerate() {
+        ranks[idx] = rank + 1;
+    }
+    ranks
+}
+
+/// Returns the column ranks of a keyword.
+fn ranks(key: &str) -> Result<Vec<usize>, ColumnarError> {
+    let key = symbols(key);
+    if key.is_empty() {
+        return Err(ColumnarError::EmptyKey);
+    }
+    Ok(sequentialize(&key))
+}
+
+/// Returns the letters, in uppercase, and digits of a text.
+fn symbols(text: &str) -> Vec<char> {
+    text.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columnar_encrypt_decrypt() {
+        let ciphertext = encrypt("We are discovered, flee at once!", "ZEBRAS").unwrap();
+        assert_eq!(ciphertext, "EVLNACDTESEAROFODEECWIREE");
+        assert_eq!(
+            decrypt(&ciphertext, "zebras").unwrap(),
+            "WEAREDISCOVEREDFLEEATONCE"
+        );
+
+        assert_eq!(encrypt("2315313134", "KEY").unwrap(), "3312534113");
+        assert_eq!(encrypt("text", "!!"), Err(ColumnarError::EmptyKey));
+    }
+
+    #[test]
+    fn test_columnar_sequentialize() {
+        assert_eq!(sequentialize(b"ZEBRAS"), vec![6, 3, 2, 4, 1, 5]);
+        assert_eq!(
+            sequentialize(b"HELLOWORLD"),
+            vec![3, 2, 4, 5, 7, 10, 8, 9, 6, 1]
+        );
+    }
+
+    #[test]
+    fn test_columnar_transpose() {
+        let key = sequentialize(b"ZEBRAS");
+        let digits: Vec<u8> = (0..23).map(|d| d % 10).collect();
+        for len in 0..digits.len() {
+            let digits = &digits[..len];
+            assert_eq!(untranspose(&transpose(digits, &key), &key), digits);
+        }
+    }
+}
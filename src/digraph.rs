@@ -0,0 +1,221 @@
+//! Text preparation shared by the digraphic ciphers (Playfair, Four-square and Two-square).
+//!
+//! Digraphic ciphers encrypt pairs of letters at a time, so the input text has
+//! to be normalized to the letters of the key square and split into digraphs
+//! before it can be enciphered. The rules used for doing so are described by
+//! [`DigraphRules`].
+
+use crate::key_square::SquareAlphabet;
+use crate::prelude::*;
+use crate::trace::Step;
+use thiserror::Error;
+
+/// Error type for the digraphic cipher functions.
+#[derive(Error, Debug, PartialEq)]
+pub enum DigraphError {
+    #[error("Keyword and text cannot be empty")]
+    EmptyInput,
+    #[error("No valid characters in the input text")]
+    InvalidText,
+    #[error("Character not found in matrix")]
+    CharNotFound,
+}
+
+/// The rules used to turn free text into digraphs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DigraphRules {
+    /// The way the alphabet is fitted into the key square(s).
+    pub alphabet: SquareAlphabet,
+    /// The letter used to split doubled letters and to pad a trailing single letter.
+    pub filler: char,
+    /// Whether a digraph made of the same letter twice is split by inserting the filler.
+    pub split_doubles: bool,
+}
+
+impl DigraphRules {
+    /// The rules of the Playfair cipher: `J` is merged into `I`, and doubled letters are split with `X`.
+    pub const PLAYFAIR: DigraphRules = DigraphRules {
+        alphabet: SquareAlphabet::MergeJ,
+        filler: 'X',
+        split_doubles: true,
+    };
+
+    /// The rules of the Four-square cipher: `Q` is omitted, and doubled letters are left as they are.
+    pub const FOUR_SQUARE: DigraphRules = DigraphRules {
+        alphabet: SquareAlphabet::OmitQ,
+        filler: 'X',
+        split_doubles: false,
+    };
+
+    /// The rules of the Two-square cipher: `Q` is omitted, and doubled letters are left as they are.
+    pub const TWO_SQUARE: DigraphRules = DigraphRules::FOUR_SQUARE;
+}
+
+impl Default for DigraphRules {
+    fn default() -> Self {
+        DigraphRules::PLAYFAIR
+    }
+}
+
+/// Prepares the input text for a digraphic cipher.
+///
+/// # Arguments
+///
+/// * `text` - The input text to be prepared.
+/// * `rules` - The rules describing the alphabet of the key square(s).
+///
+/// # Returns
+///
+/// An uppercase string containing only letters of the key square's alphabet.
+pub fn prepare_text(text: &str, rules: &DigraphRules) -> String {
+    text.chars()
+        .filter_map(|c| rules.alphabet.normalize(c))
+        .collect()
+}
+
+/// Creates digraphs from the prepared input text.
+///
+/// # Arguments
+///
+/// * `text` - The prepared text to be converted into digraphs.
+/// * `rules` - The rules describing the filler letter and how doubled letters are handled.
+///
+/// # Returns
+///
+/// A vector of digraph tuples or an error if there are no valid characters in the input text.
+pub fn create_digraphs(
+    text: &str,
+    rules: &DigraphRules,
+) -> Result<Vec<(char, char)>, DigraphError> {
+    let mut digraphs = Vec::new();
+    let chars: Vec<_> = text.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let first = chars[i];
+        let second = if i + 1 < chars.len() && (!rules.split_doubles || chars[i + 1] != first) {
+            i += 1;
+            chars[i]
+        } else {
+            rules.filler
+        };
+
+        digraphs.push((first, second));
+        i += 1;
+    }
+
+    if digraphs.is_empty() {
+        Err(DigraphError::InvalidText)
+    } else {
+        Ok(digraphs)
+    }
+}
+
+/// The substitution of a digraph: the rule applied, and the grid coordinates
+/// (row and column) of the input and output letters in their squares.
+pub(crate) struct Substitution {
+    pub rule: &'static str,
+    pub input: (char, char),
+    pub output: (char, char),
+    pub from: [(usize, usize); 2],
+    pub to: [(usize, usize); 2],
+}
+
+impl Substitution {
+    /// Returns the trace step of the substitution, with the coordinates counted from 1.
+    pub fn step(&self) -> Step {
+        let coordinates = |[(a, b), (c, d)]: [(usize, usize); 2]| {
+            format!("({},{}) ({},{})", a + 1, b + 1, c + 1, d + 1)
+        };
+        Step::new(
+            format!("{}{}", self.input.0, self.input.1),
+            format!("{}{}", self.output.0, self.output.1),
+            self.rule,
+        )
+        .detail("from", coordinates(self.from))
+        .detail("to", coordinates(self.to))
+    }
+}
+
+/// Returns the output of the substitutions, and their trace steps if `traced` is set.
+pub(crate) fn collect_output(
+    substitutions: impl Iterator<Item = Result<Substitution, DigraphError>>,
+    traced: bool,
+) -> Result<(String, Vec<Step>), DigraphError> {
+    let mut output = String::new();
+    let mut steps = Vec::new();
+    for substitution in substitutions {
+        let substitution = substitution?;
+        output.push(substitution.output.0);
+        output.push(substitution.output.1);
+        if traced {
+            steps.push(substitution.step());
+        }
+    }
+    Ok((output, steps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digraph_prepare_text() {
+        assert_eq!(
+            prepare_text("Jump, quick!", &DigraphRules::PLAYFAIR),
+            "IUMPQUICK"
+        );
+        assert_eq!(
+            prepare_text("Jump, quick!", &DigraphRules::FOUR_SQUARE),
+            "JUMPUICK"
+        );
+    }
+
+    #[test]
+    fn test_digraph_create_digraphs_split_doubles() {
+        assert_eq!(
+            create_digraphs("TREESTUMP", &DigraphRules::PLAYFAIR),
+            Ok(vec![
+                ('T', 'R'),
+                ('E', 'X'),
+                ('E', 'S'),
+                ('T', 'U'),
+                ('M', 'P')
+            ])
+        );
+    }
+
+    #[test]
+    fn test_digraph_create_digraphs_keep_doubles() {
+        assert_eq!(
+            create_digraphs("TREESTUMP", &DigraphRules::FOUR_SQUARE),
+            Ok(vec![
+                ('T', 'R'),
+                ('E', 'E'),
+                ('S', 'T'),
+                ('U', 'M'),
+                ('P', 'X')
+            ])
+        );
+    }
+
+    #[test]
+    fn test_digraph_create_digraphs_custom_filler() {
+        let rules = DigraphRules {
+            filler: 'Q',
+            ..DigraphRules::PLAYFAIR
+        };
+        assert_eq!(
+            create_digraphs("BALLOON", &rules),
+            Ok(vec![('B', 'A'), ('L', 'Q'), ('L', 'O'), ('O', 'N')])
+        );
+    }
+
+    #[test]
+    fn test_digraph_create_digraphs_empty() {
+        assert_eq!(
+            create_digraphs("", &DigraphRules::PLAYFAIR),
+            Err(DigraphError::InvalidText)
+        );
+    }
+}
@@ -0,0 +1,921 @@
+//! A simulator of the Wehrmacht and Kriegsmarine Enigma machines.
+//!
+//! The Enigma I (army and air force), the M3 (navy) and the four-rotor M4
+//! (U-boats) are supported, with the rotors I to VIII, the thin Beta and Gamma
+//! rotors, the reflectors A, B and C and their thin variants, ring settings,
+//! the double-stepping anomaly of the middle rotor and the plugboard.
+//!
+//! Machines are configured through [`EnigmaBuilder`]:
+//!
+//! ```
+//! use cyphers::enigma::{Enigma, Model, Reflector, Rotor};
+//!
+//! let mut enigma = Enigma::builder(Model::EnigmaI)
+//!     .rotors(&[Rotor::I, Rotor::II, Rotor::III])
+//!     .reflector(Reflector::B)
+//!     .ring_settings("AAA")
+//!     .positions("AAA")
+//!     .build()
+//!     .unwrap();
+//!
+//! assert_eq!(enigma.encrypt("AAAAA"), "BDZGO");
+//! ```
+
+#[cfg(feature = "std")]
+pub mod crack;
+
+use crate::cipher::{Cipher, CipherError};
+use crate::prelude::*;
+use crate::trace::Step;
+use core::fmt;
+use thiserror::Error;
+
+const ALPHABET_SIZE: usize = 26;
+
+/// Error type for the configuration of an Enigma machine.
+#[derive(Error, Debug, PartialEq)]
+pub enum EnigmaError {
+    #[error("The {0} requires {1} rotors")]
+    RotorCount(Model, usize),
+    #[error("Rotor {0} cannot be used in the {1}")]
+    InvalidRotor(Rotor, Model),
+    #[error("Rotor {0} is used more than once")]
+    DuplicateRotor(Rotor),
+    #[error("Reflector {0} cannot be used in the {1}")]
+    InvalidReflector(Reflector, Model),
+    #[error("Expected {0} letters for the ring settings")]
+    InvalidRingSettings(usize),
+    #[error("Expected {0} letters for the rotor positions")]
+    InvalidPositions(usize),
+    #[error("Invalid plugboard pair: {0}")]
+    InvalidPlug(String),
+    #[error("Letter {0} is plugged more than once")]
+    DuplicatePlug(char),
+}
+
+/// The Enigma models supported by the simulator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Model {
+    /// The three-rotor Enigma I used by the army and the air force (rotors I to V).
+    EnigmaI,
+    /// The three-rotor naval Enigma M3 (rotors I to VIII).
+    M3,
+    /// The four-rotor naval Enigma M4, with a thin fourth rotor and a thin reflector.
+    M4,
+}
+
+impl Model {
+    /// Returns the number of rotors of the model.
+    pub fn rotor_count(self) -> usize {
+        match self {
+            Model::EnigmaI | Model::M3 => 3,
+            Model::M4 => 4,
+        }
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Model::EnigmaI => write!(f, "Enigma I"),
+            Model::M3 => write!(f, "Enigma M3"),
+            Model::M4 => write!(f, "Enigma M4"),
+        }
+    }
+}
+
+/// The rotors (Walzen) of the Enigma machines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rotor {
+    I,
+    II,
+    III,
+    IV,
+    V,
+    VI,
+    VII,
+    VIII,
+    /// The thin Beta rotor of the M4, only used in the leftmost position.
+    Beta,
+    /// The thin Gamma rotor of the M4, only used in the leftmost position.
+    Gamma,
+}
+
+impl Rotor {
+    /// The rotors that can be used in the three regular rotor positions.
+    pub const ALL: [Rotor; 8] = [
+        Rotor::I,
+        Rotor::II,
+        Rotor::III,
+        Rotor::IV,
+        Rotor::V,
+        Rotor::VI,
+        Rotor::VII,
+        Rotor::VIII,
+    ];
+
+    /// Returns the wiring of the rotor, i.e. the letters that `A` to `Z` are mapped to.
+    pub fn wiring(self) -> &'static str {
+        match self {
+            Rotor::I => "EKMFLGDQVZNTOWYHXUSPAIBRCJ",
+            Rotor::II => "AJDKSIRUXBLHWTMCQGZNPYFVOE",
+            Rotor::III => "BDFHJLCPRTXVZNYEIWGAKMUSQO",
+            Rotor::IV => "ESOVPZJAYQUIRHXLNFTGKDCMWB",
+            Rotor::V => "VZBRGITYUPSDNHLXAWMJQOFECK",
+            Rotor::VI => "JPGVOUMFYQBENHZRDKASXLICTW",
+            Rotor::VII => "NZJHGRCXMYSWBOUFAIVLPEKQDT",
+            Rotor::VIII => "FKQHTLXOCBJSPDZRAMEWNIUYGV",
+            Rotor::Beta => "LEYJVCNIXWPBQMDRTAKZGFUHOS",
+            Rotor::Gamma => "FSOKANUERHMBTIQCWLVJYPXDGZ",
+        }
+    }
+
+    /// Returns the positions (as shown in the window) at which the rotor makes its left neighbour step.
+    pub fn notches(self) -> &'static str {
+        match self {
+            Rotor::I => "Q",
+            Rotor::II => "E",
+            Rotor::III => "V",
+            Rotor::IV => "J",
+            Rotor::V => "Z",
+            Rotor::VI | Rotor::VII | Rotor::VIII => "ZM",
+            Rotor::Beta | Rotor::Gamma => "",
+        }
+    }
+
+    /// Returns whether this is one of the thin rotors of the M4.
+    pub fn is_thin(self) -> bool {
+        matches!(self, Rotor::Beta | Rotor::Gamma)
+    }
+}
+
+impl fmt::Display for Rotor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// The reflectors (Umkehrwalzen) of the Enigma machines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Reflector {
+    A,
+    B,
+    C,
+    /// The thin reflector B of the M4.
+    BThin,
+    /// The thin reflector C of the M4.
+    CThin,
+}
+
+impl Reflector {
+    /// Returns the wiring of the reflector, i.e. the letters that `A` to `Z` are mapped to.
+    pub fn wiring(self) -> &'static str {
+        match self {
+            Reflector::A => "EJMZALYXVBWFCRQUONTSPIKHGD",
+            Reflector::B => "YRUHQSLDPXNGOKMIEBFZCWVJAT",
+            Reflector::C => "FVPJIAOYEDRZXWGCTKUQSBNMHL",
+            Reflector::BThin => "ENKQAUYWJICOPBLMDXZVFTHRGS",
+            Reflector::CThin => "RDOBJNTKVEHMLFCWZAXGYIPSUQ",
+        }
+    }
+
+    /// Returns whether this is one of the thin reflectors of the M4.
+    pub fn is_thin(self) -> bool {
+        matches!(self, Reflector::BThin | Reflector::CThin)
+    }
+}
+
+impl fmt::Display for Reflector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reflector::BThin => write!(f, "B (thin)"),
+            Reflector::CThin => write!(f, "C (thin)"),
+            _ => fmt::Debug::fmt(self, f),
+        }
+    }
+}
+
+/// The plugboard (Steckerbrett), swapping pairs of letters before and after the rotors.
+///
+/// With the `serde` feature, the plugboard is serialized as its pairs (e.g. `"AV BS CG"`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct Plugboard {
+    wiring: [u8; ALPHABET_SIZE],
+}
+
+impl Plugboard {
+    /// Creates a plugboard from a list of letter pairs.
+    ///
+    /// # Arguments
+    ///
+    /// * `pairs` - The pairs of plugged letters, separated by spaces (e.g. `"AV BS CG"`).
+    ///
+    /// # Returns
+    ///
+    /// The plugboard, or an error if a pair is invalid or a letter is plugged twice.
+    pub fn new(pairs: &str) -> Result<Self, EnigmaError> {
+        let mut plugboard = Plugboard::default();
+
+        for pair in pairs.split_whitespace() {
+            let letters: Vec<u8> = pair.bytes().map(|b| b.to_ascii_uppercase()).collect();
+            if letters.len() != 2
+                || !letters.iter().all(u8::is_ascii_uppercase)
+                || letters[0] == letters[1]
+            {
+                return Err(EnigmaError::InvalidPlug(pair.to_string()));
+            }
+
+            let (a, b) = (letters[0] - b'A', letters[1] - b'A');
+            for letter in [a, b] {
+                if plugboard.wiring[letter as usize] != letter {
+                    return Err(EnigmaError::DuplicatePlug((letter + b'A') as char));
+                }
+            }
+            plugboard.wiring[a as usize] = b;
+            plugboard.wiring[b as usize] = a;
+        }
+
+        Ok(plugboard)
+    }
+
+    /// Returns the letter (`A` = 0) that the given letter is plugged to.
+    pub fn swap(&self, letter: u8) -> u8 {
+        self.wiring[letter as usize]
+    }
+
+    /// Returns the plugged pairs, in alphabetical order (e.g. `"AV BS CG"`).
+    pub fn pairs(&self) -> String {
+        (0..ALPHABET_SIZE as u8)
+            .filter(|&a| self.wiring[a as usize] > a)
+            .map(|a| {
+                format!(
+                    "{}{}",
+                    (a + b'A') as char,
+                    (self.wiring[a as usize] + b'A') as char
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl TryFrom<String> for Plugboard {
+    type Error = EnigmaError;
+
+    fn try_from(pairs: String) -> Result<Self, Self::Error> {
+        Plugboard::new(&pairs)
+    }
+}
+
+impl From<Plugboard> for String {
+    fn from(plugboard: Plugboard) -> Self {
+        plugboard.pairs()
+    }
+}
+
+impl Default for Plugboard {
+    fn default() -> Self {
+        let mut wiring = [0u8; ALPHABET_SIZE];
+        for (idx, letter) in wiring.iter_mut().enumerate() {
+            *letter = idx as u8;
+        }
+        Plugboard { wiring }
+    }
+}
+
+/// A rotor placed in the machine, with its ring setting and current position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct RotorSlot {
+    rotor: Rotor,
+    forward: [u8; ALPHABET_SIZE],
+    backward: [u8; ALPHABET_SIZE],
+    ring: u8,
+    position: u8,
+}
+
+impl RotorSlot {
+    /// Places a rotor in the machine.
+    fn new(rotor: Rotor, ring: u8, position: u8) -> Self {
+        let mut forward = [0u8; ALPHABET_SIZE];
+        let mut backward = [0u8; ALPHABET_SIZE];
+        for (idx, b) in rotor.wiring().bytes().enumerate() {
+            forward[idx] = b - b'A';
+            backward[(b - b'A') as usize] = idx as u8;
+        }
+
+        RotorSlot {
+            rotor,
+            forward,
+            backward,
+            ring,
+            position,
+        }
+    }
+
+    /// Returns whether the rotor is at one of its notches.
+    fn at_notch(&self) -> bool {
+        self.rotor
+            .notches()
+            .bytes()
+            .any(|notch| notch - b'A' == self.position)
+    }
+
+    /// Advances the rotor by one position.
+    fn step(&mut self) {
+        self.position = (self.position + 1) % ALPHABET_SIZE as u8;
+    }
+
+    /// Passes a letter through the rotor, from right to left or from left to right.
+    fn map(&self, letter: u8, wiring: &[u8; ALPHABET_SIZE]) -> u8 {
+        let size = ALPHABET_SIZE as u8;
+        let shift = (self.position + size - self.ring) % size;
+        let contact = (letter + shift) % size;
+        (wiring[contact as usize] + size - shift) % size
+    }
+}
+
+/// Builder for an [`Enigma`] machine.
+///
+/// The builder holds the settings of the machine, as listed in the key sheets.
+/// With the `serde` feature, it can be stored and loaded, e.g. as
+/// `{"model": "M3", "rotors": ["I", "II", "III"], "reflector": "B", ...}`;
+/// the settings missing from the input take the defaults of [`EnigmaBuilder::new`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SettingsRepr")
+)]
+pub struct EnigmaBuilder {
+    model: Model,
+    rotors: Vec<Rotor>,
+    reflector: Reflector,
+    ring_settings: String,
+    positions: String,
+    plugboard: String,
+}
+
+impl EnigmaBuilder {
+    /// Creates a builder for the given model, with sensible defaults.
+    ///
+    /// The defaults are the rotors I, II and III (preceded by Beta on the M4),
+    /// reflector B (thin on the M4), all ring settings and positions at `A` and
+    /// an empty plugboard.
+    pub fn new(model: Model) -> Self {
+        let (rotors, reflector) = match model {
+            Model::EnigmaI | Model::M3 => (vec![Rotor::I, Rotor::II, Rotor::III], Reflector::B),
+            Model::M4 => (
+                vec![Rotor::Beta, Rotor::I, Rotor::II, Rotor::III],
+                Reflector::BThin,
+            ),
+        };
+
+        EnigmaBuilder {
+            model,
+            rotors,
+            reflector,
+            ring_settings: "A".repeat(model.rotor_count()),
+            positions: "A".repeat(model.rotor_count()),
+            plugboard: String::new(),
+        }
+    }
+
+    /// Sets the rotors, from left to right.
+    pub fn rotors(mut self, rotors: &[Rotor]) -> Self {
+        self.rotors = rotors.to_vec();
+        self
+    }
+
+    /// Sets the reflector.
+    pub fn reflector(mut self, reflector: Reflector) -> Self {
+        self.reflector = reflector;
+        self
+    }
+
+    /// Sets the ring settings (Ringstellung) of the rotors, from left to right.
+    ///
+    /// The settings are given either as letters (`"AAA"`) or as space-separated
+    /// numbers from 1 to 26 (`"01 01 01"`).
+    pub fn ring_settings(mut self, ring_settings: &str) -> Self {
+        self.ring_settings = ring_settings.to_string();
+        self
+    }
+
+    /// Sets the starting positions (Grundstellung) of the rotors, from left to right (e.g. `"AAA"`).
+    pub fn positions(mut self, positions: &str) -> Self {
+        self.positions = positions.to_string();
+        self
+    }
+
+    /// Sets the plugboard pairs, separated by spaces (e.g. `"AV BS CG"`).
+    pub fn plugboard(mut self, pairs: &str) -> Self {
+        self.plugboard = pairs.to_string();
+        self
+    }
+
+    /// Validates the configuration and builds the machine.
+    pub fn build(self) -> Result<Enigma, EnigmaError> {
+        let count = self.model.rotor_count();
+        if self.rotors.len() != count {
+            return Err(EnigmaError::RotorCount(self.model, count));
+        }
+
+        for (idx, &rotor) in self.rotors.iter().enumerate() {
+            let allowed = match self.model {
+                Model::EnigmaI => Rotor::ALL[..5].contains(&rotor),
+                Model::M3 => !rotor.is_thin(),
+                Model::M4 => rotor.is_thin() == (idx == 0),
+            };
+            if !allowed {
+                return Err(EnigmaError::InvalidRotor(rotor, self.model));
+            }
+            if self.rotors[..idx].contains(&rotor) {
+                return Err(EnigmaError::DuplicateRotor(rotor));
+            }
+        }
+
+        let reflector_allowed = match self.model {
+            Model::EnigmaI => !self.reflector.is_thin(),
+            Model::M3 => matches!(self.reflector, Reflector::B | Reflector::C),
+            Model::M4 => self.reflector.is_thin(),
+        };
+        if !reflector_allowed {
+            return Err(EnigmaError::InvalidReflector(self.reflector, self.model));
+        }
+
+        let rings = parse_settings(&self.ring_settings, count)
+            .ok_or(EnigmaError::InvalidRingSettings(count))?;
+        let positions =
+            parse_letters(&self.positions, count).ok_or(EnigmaError::InvalidPositions(count))?;
+        let plugboard = Plugboard::new(&self.plugboard)?;
+
+        let mut reflector = [0u8; ALPHABET_SIZE];
+        for (idx, b) in self.reflector.wiring().bytes().enumerate() {
+            reflector[idx] = b - b'A';
+        }
+
+        Ok(Enigma {
+            model: self.model,
+            rotors: self
+                .rotors
+                .iter()
+                .zip(rings.iter().zip(positions.iter()))
+                .map(|(&rotor, (&ring, &position))| RotorSlot::new(rotor, ring, position))
+                .collect(),
+            reflector,
+            plugboard,
+        })
+    }
+}
+
+/// Through the [`Cipher`] trait, every message is enciphered by a machine
+/// freshly built from the settings, starting at the configured positions.
+impl Cipher for EnigmaBuilder {
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        Ok(self.clone().build()?.encrypt(plaintext))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        Ok(self.clone().build()?.decrypt(ciphertext))
+    }
+}
+
+/// The serialized form of an [`EnigmaBuilder`], where only the model is required.
+///
+/// Unknown keys are rejected, so that a misspelled setting is not silently
+/// replaced by its default.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SettingsRepr {
+    model: Model,
+    rotors: Option<Vec<Rotor>>,
+    reflector: Option<Reflector>,
+    ring_settings: Option<String>,
+    positions: Option<String>,
+    plugboard: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl From<SettingsRepr> for EnigmaBuilder {
+    fn from(repr: SettingsRepr) -> Self {
+        let defaults = EnigmaBuilder::new(repr.model);
+        EnigmaBuilder {
+            model: repr.model,
+            rotors: repr.rotors.unwrap_or(defaults.rotors),
+            reflector: repr.reflector.unwrap_or(defaults.reflector),
+            ring_settings: repr.ring_settings.unwrap_or(defaults.ring_settings),
+            positions: repr.positions.unwrap_or(defaults.positions),
+            plugboard: repr.plugboard.unwrap_or(defaults.plugboard),
+        }
+    }
+}
+
+/// An Enigma machine, ready to encipher messages.
+///
+/// The Enigma is reciprocal, so the same machine (set to the same positions)
+/// is used to encrypt and decrypt. Enciphering advances the rotors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Enigma {
+    model: Model,
+    rotors: Vec<RotorSlot>,
+    reflector: [u8; ALPHABET_SIZE],
+    plugboard: Plugboard,
+}
+
+impl Enigma {
+    /// Returns a builder for an Enigma machine of the given model.
+    pub fn builder(model: Model) -> EnigmaBuilder {
+        EnigmaBuilder::new(model)
+    }
+
+    /// Returns the model of the machine.
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    /// Returns the plugboard of the machine.
+    pub fn plugboard(&self) -> &Plugboard {
+        &self.plugboard
+    }
+
+    /// Returns the current positions of the rotors, from left to right (e.g. `"ADU"`).
+    pub fn positions(&self) -> String {
+        self.rotors
+            .iter()
+            .map(|slot| (slot.position + b'A') as char)
+            .collect()
+    }
+
+    /// Sets the positions of the rotors, from left to right (e.g. `"ADU"`).
+    pub fn set_positions(&mut self, positions: &str) -> Result<(), EnigmaError> {
+        let count = self.rotors.len();
+        let positions =
+            parse_letters(positions, count).ok_or(EnigmaError::InvalidPositions(count))?;
+        for (slot, position) in self.rotors.iter_mut().zip(positions) {
+            slot.position = position;
+        }
+        Ok(())
+    }
+
+    /// Encrypts the given text.
+    ///
+    /// Only the letters of the text are enciphered (in uppercase); any other
+    /// character is dropped, as there was no key for it on the machine.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The text to be encrypted.
+    ///
+    /// # Returns
+    ///
+    /// The encrypted text.
+    pub fn encrypt(&mut self, plaintext: &str) -> String {
+        self.encipher(plaintext)
+    }
+
+    /// Decrypts the given text.
+    ///
+    /// Since the Enigma is reciprocal, this is the same operation as [`Enigma::encrypt`].
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The text to be decrypted.
+    ///
+    /// # Returns
+    ///
+    /// The decrypted text.
+    pub fn decrypt(&mut self, ciphertext: &str) -> String {
+        self.encipher(ciphertext)
+    }
+
+    /// Enciphers the given text, recording every key press.
+    ///
+    /// Since the Enigma is reciprocal, this traces both encryption and decryption.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to be enciphered. Only its letters are used.
+    ///
+    /// # Returns
+    ///
+    /// The enciphered text, and one step per letter with the rotor positions
+    /// after stepping and the path of the current through the machine: the
+    /// plugboard, the rotors from right to left, the reflector, the rotors from
+    /// left to right and the plugboard again.
+    pub fn encipher_traced(&mut self, text: &str) -> (String, Vec<Step>) {
+        text.bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .map(|b| {
+                let letter = b.to_ascii_uppercase() - b'A';
+                self.step();
+                let path = self.scramble_path(letter);
+                let output = (path[path.len() - 1] + b'A') as char;
+                let path: Vec<String> = path
+                    .iter()
+                    .map(|&l| ((l + b'A') as char).to_string())
+                    .collect();
+                let step = Step::new((letter + b'A') as char, output, "scramble")
+                    .detail("positions", self.positions())
+                    .detail("path", path.join(" "));
+                (output, step)
+            })
+            .unzip()
+    }
+
+    /// Enciphers the letters of the given text.
+    fn encipher(&mut self, text: &str) -> String {
+        text.bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .filter_map(|b| self.press_key(b.to_ascii_uppercase() - b'A'))
+            .map(|letter| (letter + b'A') as char)
+            .collect()
+    }
+
+    /// Presses a key: steps the rotors, then sends the letter (`A` = 0) through the machine.
+    ///
+    /// # Arguments
+    ///
+    /// * `letter` - The index of the letter, from 0 (`A`) to 25 (`Z`).
+    ///
+    /// # Returns
+    ///
+    /// The index of the enciphered letter, or `None` if `letter` is not below
+    /// 26, in which case the rotors do not move.
+    pub fn press_key(&mut self, letter: u8) -> Option<u8> {
+        if letter as usize >= ALPHABET_SIZE {
+            return None;
+        }
+        self.step();
+        Some(self.scramble(letter))
+    }
+
+    /// Advances the rotors as the machine does before every key press.
+    ///
+    /// The right rotor always steps. The middle rotor steps when the right rotor
+    /// is at its notch, and also when it is itself at its notch, in which case
+    /// it takes the left rotor along (the double-stepping anomaly). The fourth
+    /// rotor of the M4 never moves.
+    pub fn step(&mut self) {
+        let n = self.rotors.len();
+        let (left, middle, right) = (n - 3, n - 2, n - 1);
+
+        if self.rotors[middle].at_notch() {
+            self.rotors[middle].step();
+            self.rotors[left].step();
+        } else if self.rotors[right].at_notch() {
+            self.rotors[middle].step();
+        }
+        self.rotors[right].step();
+    }
+
+    /// Sends a letter (`A` = 0) through the plugboard, rotors and reflector without stepping.
+    fn scramble(&self, letter: u8) -> u8 {
+        let mut letter = self.plugboard.swap(letter);
+        for slot in self.rotors.iter().rev() {
+            letter = slot.map(letter, &slot.forward);
+        }
+        letter = self.reflector[letter as usize];
+        for slot in self.rotors.iter() {
+            letter = slot.map(letter, &slot.backward);
+        }
+        self.plugboard.swap(letter)
+    }
+
+    /// Sends a letter through the machine like [`Enigma::scramble`], returning
+    /// the letter after every stage.
+    fn scramble_path(&self, letter: u8) -> Vec<u8> {
+        let mut path = vec![self.plugboard.swap(letter)];
+        for slot in self.rotors.iter().rev() {
+            path.push(slot.map(path[path.len() - 1], &slot.forward));
+        }
+        path.push(self.reflector[path[path.len() - 1] as usize]);
+        for slot in self.rotors.iter() {
+            path.push(slot.map(path[path.len() - 1], &slot.backward));
+        }
+        path.push(self.plugboard.swap(path[path.len() - 1]));
+        path
+    }
+}
+
+/// Parses a string of `count` letters (spaces are ignored) into letter indices.
+fn parse_letters(letters: &str, count: usize) -> Option<Vec<u8>> {
+    let letters: Vec<u8> = letters
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace())
+        .map(|b| {
+            b.is_ascii_alphabetic()
+                .then(|| b.to_ascii_uppercase() - b'A')
+        })
+        .collect::<Option<_>>()?;
+    (letters.len() == count).then_some(letters)
+}
+
+/// Parses ring settings given either as letters or as space-separated numbers from 1 to 26.
+fn parse_settings(settings: &str, count: usize) -> Option<Vec<u8>> {
+    if settings.bytes().any(|b| b.is_ascii_digit()) {
+        let numbers: Vec<u8> = settings
+            .split_whitespace()
+            .map(|n| n.parse::<u8>().ok().filter(|n| (1..=26).contains(n)))
+            .map(|n| n.map(|n| n - 1))
+            .collect::<Option<_>>()?;
+        (numbers.len() == count).then_some(numbers)
+    } else {
+        parse_letters(settings, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Removes the spaces of a grouped message.
+    fn ungroup(text: &str) -> String {
+        text.split_whitespace().collect()
+    }
+
+    #[test]
+    fn test_enigma_basic() {
+        let mut enigma = Enigma::builder(Model::EnigmaI).build().unwrap();
+        assert_eq!(enigma.encrypt("AAAAA"), "BDZGO");
+        assert_eq!(enigma.positions(), "AAF");
+
+        enigma.set_positions("AAA").unwrap();
+        assert_eq!(enigma.decrypt("BDZGO"), "AAAAA");
+    }
+
+    #[test]
+    fn test_enigma_traced() {
+        let mut enigma = Enigma::builder(Model::EnigmaI).build().unwrap();
+        let (ciphertext, steps) = enigma.encipher_traced("a-a");
+        assert_eq!(ciphertext, "BD");
+        assert_eq!(
+            steps[0],
+            Step::new('A', 'B', "scramble")
+                .detail("positions", "AAB")
+                .detail("path", "A C D F S S E B B")
+        );
+        assert_eq!(steps[1].details[0].1, "AAC");
+    }
+
+    #[test]
+    fn test_enigma_double_stepping() {
+        let mut enigma = Enigma::builder(Model::EnigmaI)
+            .positions("ADU")
+            .build()
+            .unwrap();
+
+        let mut positions = Vec::new();
+        for _ in 0..3 {
+            enigma.press_key(0);
+            positions.push(enigma.positions());
+        }
+        assert_eq!(positions, ["ADV", "AEW", "BFX"]);
+
+        assert_eq!(enigma.press_key(26), None);
+        assert_eq!(enigma.positions(), "BFX");
+    }
+
+    #[test]
+    fn test_enigma_never_encrypts_a_letter_to_itself() {
+        let mut enigma = Enigma::builder(Model::M3)
+            .rotors(&[Rotor::VI, Rotor::VII, Rotor::VIII])
+            .reflector(Reflector::C)
+            .ring_settings("05 12 26")
+            .positions("XYZ")
+            .plugboard("AB CD EF GH")
+            .build()
+            .unwrap();
+        let plaintext = "A".repeat(500);
+        assert!(!enigma.encrypt(&plaintext).contains('A'));
+    }
+
+    #[test]
+    fn test_enigma_operation_barbarossa() {
+        // Message sent on 7 July 1941, part 1 (Enigma I, reflector B).
+        let mut enigma = Enigma::builder(Model::EnigmaI)
+            .rotors(&[Rotor::II, Rotor::IV, Rotor::V])
+            .reflector(Reflector::B)
+            .ring_settings("02 21 12")
+            .positions("BLA")
+            .plugboard("AV BS CG DL FU HZ IN KM OW RX")
+            .build()
+            .unwrap();
+
+        let ciphertext = ungroup(
+            "EDPUD NRGYS ZRCXN UYTPO MRMBO FKTBZ REZKM LXLVE FGUEY SIOZV EQMIK UBPMM YLKLT TDEIS \
+             MDICA GYKUA CTCDO MOHWX MUUIA UBSTS LRNBZ SZWNR FXWFY SSXJZ VIJHI DISHP RKLKA YUPAD \
+             TXQSP INQMA TLPIF SVKDA SCTAC DPBOP VHJK",
+        );
+        let plaintext = ungroup(
+            "AUFKL XABTE ILUNG XVONX KURTI NOWAX KURTI NOWAX NORDW ESTLX SEBEZ XSEBE ZXUAF FLIEG \
+             ERSTR ASZER IQTUN GXDUB ROWKI XDUBR OWKIX OPOTS CHKAX OPOTS CHKAX UMXEI NSAQT DREIN \
+             ULLXU HRANG ETRET ENXAN GRIFF XINFX RGTX",
+        );
+        assert_eq!(enigma.decrypt(&ciphertext), plaintext);
+    }
+
+    #[test]
+    fn test_enigma_m4_u534() {
+        // Message sent to U-534 on 1 May 1945 (Enigma M4, thin reflector B).
+        let mut enigma = Enigma::builder(Model::M4)
+            .rotors(&[Rotor::Beta, Rotor::II, Rotor::IV, Rotor::I])
+            .reflector(Reflector::BThin)
+            .ring_settings("AAAV")
+            .positions("VJNA")
+            .plugboard("AT BL DF GJ HM NW OP QY RZ VX")
+            .build()
+            .unwrap();
+
+        let ciphertext = ungroup(
+            "NCZW VUSX PNYM INHZ XMQX SFWX WLKJ AHSH NMCO CCAK UQPM KCSM HKSE INJU SBLK IOSX CKUB \
+             HMLL XCSJ USRR DVKO HULX WCCB GVLI YXEO AHXR HKKF VDRE WEZL XOBA FGYU JQUK GRTV UKAM \
+             EURB VEKS UHHV OYHA BCJW MAKL FKLM YFVN RIZR VVRT KOFD ANJM OLBG FFLE OPRG TFLV RHOW \
+             OPBE KVWM UQFM PWPA RMFH AGKX IIBG",
+        );
+        let plaintext = ungroup(
+            "VONV ONJL OOKS JHFF TTTE INSE INSD REIZ WOYY QNNS NEUN INHA LTXX BEIA NGRI FFUN TERW \
+             ASSE RGED RUEC KTYW ABOS XLET ZTER GEGN ERST ANDN ULAC HTDR EINU LUHR MARQ UANT ONJO \
+             TANE UNAC HTSE YHSD REIY ZWOZ WONU LGRA DYAC HTSM YSTO SSEN ACHX EKNS VIER MBFA ELLT \
+             YNNN NNNO OOVI ERYS ICHT EINS NULL",
+        );
+        assert_eq!(enigma.decrypt(&ciphertext), plaintext);
+    }
+
+    #[test]
+    fn test_enigma_m4_beta_a_matches_three_rotor_machine() {
+        // With Beta at A and the thin reflector B, the M4 behaves like a three-rotor machine with reflector B.
+        let mut m4 = Enigma::builder(Model::M4)
+            .rotors(&[Rotor::Beta, Rotor::I, Rotor::II, Rotor::III])
+            .reflector(Reflector::BThin)
+            .build()
+            .unwrap();
+        let mut m3 = Enigma::builder(Model::M3).build().unwrap();
+        let plaintext = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG";
+        assert_eq!(m4.encrypt(plaintext), m3.encrypt(plaintext));
+    }
+
+    #[test]
+    fn test_enigma_plugboard() {
+        let plugboard = Plugboard::new("av BS").unwrap();
+        assert_eq!(plugboard.swap(0), 21);
+        assert_eq!(plugboard.swap(2), 2);
+        assert_eq!(plugboard.pairs(), "AV BS");
+        assert_eq!(
+            Plugboard::new("AV AB"),
+            Err(EnigmaError::DuplicatePlug('A'))
+        );
+        assert_eq!(
+            Plugboard::new("AVB"),
+            Err(EnigmaError::InvalidPlug("AVB".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_enigma_invalid_configurations() {
+        assert_eq!(
+            Enigma::builder(Model::EnigmaI)
+                .rotors(&[Rotor::I, Rotor::II, Rotor::VI])
+                .build(),
+            Err(EnigmaError::InvalidRotor(Rotor::VI, Model::EnigmaI))
+        );
+        assert_eq!(
+            Enigma::builder(Model::M3)
+                .rotors(&[Rotor::I, Rotor::I, Rotor::II])
+                .build(),
+            Err(EnigmaError::DuplicateRotor(Rotor::I))
+        );
+        assert_eq!(
+            Enigma::builder(Model::M3).rotors(&[Rotor::I]).build(),
+            Err(EnigmaError::RotorCount(Model::M3, 3))
+        );
+        assert_eq!(
+            Enigma::builder(Model::M4)
+                .rotors(&[Rotor::I, Rotor::II, Rotor::III, Rotor::IV])
+                .build(),
+            Err(EnigmaError::InvalidRotor(Rotor::I, Model::M4))
+        );
+        assert_eq!(
+            Enigma::builder(Model::M4).reflector(Reflector::B).build(),
+            Err(EnigmaError::InvalidReflector(Reflector::B, Model::M4))
+        );
+        assert_eq!(
+            Enigma::builder(Model::EnigmaI).positions("AB").build(),
+            Err(EnigmaError::InvalidPositions(3))
+        );
+        assert_eq!(
+            Enigma::builder(Model::EnigmaI)
+                .ring_settings("01 02 27")
+                .build(),
+            Err(EnigmaError::InvalidRingSettings(3))
+        );
+    }
+}
@@ -0,0 +1,812 @@
+//! Key recovery attacks on the Enigma.
+//!
+//! Two classical attacks are provided:
+//!
+//! * A simulation of the Turing-Welchman **Bombe**. Given a ciphertext and a
+//!   crib (a guessed piece of plaintext), a [`Menu`] of letter pairs is built,
+//!   and every rotor order and starting position is tested for consistency with
+//!   the loops of the menu, using the diagonal board to propagate plugboard
+//!   hypotheses. The surviving settings ("stops") come with the plugboard
+//!   connections they imply.
+//! * A **ciphertext-only** attack in the style of Gillogly and Weierud-Sullivan:
+//!   the rotor orders and positions are ranked by the index of coincidence of
+//!   the decryption without a plugboard, the ring settings of the middle and
+//!   right rotors are refined, and the plugboard is then recovered by hill
+//!   climbing.
+//!
+//! Both attacks assume a three-rotor machine whose ring settings are fixed
+//! during the rotor search (`AAA` by default): the Bombe, like its historical
+//! counterpart, may miss the right setting if the middle rotor turns over
+//! within the crib under different ring settings.
+
+use super::{Enigma, EnigmaError, Model, Plugboard, Reflector, Rotor, ALPHABET_SIZE};
+pub use crate::language::index_of_coincidence;
+use std::collections::VecDeque;
+use thiserror::Error;
+
+/// Error type for the Enigma key recovery attacks.
+#[derive(Error, Debug, PartialEq)]
+pub enum CrackError {
+    #[error("Crib cannot be empty")]
+    EmptyCrib,
+    #[error("Crib does not fit in the ciphertext at this offset")]
+    CribTooLong,
+    #[error("Crib letter {0} would be enciphered to itself at position {1}")]
+    SelfEncipherment(char, usize),
+    #[error("Only three-rotor machines can be attacked")]
+    UnsupportedModel,
+    #[error("Ciphertext must only contain letters")]
+    InvalidCiphertext,
+    #[error(transparent)]
+    Enigma(#[from] EnigmaError),
+}
+
+/// The settings shared by all the attacks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchConfig {
+    /// The model of the machine (`EnigmaI` or `M3`).
+    pub model: Model,
+    /// The reflector of the machine.
+    pub reflector: Reflector,
+    /// The rotor orders to try, from left to right.
+    pub rotor_orders: Vec<[Rotor; 3]>,
+    /// The ring settings assumed during the rotor search.
+    pub ring_settings: [u8; 3],
+}
+
+impl SearchConfig {
+    /// Creates a configuration trying every rotor order of the given model, with
+    /// reflector B and the ring settings `AAA`.
+    pub fn new(model: Model) -> Self {
+        let rotors: &[Rotor] = match model {
+            Model::EnigmaI => &Rotor::ALL[..5],
+            _ => &Rotor::ALL,
+        };
+
+        let mut rotor_orders = Vec::new();
+        for &left in rotors {
+            for &middle in rotors {
+                for &right in rotors {
+                    if left != middle && middle != right && left != right {
+                        rotor_orders.push([left, middle, right]);
+                    }
+                }
+            }
+        }
+
+        SearchConfig {
+            model,
+            reflector: Reflector::B,
+            rotor_orders,
+            ring_settings: [0; 3],
+        }
+    }
+
+    /// Restricts the search to the given rotor orders.
+    pub fn rotor_orders(mut self, rotor_orders: &[[Rotor; 3]]) -> Self {
+        self.rotor_orders = rotor_orders.to_vec();
+        self
+    }
+
+    /// Sets the reflector of the machine.
+    pub fn reflector(mut self, reflector: Reflector) -> Self {
+        self.reflector = reflector;
+        self
+    }
+
+    /// Builds a machine with the given rotor order, without a plugboard.
+    fn machine(&self, rotors: [Rotor; 3]) -> Result<Enigma, CrackError> {
+        if self.model == Model::M4 {
+            return Err(CrackError::UnsupportedModel);
+        }
+
+        let mut machine = Enigma::builder(self.model)
+            .rotors(&rotors)
+            .reflector(self.reflector)
+            .build()?;
+        for (slot, &ring) in machine.rotors.iter_mut().zip(&self.ring_settings) {
+            slot.ring = ring;
+        }
+        Ok(machine)
+    }
+}
+
+/// A connection of the menu: at `position`, `plain` is enciphered to `cipher`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MenuEdge {
+    pub position: usize,
+    pub plain: char,
+    pub cipher: char,
+}
+
+/// The menu of a crib: the graph of letters linked by the positions at which they are enciphered to each other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Menu {
+    edges: Vec<MenuEdge>,
+}
+
+impl Menu {
+    /// Builds the menu of a crib placed at the given offset of the ciphertext.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The ciphertext, as uppercase letters.
+    /// * `crib` - The guessed plaintext.
+    /// * `offset` - The position of the first letter of the crib in the ciphertext.
+    ///
+    /// # Returns
+    ///
+    /// The menu, or an error if the crib doesn't fit or a letter would be enciphered to itself.
+    pub fn new(ciphertext: &str, crib: &str, offset: usize) -> Result<Self, CrackError> {
+        let ciphertext = letters(ciphertext)?;
+        let crib: Vec<u8> = crib
+            .bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .map(|b| b.to_ascii_uppercase() - b'A')
+            .collect();
+
+        if crib.is_empty() {
+            return Err(CrackError::EmptyCrib);
+        }
+        if offset + crib.len() > ciphertext.len() {
+            return Err(CrackError::CribTooLong);
+        }
+
+        let edges = crib
+            .iter()
+            .zip(&ciphertext[offset..])
+            .enumerate()
+            .map(|(idx, (&plain, &cipher))| {
+                let position = offset + idx;
+                if plain == cipher {
+                    Err(CrackError::SelfEncipherment(
+                        (plain + b'A') as char,
+                        position,
+                    ))
+                } else {
+                    Ok(MenuEdge {
+                        position,
+                        plain: (plain + b'A') as char,
+                        cipher: (cipher + b'A') as char,
+                    })
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Menu { edges })
+    }
+
+    /// Returns the connections of the menu.
+    pub fn edges(&self) -> &[MenuEdge] {
+        &self.edges
+    }
+
+    /// Returns the letter with the most connections, which is used as the input of the Bombe.
+    pub fn central_letter(&self) -> char {
+        let mut degrees = [0usize; ALPHABET_SIZE];
+        for edge in &self.edges {
+            degrees[letter_index(edge.plain)] += 1;
+            degrees[letter_index(edge.cipher)] += 1;
+        }
+        let best = (0..ALPHABET_SIZE)
+            .max_by_key(|&l| (degrees[l], std::cmp::Reverse(l)))
+            .unwrap_or_default();
+        (best as u8 + b'A') as char
+    }
+
+    /// Returns a set of independent loops of the menu.
+    ///
+    /// Each loop is given as the sequence of letters visited, the first letter
+    /// being repeated at the end (e.g. `['E', 'T', 'R', 'E']`). The more loops a
+    /// menu has, the fewer false stops the Bombe produces.
+    pub fn loops(&self) -> Vec<Vec<char>> {
+        let mut parent: [Option<(usize, usize)>; ALPHABET_SIZE] = [None; ALPHABET_SIZE];
+        let mut depth = [usize::MAX; ALPHABET_SIZE];
+        let mut tree_edges = vec![false; self.edges.len()];
+        let adjacency = self.adjacency();
+
+        // Build a spanning forest; every edge outside of it closes a loop.
+        for root in 0..ALPHABET_SIZE {
+            if depth[root] != usize::MAX || adjacency[root].is_empty() {
+                continue;
+            }
+            depth[root] = 0;
+            let mut queue = VecDeque::from([root]);
+            while let Some(letter) = queue.pop_front() {
+                for &(other, edge) in &adjacency[letter] {
+                    if depth[other] == usize::MAX {
+                        depth[other] = depth[letter] + 1;
+                        parent[other] = Some((letter, edge));
+                        tree_edges[edge] = true;
+                        queue.push_back(other);
+                    }
+                }
+            }
+        }
+
+        self.edges
+            .iter()
+            .enumerate()
+            .filter(|&(idx, _)| !tree_edges[idx])
+            .map(|(_, edge)| {
+                // Walk up the tree from both ends of the edge until the paths meet.
+                let (mut a, mut b) = (letter_index(edge.plain), letter_index(edge.cipher));
+                let (mut from_a, mut from_b) = (vec![a], vec![b]);
+                while a != b {
+                    if depth[a] >= depth[b] {
+                        a = parent[a].map(|(p, _)| p).unwrap_or(a);
+                        from_a.push(a);
+                    } else {
+                        b = parent[b].map(|(p, _)| p).unwrap_or(b);
+                        from_b.push(b);
+                    }
+                }
+                from_b.pop();
+                from_a.extend(from_b.into_iter().rev());
+                from_a.push(from_a[0]);
+                from_a
+                    .into_iter()
+                    .map(|l| (l as u8 + b'A') as char)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns, for every letter, the letters it is connected to and the index of the connecting edge.
+    fn adjacency(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut adjacency = vec![Vec::new(); ALPHABET_SIZE];
+        for (idx, edge) in self.edges.iter().enumerate() {
+            let (a, b) = (letter_index(edge.plain), letter_index(edge.cipher));
+            adjacency[a].push((b, idx));
+            adjacency[b].push((a, idx));
+        }
+        adjacency
+    }
+}
+
+/// A setting of the machine that is consistent with the menu.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stop {
+    /// The rotor order, from left to right.
+    pub rotors: [Rotor; 3],
+    /// The starting positions of the rotors at the beginning of the message.
+    pub positions: String,
+    /// The plugboard connections implied by the stop (letters plugged to themselves are omitted).
+    pub plugboard: Plugboard,
+}
+
+/// Returns the offsets at which a crib can be placed in the ciphertext.
+///
+/// Since the Enigma never enciphers a letter to itself, the crib cannot be
+/// placed where one of its letters is aligned with the same ciphertext letter.
+pub fn crib_positions(ciphertext: &str, crib: &str) -> Vec<usize> {
+    let ciphertext: Vec<u8> = ciphertext.bytes().filter(u8::is_ascii_alphabetic).collect();
+    let crib: Vec<u8> = crib.bytes().filter(u8::is_ascii_alphabetic).collect();
+    if crib.is_empty() || crib.len() > ciphertext.len() {
+        return Vec::new();
+    }
+
+    (0..=ciphertext.len() - crib.len())
+        .filter(|&offset| {
+            crib.iter()
+                .zip(&ciphertext[offset..])
+                .all(|(p, c)| !p.eq_ignore_ascii_case(c))
+        })
+        .collect()
+}
+
+/// Runs the Bombe: finds the rotor orders and starting positions consistent with a crib.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext, as letters.
+/// * `crib` - The guessed plaintext.
+/// * `offset` - The position of the first letter of the crib in the ciphertext.
+/// * `config` - The machine settings and rotor orders to try.
+///
+/// # Returns
+///
+/// The stops of the Bombe, or an error if the menu cannot be built.
+pub fn bombe(
+    ciphertext: &str,
+    crib: &str,
+    offset: usize,
+    config: &SearchConfig,
+) -> Result<Vec<Stop>, CrackError> {
+    let menu = Menu::new(ciphertext, crib, offset)?;
+    let adjacency = menu.adjacency();
+    let central = letter_index(menu.central_letter());
+    let last = menu
+        .edges
+        .iter()
+        .map(|e| e.position)
+        .max()
+        .unwrap_or_default();
+    let mut stops = Vec::new();
+
+    for &rotors in &config.rotor_orders {
+        let mut machine = config.machine(rotors)?;
+        for start in all_positions() {
+            machine.set_rotor_positions(start);
+            // The scrambler (without plugboard) at every position covered by the menu.
+            let scramblers = scrambler_table(&machine, last + 1);
+            let edge_scramblers: Vec<&[u8; ALPHABET_SIZE]> =
+                menu.edges.iter().map(|e| &scramblers[e.position]).collect();
+
+            for hypothesis in 0..ALPHABET_SIZE {
+                if let Some(steckers) =
+                    test_hypothesis(&adjacency, &edge_scramblers, central, hypothesis)
+                {
+                    let mut plugboard = Plugboard::default();
+                    for (letter, partner) in steckers.iter().enumerate() {
+                        if let Some(partner) = *partner {
+                            plugboard.wiring[letter] = partner as u8;
+                        }
+                    }
+                    stops.push(Stop {
+                        rotors,
+                        positions: start.iter().map(|&p| (p + b'A') as char).collect(),
+                        plugboard,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(stops)
+}
+
+/// Tests whether "`central` is plugged to `hypothesis`" is consistent with the menu.
+///
+/// Starting from the hypothesis, the implications of the menu connections and
+/// of the diagonal board (if `A` is plugged to `B`, `B` is plugged to `A`) are
+/// propagated. The hypothesis is consistent if no letter ends up plugged to
+/// two different letters.
+///
+/// # Returns
+///
+/// The deduced plugboard partner of every letter, or `None` if the hypothesis is inconsistent.
+fn test_hypothesis(
+    adjacency: &[Vec<(usize, usize)>],
+    scramblers: &[&[u8; ALPHABET_SIZE]],
+    central: usize,
+    hypothesis: usize,
+) -> Option<[Option<usize>; ALPHABET_SIZE]> {
+    let mut steckers = [None; ALPHABET_SIZE];
+    let mut queue = vec![(central, hypothesis)];
+
+    while let Some((letter, partner)) = queue.pop() {
+        match steckers[letter] {
+            Some(existing) if existing == partner => continue,
+            Some(_) => return None,
+            None => steckers[letter] = Some(partner),
+        }
+
+        // Diagonal board.
+        queue.push((partner, letter));
+        // Menu connections: if `letter` is plugged to `partner`, the other end
+        // of the connection is plugged to the scrambled `partner`.
+        for &(other, edge) in &adjacency[letter] {
+            queue.push((other, scramblers[edge][partner] as usize));
+        }
+    }
+
+    Some(steckers)
+}
+
+/// A candidate key found by the ciphertext-only attack.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    /// The rotor order, from left to right.
+    pub rotors: [Rotor; 3],
+    /// The ring settings of the rotors.
+    pub ring_settings: [u8; 3],
+    /// The starting positions of the rotors.
+    pub positions: [u8; 3],
+    /// The plugboard.
+    pub plugboard: Plugboard,
+    /// The score of the decryption with this key.
+    pub score: f64,
+}
+
+impl Candidate {
+    /// Builds the machine described by this candidate, ready to decrypt the message.
+    pub fn machine(&self, config: &SearchConfig) -> Result<Enigma, CrackError> {
+        let mut machine = config.machine(self.rotors)?;
+        for (slot, &ring) in machine.rotors.iter_mut().zip(&self.ring_settings) {
+            slot.ring = ring;
+        }
+        machine.set_rotor_positions(self.positions);
+        machine.plugboard = self.plugboard;
+        Ok(machine)
+    }
+}
+
+/// Ranks the rotor orders and starting positions by the index of coincidence of the decryption without a plugboard.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext, as letters.
+/// * `config` - The machine settings and rotor orders to try.
+/// * `keep` - The number of best candidates to return.
+///
+/// # Returns
+///
+/// The best candidates, sorted by decreasing index of coincidence.
+pub fn rotor_search(
+    ciphertext: &str,
+    config: &SearchConfig,
+    keep: usize,
+) -> Result<Vec<Candidate>, CrackError> {
+    let ciphertext = letters(ciphertext)?;
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for &rotors in &config.rotor_orders {
+        let mut machine = config.machine(rotors)?;
+        for start in all_positions() {
+            machine.set_rotor_positions(start);
+            let decrypted: Vec<u8> = ciphertext
+                .iter()
+                .filter_map(|&c| machine.press_key(c))
+                .collect();
+            let score = index_of_coincidence(&decrypted);
+
+            if candidates.len() < keep || score > candidates[candidates.len() - 1].score {
+                candidates.push(Candidate {
+                    rotors,
+                    ring_settings: config.ring_settings,
+                    positions: start,
+                    plugboard: Plugboard::default(),
+                    score,
+                });
+                candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+                candidates.truncate(keep);
+            }
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// Refines the ring settings of the middle and right rotors of a candidate.
+///
+/// Changing a ring setting together with the position of the rotor keeps the
+/// wiring aligned, and only moves the turnover of the rotor. The combination
+/// with the best index of coincidence is kept.
+pub fn refine_rings(
+    ciphertext: &str,
+    candidate: &Candidate,
+    config: &SearchConfig,
+) -> Result<Candidate, CrackError> {
+    let ciphertext = letters(ciphertext)?;
+    let size = ALPHABET_SIZE as u8;
+    let mut best = candidate.clone();
+
+    for middle in 0..size {
+        for right in 0..size {
+            let mut trial = candidate.clone();
+            trial.ring_settings[1] = (candidate.ring_settings[1] + middle) % size;
+            trial.ring_settings[2] = (candidate.ring_settings[2] + right) % size;
+            trial.positions[1] = (candidate.positions[1] + middle) % size;
+            trial.positions[2] = (candidate.positions[2] + right) % size;
+
+            let table = scrambler_table(&trial.machine(config)?, ciphertext.len());
+            trial.score =
+                index_of_coincidence(&decrypt_with(&table, &ciphertext, &trial.plugboard));
+            if trial.score > best.score {
+                best = trial;
+            }
+        }
+    }
+
+    Ok(best)
+}
+
+/// Recovers the plugboard of a candidate by hill climbing.
+///
+/// Starting from the candidate's plugboard, pairs of letters are repeatedly
+/// connected or disconnected, keeping every change that improves the score of
+/// the decryption, until no change helps anymore.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext, as letters.
+/// * `candidate` - The candidate whose rotor settings are used.
+/// * `config` - The machine settings.
+/// * `max_pairs` - The maximum number of plugboard connections.
+/// * `score` - The fitness function, given the decryption as letter indices (`A` = 0).
+///
+/// # Returns
+///
+/// The candidate with the best plugboard found and its score.
+pub fn hill_climb_plugboard(
+    ciphertext: &str,
+    candidate: &Candidate,
+    config: &SearchConfig,
+    max_pairs: usize,
+    score: impl Fn(&[u8]) -> f64,
+) -> Result<Candidate, CrackError> {
+    let ciphertext = letters(ciphertext)?;
+    let mut unplugged = candidate.clone();
+    unplugged.plugboard = Plugboard::default();
+    let table = scrambler_table(&unplugged.machine(config)?, ciphertext.len());
+
+    let mut best = candidate.clone();
+    best.score = score(&decrypt_with(&table, &ciphertext, &best.plugboard));
+
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for a in 0..ALPHABET_SIZE {
+            for b in a + 1..ALPHABET_SIZE {
+                let mut plugboard = best.plugboard;
+                if plugboard.wiring[a] == b as u8 {
+                    // Disconnect `a` and `b`.
+                    plugboard.wiring[a] = a as u8;
+                    plugboard.wiring[b] = b as u8;
+                } else {
+                    // Connect `a` and `b`, releasing their previous partners.
+                    for letter in [a, b] {
+                        let partner = plugboard.wiring[letter] as usize;
+                        plugboard.wiring[partner] = partner as u8;
+                        plugboard.wiring[letter] = letter as u8;
+                    }
+                    plugboard.wiring[a] = b as u8;
+                    plugboard.wiring[b] = a as u8;
+                }
+
+                let pairs = plugboard
+                    .wiring
+                    .iter()
+                    .enumerate()
+                    .filter(|&(l, &p)| p as usize > l)
+                    .count();
+                if pairs > max_pairs {
+                    continue;
+                }
+
+                let trial_score = score(&decrypt_with(&table, &ciphertext, &plugboard));
+                if trial_score > best.score {
+                    best.plugboard = plugboard;
+                    best.score = trial_score;
+                    improved = true;
+                }
+            }
+        }
+    }
+
+    Ok(best)
+}
+
+/// Runs the whole ciphertext-only attack and returns the best key found.
+///
+/// The `candidates` best rotor settings found by [`rotor_search`] have their
+/// ring settings refined, then their plugboard recovered by hill climbing on
+/// the index of coincidence.
+pub fn ciphertext_only(
+    ciphertext: &str,
+    config: &SearchConfig,
+    candidates: usize,
+    max_pairs: usize,
+) -> Result<Option<Candidate>, CrackError> {
+    let mut best: Option<Candidate> = None;
+
+    for candidate in rotor_search(ciphertext, config, candidates)? {
+        let candidate = refine_rings(ciphertext, &candidate, config)?;
+        let candidate = hill_climb_plugboard(
+            ciphertext,
+            &candidate,
+            config,
+            max_pairs,
+            index_of_coincidence,
+        )?;
+        if best.as_ref().is_none_or(|b| candidate.score > b.score) {
+            best = Some(candidate);
+        }
+    }
+
+    Ok(best)
+}
+
+impl Enigma {
+    /// Sets the rotor positions from letter indices, from left to right.
+    fn set_rotor_positions(&mut self, positions: [u8; 3]) {
+        for (slot, position) in self.rotors.iter_mut().zip(positions) {
+            slot.position = position;
+        }
+    }
+}
+
+/// Iterates over all the starting positions of a three-rotor machine.
+fn all_positions() -> impl Iterator<Item = [u8; 3]> {
+    let size = ALPHABET_SIZE as u8;
+    (0..size).flat_map(move |l| (0..size).flat_map(move |m| (0..size).map(move |r| [l, m, r])))
+}
+
+/// Computes the scrambler (rotors and reflector, without plugboard) used for each of the first `len` letters.
+fn scrambler_table(machine: &Enigma, len: usize) -> Vec<[u8; ALPHABET_SIZE]> {
+    let mut machine = machine.clone();
+    machine.plugboard = Plugboard::default();
+
+    (0..len)
+        .map(|_| {
+            machine.step();
+            let mut scrambler = [0u8; ALPHABET_SIZE];
+            for (letter, output) in scrambler.iter_mut().enumerate() {
+                *output = machine.scramble(letter as u8);
+            }
+            scrambler
+        })
+        .collect()
+}
+
+/// Decrypts a text with precomputed scramblers and the given plugboard.
+fn decrypt_with(
+    table: &[[u8; ALPHABET_SIZE]],
+    ciphertext: &[u8],
+    plugboard: &Plugboard,
+) -> Vec<u8> {
+    ciphertext
+        .iter()
+        .zip(table)
+        .map(|(&c, scrambler)| plugboard.swap(scrambler[plugboard.swap(c) as usize]))
+        .collect()
+}
+
+/// Converts a ciphertext to letter indices, ignoring whitespace.
+fn letters(text: &str) -> Result<Vec<u8>, CrackError> {
+    text.bytes()
+        .filter(|b| !b.is_ascii_whitespace())
+        .map(|b| {
+            b.is_ascii_alphabetic()
+                .then(|| b.to_ascii_uppercase() - b'A')
+                .ok_or(CrackError::InvalidCiphertext)
+        })
+        .collect()
+}
+
+/// Returns the index (`A` = 0) of an uppercase letter.
+fn letter_index(c: char) -> usize {
+    c as usize - 'A' as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: &str = "WETTERVORHERSAGEFUERDIEBUCHTVONBISKAYAHEUTENACHTUNDMORGEN";
+
+    fn sample_machine() -> Enigma {
+        Enigma::builder(Model::EnigmaI)
+            .rotors(&[Rotor::II, Rotor::V, Rotor::III])
+            .reflector(Reflector::B)
+            .positions("KDO")
+            .plugboard("AK BZ CE DX FP GW HS")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_crack_crib_positions() {
+        assert_eq!(crib_positions("ABCDE", "BC"), vec![0, 2, 3]);
+        assert_eq!(crib_positions("AB", "ABC"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_crack_menu() {
+        let menu = Menu::new("XYZAB", "ABCDE", 0).unwrap();
+        assert_eq!(
+            menu.edges()[1],
+            MenuEdge {
+                position: 1,
+                plain: 'B',
+                cipher: 'Y'
+            }
+        );
+        assert_eq!(
+            Menu::new("XBZ", "ABC", 0),
+            Err(CrackError::SelfEncipherment('B', 1))
+        );
+        assert_eq!(Menu::new("XYZ", "ABCD", 0), Err(CrackError::CribTooLong));
+        assert_eq!(Menu::new("XYZ", "", 0), Err(CrackError::EmptyCrib));
+    }
+
+    #[test]
+    fn test_crack_invalid_config() {
+        let config = SearchConfig::new(Model::M4);
+        assert_eq!(
+            rotor_search("ABC", &config, 1),
+            Err(CrackError::UnsupportedModel)
+        );
+
+        let config =
+            SearchConfig::new(Model::EnigmaI).rotor_orders(&[[Rotor::VI, Rotor::I, Rotor::II]]);
+        assert_eq!(
+            rotor_search("ABC", &config, 1),
+            Err(CrackError::Enigma(EnigmaError::InvalidRotor(
+                Rotor::VI,
+                Model::EnigmaI
+            )))
+        );
+        let config = SearchConfig::new(Model::M3).reflector(Reflector::BThin);
+        assert_eq!(
+            rotor_search("ABC", &config, 1),
+            Err(CrackError::Enigma(EnigmaError::InvalidReflector(
+                Reflector::BThin,
+                Model::M3
+            )))
+        );
+    }
+
+    #[test]
+    fn test_crack_menu_loops() {
+        // A-B at 0, B-C at 1, C-A at 2, A-D at 3, D-B at 4: two independent loops.
+        let menu = Menu::new("BCADB", "ABCAD", 0).unwrap();
+        assert_eq!(menu.central_letter(), 'A');
+        let loops = menu.loops();
+        assert_eq!(loops.len(), 2);
+        for lp in loops {
+            assert_eq!(lp.first(), lp.last());
+            assert!(lp.len() >= 3);
+        }
+    }
+
+    #[test]
+    fn test_crack_bombe_finds_the_key() {
+        let ciphertext = sample_machine().encrypt(PLAINTEXT);
+        let crib = "WETTERVORHERSAGE";
+        assert!(crib_positions(&ciphertext, crib).contains(&0));
+
+        let config =
+            SearchConfig::new(Model::EnigmaI).rotor_orders(&[[Rotor::II, Rotor::V, Rotor::III]]);
+        let stops = bombe(&ciphertext, crib, 0, &config).unwrap();
+
+        let stop = stops
+            .iter()
+            .find(|s| s.positions == "KDO")
+            .expect("no stop at the right position");
+        assert!(stops.len() < 50);
+        let mut machine = Enigma::builder(Model::EnigmaI)
+            .rotors(&stop.rotors)
+            .positions(&stop.positions)
+            .plugboard(&stop.plugboard.pairs())
+            .build()
+            .unwrap();
+        assert!(machine.decrypt(&ciphertext).starts_with(crib));
+    }
+
+    #[test]
+    fn test_crack_index_of_coincidence() {
+        assert_eq!(index_of_coincidence(&[0, 0, 0, 0]), 1.0);
+        assert_eq!(index_of_coincidence(&[0, 1, 2, 3]), 0.0);
+        assert_eq!(index_of_coincidence(&[0]), 0.0);
+    }
+
+    #[test]
+    fn test_crack_ciphertext_only() {
+        let plaintext =
+            "THEENIGMAMACHINEWASUSEDBYTHEGERMANARMEDFORCESTHROUGHOUTTHESECONDWORLDWARANDITS\
+            MESSAGESWEREBELIEVEDTOBEUNBREAKABLEBYTHOSEWHOSENTTHEMHOWEVERTHEPOLISHCIPHERBUREAU\
+            HADALREADYREADTHEMBEFORETHEWARANDTHEIRWORKWASPASSEDONTOTHEBRITISHATBLETCHLEYPARK\
+            WHEREALANTURINGANDGORDONWELCHMANDESIGNEDTHEBOMBEWHICHSEARCHEDFORTHEDAILYSETTINGS\
+            USINGCRIBSTHATISPIECESOFGUESSEDPLAINTEXTSUCHASWEATHERREPORTSANDROUTINEGREETINGS\
+            THEINTELLIGENCEGAINEDFROMTHESEDECRYPTSWASCALLEDULTRAANDITISOFTENSAIDTOHAVE\
+            SHORTENEDTHEWARBYSEVERALYEARSANDSAVEDCOUNTLESSLIVESONBOTHSIDESOFTHECONFLICT";
+        let rotors = [Rotor::IV, Rotor::I, Rotor::V];
+        let mut machine = Enigma::builder(Model::EnigmaI)
+            .rotors(&rotors)
+            .ring_settings("AAA")
+            .positions("QHW")
+            .plugboard("AR GK OX")
+            .build()
+            .unwrap();
+        let ciphertext = machine.encrypt(plaintext);
+
+        let config = SearchConfig::new(Model::EnigmaI).rotor_orders(&[rotors]);
+        let candidate = ciphertext_only(&ciphertext, &config, 1, 10)
+            .unwrap()
+            .unwrap();
+        let mut machine = candidate.machine(&config).unwrap();
+        assert_eq!(machine.decrypt(&ciphertext), plaintext);
+    }
+}
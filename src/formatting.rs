@@ -0,0 +1,159 @@
+//! Output formatting shared by the ciphers.
+//!
+//! The ciphers of the crate do not treat the characters other than letters
+//! the same way: [`crate::caesar`] keeps case, spaces and punctuation in place,
+//! while [`crate::vigenere`] and [`crate::playfair`] only work on the uppercase
+//! letters. A [`Formatting`] makes this a choice of the caller: the cipher is
+//! applied to the letters only, so that its key advances on letters only, and
+//! the layout of the input is then stripped, restored or replaced by groups of
+//! letters.
+//!
+//! ```
+//! use cyphers::formatting::Formatting;
+//!
+//! let encrypt = |letters: &str| cyphers::vigenere::encrypt(letters, "KEY");
+//! assert_eq!(Formatting::Strip.apply("Hello, World!", encrypt), "RIJVSUYVJN");
+//! assert_eq!(Formatting::Preserve.apply("Hello, World!", encrypt), "Rijvs, Uyvjn!");
+//! assert_eq!(Formatting::Group(5).apply("Hello, World!", encrypt), "RIJVS UYVJN");
+//! ```
+
+use crate::prelude::*;
+
+/// How the output of a cipher is laid out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Formatting {
+    /// Uppercase letters only, without spaces or punctuation.
+    #[default]
+    Strip,
+    /// The case of every letter and the other characters of the input, in place.
+    ///
+    /// When the cipher adds letters (e.g. the fillers of Playfair), the extra
+    /// letters are appended in uppercase; when it drops letters, the remaining
+    /// non-letters are kept.
+    Preserve,
+    /// Uppercase letters in groups of the given size, separated by spaces, as
+    /// traditionally transmitted. A size of 0 is the same as [`Formatting::Strip`].
+    Group(usize),
+}
+
+impl Formatting {
+    /// Applies a cipher to the letters of a text and formats the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The input text.
+    /// * `cipher` - The cipher, given the uppercase letters of the text, as by
+    ///   [`crate::vigenere::prepare_string`].
+    ///
+    /// # Returns
+    ///
+    /// The output of the cipher, formatted after the input text.
+    pub fn apply(&self, text: &str, cipher: impl FnOnce(&str) -> String) -> String {
+        self.format(text, &cipher(&letters(text)))
+    }
+
+    /// Applies a fallible cipher to the letters of a text and formats the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The input text.
+    /// * `cipher` - The cipher, given the uppercase letters of the text.
+    ///
+    /// # Returns
+    ///
+    /// The output of the cipher formatted after the input text, or the error of the cipher.
+    pub fn try_apply<E>(
+        &self,
+        text: &str,
+        cipher: impl FnOnce(&str) -> Result<String, E>,
+    ) -> Result<String, E> {
+        Ok(self.format(text, &cipher(&letters(text))?))
+    }
+
+    /// Formats the output of a cipher after its input.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The text given to the cipher.
+    /// * `output` - The output of the cipher. Only its letters are used.
+    pub fn format(&self, input: &str, output: &str) -> String {
+        let mut letters = output.chars().filter(char::is_ascii_alphabetic);
+        match *self {
+            Formatting::Strip | Formatting::Group(0) => {
+                letters.map(|c| c.to_ascii_uppercase()).collect()
+            }
+            Formatting::Group(size) => {
+                let letters: Vec<char> = letters.map(|c| c.to_ascii_uppercase()).collect();
+                letters
+                    .chunks(size)
+                    .map(|group| group.iter().collect::<String>())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+            Formatting::Preserve => {
+                let mut formatted: String = input
+                    .chars()
+                    .filter_map(|c| match c {
+                        'a'..='z' => letters.next().map(|l| l.to_ascii_lowercase()),
+                        'A'..='Z' => letters.next().map(|l| l.to_ascii_uppercase()),
+                        _ => Some(c),
+                    })
+                    .collect();
+                formatted.extend(letters.map(|c| c.to_ascii_uppercase()));
+                formatted
+            }
+        }
+    }
+}
+
+/// Returns the uppercase ASCII letters of a text.
+fn letters(text: &str) -> String {
+    text.chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caesar;
+
+    #[test]
+    fn test_formatting_format() {
+        let input = "Attack at dawn!";
+        assert_eq!(
+            Formatting::Strip.format(input, "dwwdfn dw gdzq"),
+            "DWWDFNDWGDZQ"
+        );
+        assert_eq!(
+            Formatting::Preserve.format(input, "DWWDFNDWGDZQ"),
+            "Dwwdfn dw gdzq!"
+        );
+        assert_eq!(
+            Formatting::Group(5).format(input, "DWWDFNDWGDZQ"),
+            "DWWDF NDWGD ZQ"
+        );
+        assert_eq!(
+            Formatting::Group(0).format(input, "DWWDFNDWGDZQ"),
+            "DWWDFNDWGDZQ"
+        );
+
+        // Extra letters are appended, and missing letters leave the punctuation.
+        assert_eq!(Formatting::Preserve.format("Hi, you", "ABCDEX"), "Ab, cdeX");
+        assert_eq!(Formatting::Preserve.format("Hi, you", "ABC"), "Ab, c");
+    }
+
+    #[test]
+    fn test_formatting_apply() {
+        // Preserving the layout of a Caesar cipher gives the same result as the cipher itself.
+        let text = "The Quick Brown Fox, 1 time.";
+        assert_eq!(
+            Formatting::Preserve.apply(text, |letters| caesar::encrypt(letters, 3)),
+            caesar::encrypt(text, 3)
+        );
+
+        let result: Result<String, &str> = Formatting::Preserve.try_apply(text, |_| Err("failed"));
+        assert_eq!(result, Err("failed"));
+    }
+}
@@ -0,0 +1,267 @@
+//! An implementation of the Four-square cipher.
+//!
+//! The Four-square cipher uses four 5x5 squares arranged in a larger square.
+//! The upper-left and lower-right squares hold the plain alphabet, while the
+//! upper-right and lower-left squares are keyed with two different keywords.
+
+use crate::digraph::{
+    collect_output, create_digraphs, prepare_text, DigraphError, DigraphRules, Substitution,
+};
+use crate::key_square::KeySquare;
+use crate::prelude::*;
+use crate::trace::Step;
+
+/// Modes of operation for the Four-square cipher.
+#[derive(Clone, Copy)]
+enum Mode {
+    Encrypt,
+    Decrypt,
+}
+
+/// Encrypts a given plaintext string using the Four-square cipher.
+///
+/// # Arguments
+///
+/// * `first_key` - The keyword of the upper-right square.
+/// * `second_key` - The keyword of the lower-left square.
+/// * `text` - The input string to be encrypted.
+///
+/// # Returns
+///
+/// The encrypted version of the input string.
+pub fn encrypt(first_key: &str, second_key: &str, text: &str) -> Result<String, DigraphError> {
+    encrypt_with_rules(first_key, second_key, text, &DigraphRules::FOUR_SQUARE)
+}
+
+/// Decrypts a given ciphertext string using the Four-square cipher.
+///
+/// # Arguments
+///
+/// * `first_key` - The keyword of the upper-right square.
+/// * `second_key` - The keyword of the lower-left square.
+/// * `text` - The encrypted input string to be decrypted.
+///
+/// # Returns
+///
+/// The decrypted version of the input string.
+pub fn decrypt(first_key: &str, second_key: &str, text: &str) -> Result<String, DigraphError> {
+    decrypt_with_rules(first_key, second_key, text, &DigraphRules::FOUR_SQUARE)
+}
+
+/// Encrypts a given plaintext string using the Four-square cipher and custom digraph rules.
+///
+/// # Arguments
+///
+/// * `first_key` - The keyword of the upper-right square.
+/// * `second_key` - The keyword of the lower-left square.
+/// * `text` - The input string to be encrypted.
+/// * `rules` - The rules used to prepare the text and split it into digraphs.
+///
+/// # Returns
+///
+/// The encrypted version of the input string.
+pub fn encrypt_with_rules(
+    first_key: &str,
+    second_key: &str,
+    text: &str,
+    rules: &DigraphRules,
+) -> Result<String, DigraphError> {
+    four_square_cipher(Mode::Encrypt, first_key, second_key, text, rules, false)
+        .map(|(text, _)| text)
+}
+
+/// Decrypts a given ciphertext string using the Four-square cipher and custom digraph rules.
+///
+/// # Arguments
+///
+/// * `first_key` - The keyword of the upper-right square.
+/// * `second_key` - The keyword of the lower-left square.
+/// * `text` - The encrypted input string to be decrypted.
+/// * `rules` - The rules used to prepare the text and split it into digraphs.
+///
+/// # Returns
+///
+/// The decrypted version of the input string.
+pub fn decrypt_with_rules(
+    first_key: &str,
+    second_key: &str,
+    text: &str,
+    rules: &DigraphRules,
+) -> Result<String, DigraphError> {
+    four_square_cipher(Mode::Decrypt, first_key, second_key, text, rules, false)
+        .map(|(text, _)| text)
+}
+
+/// Encrypts a given plaintext string using the Four-square cipher, recording every step.
+///
+/// # Arguments
+///
+/// * `first_key` - The keyword of the upper-right square.
+/// * `second_key` - The keyword of the lower-left square.
+/// * `text` - The input string to be encrypted.
+///
+/// # Returns
+///
+/// The encrypted version of the input string, and one step per digraph with
+/// the grid coordinates of the letters in their squares.
+pub fn encrypt_traced(
+    first_key: &str,
+    second_key: &str,
+    text: &str,
+) -> Result<(String, Vec<Step>), DigraphError> {
+    four_square_cipher(
+        Mode::Encrypt,
+        first_key,
+        second_key,
+        text,
+        &DigraphRules::FOUR_SQUARE,
+        true,
+    )
+}
+
+/// Decrypts a given ciphertext string using the Four-square cipher, recording every step.
+///
+/// # Arguments
+///
+/// * `first_key` - The keyword of the upper-right square.
+/// * `second_key` - The keyword of the lower-left square.
+/// * `text` - The encrypted input string to be decrypted.
+///
+/// # Returns
+///
+/// The decrypted version of the input string, and one step per digraph with
+/// the grid coordinates of the letters in their squares.
+pub fn decrypt_traced(
+    first_key: &str,
+    second_key: &str,
+    text: &str,
+) -> Result<(String, Vec<Step>), DigraphError> {
+    four_square_cipher(
+        Mode::Decrypt,
+        first_key,
+        second_key,
+        text,
+        &DigraphRules::FOUR_SQUARE,
+        true,
+    )
+}
+
+/// Encrypts or decrypts a given string based on the specified mode using the Four-square cipher.
+///
+/// # Arguments
+///
+/// * `mode` - The operation mode (`Encrypt` or `Decrypt`).
+/// * `first_key` - The keyword of the upper-right square.
+/// * `second_key` - The keyword of the lower-left square.
+/// * `text` - The input string to be processed.
+/// * `rules` - The rules used to prepare the text and split it into digraphs.
+/// * `traced` - Whether to record the steps.
+///
+/// # Returns
+///
+/// The processed version of the input string based on the mode, and one step
+/// per digraph if `traced` is set.
+fn four_square_cipher(
+    mode: Mode,
+    first_key: &str,
+    second_key: &str,
+    text: &str,
+    rules: &DigraphRules,
+    traced: bool,
+) -> Result<(String, Vec<Step>), DigraphError> {
+    if first_key.is_empty() || second_key.is_empty() || text.is_empty() {
+        return Err(DigraphError::EmptyInput);
+    }
+
+    let plain = KeySquare::new("", rules.alphabet);
+    let upper_right = KeySquare::new(first_key, rules.alphabet);
+    let lower_left = KeySquare::new(second_key, rules.alphabet);
+
+    let (first_square, second_square, first_out, second_out) = match mode {
+        Mode::Encrypt => (&plain, &plain, &upper_right, &lower_left),
+        Mode::Decrypt => (&upper_right, &lower_left, &plain, &plain),
+    };
+
+    let prepared_text = prepare_text(text, rules);
+    let digraphs = create_digraphs(&prepared_text, rules)?;
+    let substitutions = digraphs.iter().map(|&(a, b)| {
+        let (r1, c1) = first_square.position(a).ok_or(DigraphError::CharNotFound)?;
+        let (r2, c2) = second_square
+            .position(b)
+            .ok_or(DigraphError::CharNotFound)?;
+        Ok(Substitution {
+            rule: "rectangle",
+            input: (a, b),
+            output: (first_out.char_at(r1, c2), second_out.char_at(r2, c1)),
+            from: [(r1, c1), (r2, c2)],
+            to: [(r1, c2), (r2, c1)],
+        })
+    });
+    collect_output(substitutions, traced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_four_square_encryption() {
+        assert_eq!(
+            encrypt("example", "keyword", "help me obi wan kenobi"),
+            Ok("FYGMKYHOBXMFKKKIMD".to_string())
+        );
+    }
+
+    #[test]
+    fn test_four_square_decryption() {
+        assert_eq!(
+            decrypt("example", "keyword", "FYGMKYHOBXMFKKKIMD"),
+            Ok("HELPMEOBIWANKENOBI".to_string())
+        );
+    }
+
+    #[test]
+    fn test_four_square_merge_j_rules() {
+        let ciphertext =
+            encrypt_with_rules("example", "keyword", "jump", &DigraphRules::PLAYFAIR).unwrap();
+        assert_eq!(
+            decrypt_with_rules("example", "keyword", &ciphertext, &DigraphRules::PLAYFAIR),
+            Ok("IUMP".to_string())
+        );
+    }
+
+    #[test]
+    fn test_four_square_traced() {
+        let (ciphertext, steps) = encrypt_traced("example", "keyword", "help me").unwrap();
+        assert_eq!(ciphertext, "FYGMKY");
+        assert_eq!(steps.len(), 3);
+        assert_eq!(
+            steps[0],
+            Step::new("HE", "FY", "rectangle")
+                .detail("from", "(2,3) (1,5)")
+                .detail("to", "(2,5) (1,3)")
+        );
+        let (plaintext, _) = decrypt_traced("example", "keyword", &ciphertext).unwrap();
+        assert_eq!(plaintext, "HELPME");
+    }
+
+    #[test]
+    fn test_four_square_empty_input() {
+        assert_eq!(
+            encrypt("", "keyword", "text"),
+            Err(DigraphError::EmptyInput)
+        );
+        assert_eq!(
+            encrypt("example", "keyword", ""),
+            Err(DigraphError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_four_square_invalid_text() {
+        assert_eq!(
+            encrypt("example", "keyword", "1234"),
+            Err(DigraphError::InvalidText)
+        );
+    }
+}
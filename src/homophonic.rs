@@ -0,0 +1,594 @@
+//! An implementation of the homophonic substitution cipher.
+//!
+//! A homophonic substitution replaces each letter with one of several symbols,
+//! its *homophones*, chosen at random every time the letter is encrypted. The
+//! number of homophones of a letter is proportional to its frequency in English,
+//! so that every symbol appears about as often as the others in the ciphertext,
+//! and simple frequency analysis reveals nothing.
+//!
+//! The cipher is still a substitution: every symbol always stands for the same
+//! letter. It is attacked by searching the key that makes the plaintext look
+//! most like English, as was done to solve the Zodiac Killer's 340-character
+//! cipher in 2020. The [`solve`] function implements such a search with
+//! simulated annealing, scoring candidate plaintexts with English trigram
+//! statistics.
+
+use crate::language::{NgramModel, Scorer, ENGLISH_FREQUENCIES};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// The number of letters of the alphabet.
+const LETTERS: usize = 26;
+
+/// Error type for the homophonic substitution cipher.
+#[derive(Error, Debug, PartialEq)]
+pub enum HomophonicError {
+    #[error("At least 26 symbols are needed, got {0}")]
+    TooFewSymbols(usize),
+    #[error("Symbol used more than once: {0}")]
+    DuplicateSymbol(String),
+    #[error("Symbols must be non-empty and contain no whitespace: {0:?}")]
+    InvalidSymbol(String),
+    #[error("Unknown symbol in the ciphertext: {0}")]
+    UnknownSymbol(String),
+    #[error("No homophone for the letter {0}")]
+    NoHomophone(char),
+}
+
+/// The symbols to encrypt with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SymbolSet {
+    /// The numbers from zero, padded with zeros to the same width (e.g. `00` to `99`).
+    Numeric(usize),
+    /// Arbitrary symbols. Each symbol must be non-empty and contain no whitespace.
+    Custom(Vec<String>),
+}
+
+impl SymbolSet {
+    /// Lists the symbols of the set.
+    fn symbols(&self) -> Vec<String> {
+        match self {
+            SymbolSet::Numeric(count) => {
+                let width = count.saturating_sub(1).to_string().len();
+                (0..*count).map(|n| format!("{n:0width$}")).collect()
+            }
+            SymbolSet::Custom(symbols) => symbols.clone(),
+        }
+    }
+}
+
+/// Allocates symbols to the letters in proportion to their frequency in English.
+///
+/// Every letter gets at least one symbol, and the others are shared with the
+/// largest remainder method, so that the counts add up exactly to `total`.
+///
+/// # Arguments
+///
+/// * `total` - The number of symbols to allocate.
+///
+/// # Returns
+///
+/// The number of symbols of each letter, from `A` to `Z`, or an error if there are fewer than 26 symbols.
+pub fn allocate(total: usize) -> Result<[usize; LETTERS], HomophonicError> {
+    if total < LETTERS {
+        return Err(HomophonicError::TooFewSymbols(total));
+    }
+
+    let spare = (total - LETTERS) as f64;
+    let sum: f64 = ENGLISH_FREQUENCIES.iter().sum();
+    let quotas = ENGLISH_FREQUENCIES.map(|frequency| frequency / sum * spare);
+    let mut counts = quotas.map(|quota| 1 + quota.floor() as usize);
+
+    let mut order: Vec<usize> = (0..LETTERS).collect();
+    order.sort_by(|&a, &b| (quotas[b].fract()).total_cmp(&quotas[a].fract()));
+    let allocated: usize = counts.iter().sum();
+    for &letter in order.iter().take(total - allocated) {
+        counts[letter] += 1;
+    }
+
+    Ok(counts)
+}
+
+/// A homophonic substitution key.
+///
+/// With the `serde` feature, the key is serialized as the homophones of each
+/// letter, from `A` to `Z`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "Vec<Vec<String>>", into = "Vec<Vec<String>>")
+)]
+pub struct Homophonic {
+    /// The homophones of each letter, from `A` to `Z`.
+    homophones: Vec<Vec<String>>,
+    /// The letter of each symbol.
+    letters: HashMap<String, char>,
+}
+
+impl Homophonic {
+    /// Creates a random key, allocating the symbols with [`allocate`].
+    ///
+    /// # Arguments
+    ///
+    /// * `symbols` - The symbols to encrypt with.
+    /// * `rng` - The random number generator used to assign the symbols to the letters.
+    ///
+    /// # Returns
+    ///
+    /// The key, or an error if there are too few symbols or a symbol is invalid.
+    pub fn new(symbols: SymbolSet, rng: &mut impl Rng) -> Result<Self, HomophonicError> {
+        let mut symbols = symbols.symbols();
+        let counts = allocate(symbols.len())?;
+        symbols.shuffle(rng);
+
+        let mut symbols = symbols.into_iter();
+        let homophones: Vec<Vec<String>> = counts
+            .iter()
+            .map(|&count| symbols.by_ref().take(count).collect())
+            .collect();
+        Self::from_homophones(homophones)
+    }
+
+    /// Creates a key from the homophones of each letter.
+    ///
+    /// # Arguments
+    ///
+    /// * `homophones` - The symbols of each letter, from `A` to `Z`. A letter
+    ///   without homophones cannot be encrypted. Missing letters have none.
+    ///
+    /// # Returns
+    ///
+    /// The key, or an error if a symbol is invalid or used more than once.
+    pub fn from_homophones<S: AsRef<str>>(
+        homophones: Vec<Vec<S>>,
+    ) -> Result<Self, HomophonicError> {
+        let mut letters = HashMap::new();
+        let mut lists = vec![Vec::new(); LETTERS];
+        for ((letter, symbols), list) in ('A'..='Z').zip(homophones).zip(lists.iter_mut()) {
+            for symbol in symbols {
+                let symbol = symbol.as_ref();
+                if symbol.is_empty() || symbol.contains(char::is_whitespace) {
+                    return Err(HomophonicError::InvalidSymbol(symbol.to_string()));
+                }
+                if letters.insert(symbol.to_string(), letter).is_some() {
+                    return Err(HomophonicError::DuplicateSymbol(symbol.to_string()));
+                }
+                list.push(symbol.to_string());
+            }
+        }
+
+        Ok(Homophonic {
+            homophones: lists,
+            letters,
+        })
+    }
+
+    /// Returns the homophones of a letter.
+    ///
+    /// # Arguments
+    ///
+    /// * `letter` - The letter, in either case.
+    ///
+    /// # Returns
+    ///
+    /// The symbols standing for the letter, empty if `letter` is not an ASCII letter.
+    pub fn homophones(&self, letter: char) -> &[String] {
+        match letter.is_ascii_alphabetic() {
+            true => &self.homophones[(letter.to_ascii_uppercase() as u8 - b'A') as usize],
+            false => &[],
+        }
+    }
+
+    /// Whether all the symbols are single characters, written without separators.
+    fn single_characters(&self) -> bool {
+        self.letters
+            .keys()
+            .all(|symbol| symbol.chars().count() == 1)
+    }
+
+    /// Encrypts the provided plaintext, choosing a random homophone for every letter.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The text to be encrypted. Non-alphabetic characters are dropped.
+    /// * `rng` - The random number generator used to choose the homophones.
+    ///
+    /// # Returns
+    ///
+    /// The ciphertext, or an error if a letter has no homophone. If all the
+    /// symbols are single characters they are concatenated, otherwise they are
+    /// separated by spaces.
+    pub fn encrypt(&self, plaintext: &str, rng: &mut impl Rng) -> Result<String, HomophonicError> {
+        let symbols = plaintext
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .map(|c| {
+                self.homophones(c)
+                    .choose(rng)
+                    .map(String::as_str)
+                    .ok_or(HomophonicError::NoHomophone(c.to_ascii_uppercase()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let separator = if self.single_characters() { "" } else { " " };
+        Ok(symbols.join(separator))
+    }
+
+    /// Decrypts the provided ciphertext.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The symbols to be decrypted. Symbols of several characters
+    ///   must be separated by whitespace.
+    ///
+    /// # Returns
+    ///
+    /// The plaintext in uppercase, or an error if a symbol is not part of the key.
+    pub fn decrypt(&self, ciphertext: &str) -> Result<String, HomophonicError> {
+        let decrypt = |symbol: &str| {
+            self.letters
+                .get(symbol)
+                .copied()
+                .ok_or_else(|| HomophonicError::UnknownSymbol(symbol.to_string()))
+        };
+
+        if self.single_characters() {
+            ciphertext
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| decrypt(c.encode_utf8(&mut [0; 4])))
+                .collect()
+        } else {
+            ciphertext.split_whitespace().map(decrypt).collect()
+        }
+    }
+}
+
+impl TryFrom<Vec<Vec<String>>> for Homophonic {
+    type Error = HomophonicError;
+
+    fn try_from(homophones: Vec<Vec<String>>) -> Result<Self, Self::Error> {
+        Self::from_homophones(homophones)
+    }
+}
+
+impl From<Homophonic> for Vec<Vec<String>> {
+    fn from(homophonic: Homophonic) -> Self {
+        homophonic.homophones
+    }
+}
+
+/// Settings of the simulated annealing search of [`solve`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnealingConfig {
+    /// The number of changes tried in each run.
+    pub iterations: usize,
+    /// The number of independent runs, each from a random key.
+    pub restarts: usize,
+    /// The temperature at the start of each run.
+    pub start_temperature: f64,
+    /// The temperature at the end of each run.
+    pub end_temperature: f64,
+    /// The weight of the entropy of the letters in the score.
+    pub entropy_weight: f64,
+}
+
+impl Default for AnnealingConfig {
+    fn default() -> Self {
+        AnnealingConfig {
+            iterations: 100_000,
+            restarts: 6,
+            start_temperature: 12.0,
+            end_temperature: 0.1,
+            entropy_weight: 2.0,
+        }
+    }
+}
+
+/// The best key found by [`solve`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution {
+    /// The key, mapping every symbol of the ciphertext to a letter.
+    pub key: Homophonic,
+    /// The decrypted text.
+    pub plaintext: String,
+    /// The score of the plaintext (higher is better).
+    pub score: f64,
+}
+
+/// Breaks a homophonic substitution by simulated annealing.
+///
+/// Each run starts from a random key and repeatedly assigns a random letter to
+/// a random symbol. Changes that make the plaintext more English-like are
+/// always kept, and worse ones are kept with a probability that decreases with
+/// the temperature, which allows the search to escape local maxima early on.
+///
+/// The score of a plaintext is the log-probability of its trigrams, plus the
+/// entropy of its letters times their number and `config.entropy_weight`.
+/// Without the entropy term, the search tends to decrypt every symbol as one
+/// of a few frequent letters, e.g. "THETHESE...", whose trigrams are all likely.
+/// Only the trigrams containing the changed symbol are rescored.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext. Symbols are separated by whitespace, or are
+///   single characters if the ciphertext contains no whitespace.
+/// * `config` - The settings of the search.
+/// * `rng` - The random number generator driving the search.
+///
+/// # Returns
+///
+/// The best solution over all the runs.
+pub fn solve(ciphertext: &str, config: &AnnealingConfig, rng: &mut impl Rng) -> Solution {
+    let tokens: Vec<&str> = if ciphertext.trim().contains(char::is_whitespace) {
+        ciphertext.split_whitespace().collect()
+    } else {
+        ciphertext
+            .trim()
+            .char_indices()
+            .map(|(idx, c)| &ciphertext.trim()[idx..idx + c.len_utf8()])
+            .collect()
+    };
+
+    let mut symbols = tokens.clone();
+    symbols.sort_unstable();
+    symbols.dedup();
+    let text: Vec<usize> = tokens
+        .iter()
+        .map(|token| symbols.binary_search(token).unwrap_or_default())
+        .collect();
+
+    // The positions of each symbol, and the trigrams starting at each affected position.
+    let mut occurrences = vec![Vec::new(); symbols.len()];
+    for (position, &symbol) in text.iter().enumerate() {
+        occurrences[symbol].push(position);
+    }
+    let windows: Vec<Vec<usize>> = occurrences
+        .iter()
+        .map(|positions| trigram_starts(positions, text.len()))
+        .collect();
+
+    let model = NgramModel::english(3).expect("trigrams are supported");
+    let mut best_key = vec![0; symbols.len()];
+    let mut best_score = f64::NEG_INFINITY;
+
+    for _ in 0..config.restarts.max(1) {
+        let mut key: Vec<u8> = (0..symbols.len())
+            .map(|_| rng.gen_range(0..LETTERS as u8))
+            .collect();
+        let mut plain: Vec<u8> = text.iter().map(|&symbol| key[symbol]).collect();
+        let mut counts = [0; LETTERS];
+        for &letter in &plain {
+            counts[letter as usize] += 1;
+        }
+        let mut score = model.score_letters(&plain) + config.entropy_weight * entropy(&counts);
+        let mut run_key = key.clone();
+        let mut run_score = score;
+
+        let ratio = config.end_temperature / config.start_temperature;
+        let iterations = if symbols.is_empty() {
+            0
+        } else {
+            config.iterations
+        };
+        for iteration in 0..iterations {
+            let progress = iteration as f64 / config.iterations as f64;
+            let temperature = config.start_temperature * ratio.powf(progress);
+
+            let symbol = rng.gen_range(0..symbols.len());
+            let letter = rng.gen_range(0..LETTERS as u8);
+            let previous = key[symbol];
+            if letter == previous {
+                continue;
+            }
+
+            let old: f64 = windows[symbol]
+                .iter()
+                .map(|&start| model.log_probability(&plain[start..]))
+                .sum();
+            for &position in &occurrences[symbol] {
+                plain[position] = letter;
+            }
+            let new: f64 = windows[symbol]
+                .iter()
+                .map(|&start| model.log_probability(&plain[start..]))
+                .sum();
+
+            let moved = occurrences[symbol].len();
+            let before = entropy(&counts);
+            counts[previous as usize] -= moved;
+            counts[letter as usize] += moved;
+            let delta = new - old + config.entropy_weight * (entropy(&counts) - before);
+
+            if delta >= 0.0 || rng.gen::<f64>() < (delta / temperature).exp() {
+                key[symbol] = letter;
+                score += delta;
+                if score > run_score {
+                    run_score = score;
+                    run_key.clone_from(&key);
+                }
+            } else {
+                for &position in &occurrences[symbol] {
+                    plain[position] = previous;
+                }
+                counts[previous as usize] += moved;
+                counts[letter as usize] -= moved;
+            }
+        }
+
+        if run_score > best_score {
+            best_score = run_score;
+            best_key = run_key;
+        }
+    }
+
+    let mut homophones = vec![Vec::new(); LETTERS];
+    for (symbol, &letter) in symbols.iter().zip(&best_key) {
+        homophones[letter as usize].push(*symbol);
+    }
+    let key = Homophonic::from_homophones(homophones)
+        .expect("the symbols of the ciphertext are distinct and non-empty");
+    let plaintext = text
+        .iter()
+        .map(|&symbol| (best_key[symbol] + b'A') as char)
+        .collect();
+
+    Solution {
+        key,
+        plaintext,
+        score: best_score,
+    }
+}
+
+/// Returns the start of every trigram of the text covering one of the positions, once each.
+fn trigram_starts(positions: &[usize], len: usize) -> Vec<usize> {
+    let mut starts: Vec<usize> = positions
+        .iter()
+        .flat_map(|&p| p.saturating_sub(2)..=p)
+        .filter(|&start| start + 3 <= len)
+        .collect();
+    starts.sort_unstable();
+    starts.dedup();
+    starts
+}
+
+/// Returns the entropy of the letters, in nats, times their number.
+fn entropy(counts: &[usize; LETTERS]) -> f64 {
+    let total: usize = counts.iter().sum();
+    let x_ln_x = |count: usize| match count {
+        0 => 0.0,
+        _ => count as f64 * (count as f64).ln(),
+    };
+    x_ln_x(total) - counts.iter().map(|&count| x_ln_x(count)).sum::<f64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const PLAINTEXT: &str =
+        "When the old lighthouse keeper retired, the village held a small party \
+        on the harbour wall. He had lived in the tower for almost forty years, climbing the narrow \
+        stairs every evening to light the lamp and every morning to put it out again. In all that \
+        time he had seen more storms than he could count, and he had helped to pull many sailors \
+        out of the cold water when their boats were thrown against the rocks. The new lamp would \
+        turn itself on and off without any help, and nobody would need to live in the tower any \
+        more. The children asked him what he would do with his time, and he said that he would \
+        finally learn to swim.";
+
+    #[test]
+    fn test_homophonic_allocate() {
+        let counts = allocate(100).unwrap();
+        assert_eq!(counts.iter().sum::<usize>(), 100);
+        assert!(counts.iter().all(|&count| count >= 1));
+        // E is the most frequent letter, and gets the most homophones.
+        assert_eq!(counts.iter().max(), Some(&counts[4]));
+        assert_eq!(counts[25], 1);
+
+        assert_eq!(allocate(26).unwrap(), [1; 26]);
+        assert_eq!(allocate(25), Err(HomophonicError::TooFewSymbols(25)));
+    }
+
+    #[test]
+    fn test_homophonic_round_trip() {
+        let mut rng = StdRng::seed_from_u64(340);
+        let cipher = Homophonic::new(SymbolSet::Numeric(100), &mut rng).unwrap();
+        assert_eq!(cipher.homophones('e').len(), allocate(100).unwrap()[4]);
+        assert!(cipher.homophones('e').iter().all(|s| s.len() == 2));
+
+        let ciphertext = cipher.encrypt("Attack at dawn", &mut rng).unwrap();
+        assert_eq!(ciphertext.split(' ').count(), 12);
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "ATTACKATDAWN");
+
+        // Repeated letters are spread over several homophones.
+        let ciphertext = cipher.encrypt(&"E".repeat(50), &mut rng).unwrap();
+        let mut symbols: Vec<&str> = ciphertext.split(' ').collect();
+        symbols.sort_unstable();
+        symbols.dedup();
+        assert!(symbols.len() > 1);
+    }
+
+    #[test]
+    fn test_homophonic_custom_symbols() {
+        let symbols: Vec<String> = ('!'..='~').map(String::from).collect();
+        let mut rng = StdRng::seed_from_u64(1969);
+        let cipher = Homophonic::new(SymbolSet::Custom(symbols), &mut rng).unwrap();
+
+        let ciphertext = cipher.encrypt("I like killing people", &mut rng).unwrap();
+        assert_eq!(ciphertext.chars().count(), 18);
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "ILIKEKILLINGPEOPLE");
+
+        let cipher = Homophonic::from_homophones(vec![vec!["1", "2"], vec!["3"]]).unwrap();
+        assert_eq!(cipher.decrypt("2 1 3").unwrap(), "AAB");
+        assert_eq!(
+            cipher.encrypt("cab", &mut rng),
+            Err(HomophonicError::NoHomophone('C'))
+        );
+        assert_eq!(
+            cipher.decrypt("4"),
+            Err(HomophonicError::UnknownSymbol("4".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_homophonic_invalid_symbols() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(
+            Homophonic::new(SymbolSet::Numeric(20), &mut rng),
+            Err(HomophonicError::TooFewSymbols(20))
+        );
+        assert_eq!(
+            Homophonic::from_homophones(vec![vec!["1"], vec!["1"]]),
+            Err(HomophonicError::DuplicateSymbol("1".to_string()))
+        );
+        assert_eq!(
+            Homophonic::from_homophones(vec![vec!["1 2"]]),
+            Err(HomophonicError::InvalidSymbol("1 2".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_homophonic_trigram_starts() {
+        assert_eq!(trigram_starts(&[3, 4], 10), vec![1, 2, 3, 4]);
+        assert_eq!(trigram_starts(&[0, 1, 9], 10), vec![0, 1, 7]);
+        assert_eq!(trigram_starts(&[1], 2), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_homophonic_solve() {
+        let mut rng = StdRng::seed_from_u64(2020);
+        let cipher = Homophonic::new(SymbolSet::Numeric(60), &mut rng).unwrap();
+        let ciphertext = cipher.encrypt(PLAINTEXT, &mut rng).unwrap();
+        let expected = cipher.decrypt(&ciphertext).unwrap();
+
+        let solution = solve(&ciphertext, &AnnealingConfig::default(), &mut rng);
+        let correct = solution
+            .plaintext
+            .chars()
+            .zip(expected.chars())
+            .filter(|(a, b)| a == b)
+            .count();
+        assert_eq!(solution.plaintext.len(), expected.len());
+        assert!(correct * 10 >= expected.len() * 9, "{}", solution.plaintext);
+        assert_eq!(
+            solution.key.decrypt(&ciphertext).unwrap(),
+            solution.plaintext
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_homophonic_serde() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let cipher = Homophonic::new(SymbolSet::Numeric(100), &mut rng).unwrap();
+        let json = serde_json::to_string(&cipher).unwrap();
+        assert!(json.starts_with("[["));
+        assert_eq!(serde_json::from_str::<Homophonic>(&json).unwrap(), cipher);
+        assert!(serde_json::from_str::<Homophonic>(r#"[["1"], ["1"]]"#).is_err());
+    }
+}
@@ -0,0 +1,490 @@
+//! Identification of the cipher type of a ciphertext.
+//!
+//! Like the classifiers of the American Cryptogram Association (ACA) and NCID,
+//! [`identify`] computes statistics of the ciphertext ([`Features`]) and
+//! compares them with those expected from each family of ciphers:
+//!
+//! * the symbols used (letters, digits, Morse signals, `A` and `B` only...);
+//! * the index of coincidence, which stays at the level of the language for
+//!   substitutions and transpositions and drops for polyalphabetic ciphers;
+//! * the periodic index of coincidence, which recovers the level of the
+//!   language for periodic ciphers when the text is split by the right period;
+//! * the similarity of the letter frequencies with English, which stays high
+//!   for transpositions only;
+//! * the length parity and the absence of doubled letters in a digraph, which
+//!   characterize Playfair.
+//!
+//! Each statistic contributes a Gaussian log-likelihood around its expected
+//! value, and the likelihoods are normalized into probabilities. The model is
+//! tuned for English plaintexts of a few hundred letters; short ciphertexts
+//! give flatter probabilities.
+
+use crate::language::{index_of_coincidence, ENGLISH_FREQUENCIES};
+use std::fmt;
+
+const ALPHABET_SIZE: usize = 26;
+
+/// The largest period tried for the periodic index of coincidence.
+const MAX_PERIOD: usize = 20;
+
+/// The smallest number of letters per column for the periodic index of coincidence.
+const MIN_COLUMN: usize = 20;
+
+/// The length above which the statistics are trusted with their nominal spread.
+const REFERENCE_LENGTH: f64 = 200.0;
+
+/// The families of ciphers that can be told apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CipherType {
+    /// Monoalphabetic substitutions: `caesar`, `rot13`, `affine` and keyed alphabets.
+    Monoalphabetic,
+    /// Transpositions, which keep the letters of the plaintext: `nihilist` transposition.
+    Transposition,
+    /// Periodic polyalphabetic ciphers: `vigenere`, `beaufort`, `porta` and `quagmire`.
+    Periodic,
+    /// Aperiodic polyalphabetic ciphers and machines: `otp`, `solitaire`,
+    /// `chaocipher`, `enigma`, and the autokey and running key Vigenère.
+    Aperiodic,
+    /// The `playfair` cipher.
+    Playfair,
+    /// Other digraphic ciphers: `two_square` and `four_square`.
+    Digraphic,
+    /// The `polybius_square` cipher.
+    Polybius,
+    /// The `nihilist` substitution.
+    Nihilist,
+    /// The straddling checkerboard and the VIC cipher: `straddling_checkerboard` and `vic`.
+    Checkerboard,
+    /// Homophonic substitutions: `homophonic`.
+    Homophonic,
+    /// The `bacon` cipher.
+    Bacon,
+    /// Plain Morse code: `morse`.
+    Morse,
+    /// The Morbit cipher: `morse::encrypt_morbit`.
+    Morbit,
+}
+
+impl CipherType {
+    /// Returns the modules of the crate implementing this family.
+    pub fn modules(&self) -> &'static [&'static str] {
+        match self {
+            CipherType::Monoalphabetic => &["caesar", "rot13", "affine"],
+            CipherType::Transposition => &["nihilist"],
+            CipherType::Periodic => &["vigenere", "beaufort", "porta", "quagmire"],
+            CipherType::Aperiodic => &["otp", "solitaire", "chaocipher", "enigma", "vigenere"],
+            CipherType::Playfair => &["playfair"],
+            CipherType::Digraphic => &["two_square", "four_square"],
+            CipherType::Polybius => &["polybius_square"],
+            CipherType::Nihilist => &["nihilist"],
+            CipherType::Checkerboard => &["straddling_checkerboard", "vic"],
+            CipherType::Homophonic => &["homophonic"],
+            CipherType::Bacon => &["bacon"],
+            CipherType::Morse => &["morse"],
+            CipherType::Morbit => &["morse"],
+        }
+    }
+}
+
+impl fmt::Display for CipherType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CipherType::Monoalphabetic => "Monoalphabetic substitution",
+            CipherType::Transposition => "Transposition",
+            CipherType::Periodic => "Periodic polyalphabetic",
+            CipherType::Aperiodic => "Aperiodic polyalphabetic",
+            CipherType::Playfair => "Playfair",
+            CipherType::Digraphic => "Digraphic substitution",
+            CipherType::Polybius => "Polybius square",
+            CipherType::Nihilist => "Nihilist substitution",
+            CipherType::Checkerboard => "Straddling checkerboard",
+            CipherType::Homophonic => "Homophonic substitution",
+            CipherType::Bacon => "Bacon",
+            CipherType::Morse => "Morse code",
+            CipherType::Morbit => "Morbit",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A cipher type and its probability.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hypothesis {
+    pub cipher: CipherType,
+    pub probability: f64,
+}
+
+/// Statistics of a ciphertext.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Features {
+    /// The number of letters.
+    pub letters: usize,
+    /// The number of digits.
+    pub digits: usize,
+    /// The number of other characters, whitespace excepted.
+    pub others: usize,
+    /// The distinct characters, whitespace excepted, in uppercase and in order.
+    pub alphabet: Vec<char>,
+    /// The index of coincidence of the letters, times 26 (1.0 for random text, about 1.73 for English).
+    pub ioc: f64,
+    /// The best average index of coincidence, times 26, of the letters split in columns of at least 20 letters.
+    pub periodic_ioc: f64,
+    /// The period giving `periodic_ioc`, 1 if no period does better than the whole text.
+    pub period: usize,
+    /// The cosine similarity of the letter frequencies with those of English.
+    pub english_similarity: f64,
+    /// Whether the number of letters is even.
+    pub even_length: bool,
+    /// Whether a digraph (letters `2k` and `2k + 1`) is made of a doubled letter.
+    pub doubled_digraph: bool,
+    /// The smallest and largest number, if the ciphertext is made of whitespace-separated numbers.
+    pub number_range: Option<(u32, u32)>,
+    /// The number of distinct whitespace-separated numbers.
+    pub distinct_numbers: usize,
+}
+
+impl Features {
+    /// Computes the statistics of a ciphertext.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The ciphertext to analyze.
+    pub fn new(ciphertext: &str) -> Self {
+        let letters: Vec<u8> = ciphertext
+            .bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .map(|b| b.to_ascii_uppercase() - b'A')
+            .collect();
+        let digits = ciphertext.chars().filter(char::is_ascii_digit).count();
+        let others = ciphertext
+            .chars()
+            .filter(|c| !c.is_ascii_alphanumeric() && !c.is_whitespace())
+            .count();
+
+        let mut alphabet: Vec<char> = ciphertext
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        alphabet.sort_unstable();
+        alphabet.dedup();
+
+        let ioc = index_of_coincidence(&letters) * ALPHABET_SIZE as f64;
+        let (period, periodic_ioc) = (2..=MAX_PERIOD.min(letters.len() / MIN_COLUMN))
+            .map(|period| (period, periodic_ioc(&letters, period)))
+            .fold((1, ioc), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+
+        let numbers: Option<Vec<u32>> = ciphertext
+            .split_whitespace()
+            .map(|token| token.parse().ok())
+            .collect();
+        let numbers = numbers.filter(|numbers| numbers.len() > 1);
+        let number_range = numbers.as_ref().map(|numbers| {
+            let min = numbers.iter().copied().min().unwrap_or_default();
+            (min, numbers.iter().copied().max().unwrap_or_default())
+        });
+        let distinct_numbers = numbers.map_or(0, |mut numbers| {
+            numbers.sort_unstable();
+            numbers.dedup();
+            numbers.len()
+        });
+
+        Features {
+            letters: letters.len(),
+            digits,
+            others,
+            alphabet,
+            ioc,
+            periodic_ioc,
+            period,
+            english_similarity: english_similarity(&letters),
+            even_length: letters.len().is_multiple_of(2),
+            doubled_digraph: letters
+                .chunks(2)
+                .any(|pair| pair.len() == 2 && pair[0] == pair[1]),
+            number_range,
+            distinct_numbers,
+        }
+    }
+
+    /// Whether all the characters of the ciphertext, whitespace excepted, are in `symbols`.
+    fn only(&self, symbols: &str) -> bool {
+        !self.alphabet.is_empty() && self.alphabet.iter().all(|&c| symbols.contains(c))
+    }
+
+    /// Returns the log-likelihood of the features under each cipher type.
+    fn log_likelihoods(&self) -> Vec<(CipherType, f64)> {
+        use CipherType::*;
+
+        // Short texts have noisier statistics.
+        let spread = (REFERENCE_LENGTH / self.letters.max(1) as f64)
+            .sqrt()
+            .max(1.0);
+        let gaussian = |x: f64, mean: f64, sigma: f64| {
+            let z = (x - mean) / (sigma * spread);
+            -z * z / 2.0
+        };
+
+        if self.only(".-/") {
+            return vec![(Morse, 0.0)];
+        }
+        if self.only("AB") {
+            let penalty = if self.letters.is_multiple_of(5) {
+                0.0
+            } else {
+                -2.0
+            };
+            return vec![(Bacon, penalty)];
+        }
+
+        if self.letters == 0 && self.others == 0 && self.digits > 0 {
+            let mut likelihoods = Vec::new();
+            if let Some((min, max)) = self.number_range {
+                if min >= 22 && max <= 110 {
+                    likelihoods.push((Nihilist, 0.0));
+                }
+                if self.distinct_numbers > ALPHABET_SIZE {
+                    likelihoods.push((Homophonic, -1.0));
+                }
+            }
+            if self.only("12345") {
+                let penalty = if self.digits.is_multiple_of(2) {
+                    0.0
+                } else {
+                    -4.0
+                };
+                likelihoods.push((Polybius, penalty));
+            }
+            if self.only("123456789") && self.alphabet.len() > 5 {
+                likelihoods.push((Morbit, -0.5));
+            }
+            if self.alphabet.contains(&'0') {
+                likelihoods.push((Checkerboard, -0.5));
+                let penalty = if self.digits.is_multiple_of(2) {
+                    -2.0
+                } else {
+                    -6.0
+                };
+                likelihoods.push((Homophonic, penalty));
+            }
+            return likelihoods;
+        }
+
+        // Punctuation kept from the plaintext does not count as symbols.
+        let symbols = self
+            .alphabet
+            .iter()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .count();
+        if symbols > ALPHABET_SIZE {
+            return vec![(Homophonic, 0.0)];
+        }
+        if self.letters == 0 {
+            return Vec::new();
+        }
+
+        // Playfair never enciphers a digraph into a doubled letter, which
+        // happens to the other ciphers at the rate of their coincidences.
+        let pairs = (self.letters / 2) as f64;
+        let undoubled = |kappa: f64| {
+            if self.doubled_digraph {
+                0.0
+            } else {
+                pairs * (1.0 - kappa / ALPHABET_SIZE as f64).ln()
+            }
+        };
+
+        let mut likelihoods = vec![
+            (
+                Monoalphabetic,
+                gaussian(self.ioc, 1.73, 0.15)
+                    + gaussian(self.english_similarity, 0.6, 0.15)
+                    + undoubled(1.73),
+            ),
+            (
+                Transposition,
+                gaussian(self.ioc, 1.73, 0.15)
+                    + gaussian(self.english_similarity, 0.97, 0.03)
+                    + undoubled(1.73),
+            ),
+            (
+                Aperiodic,
+                gaussian(self.ioc, 1.0, 0.08)
+                    + gaussian(self.periodic_ioc, 1.1, 0.15)
+                    + undoubled(1.0),
+            ),
+        ];
+        if self.period > 1 {
+            likelihoods.push((
+                Periodic,
+                gaussian(self.ioc, 1.2, 0.2)
+                    + gaussian(self.periodic_ioc, 1.72, 0.12)
+                    + undoubled(1.2),
+            ));
+        }
+        // Digraphic ciphers use a 5x5 square, missing a letter.
+        let square = self
+            .alphabet
+            .iter()
+            .filter(|c| c.is_ascii_uppercase())
+            .count()
+            < ALPHABET_SIZE;
+        if self.even_length && square {
+            let likelihood = gaussian(self.ioc, 1.4, 0.2) + gaussian(self.periodic_ioc, 1.5, 0.2);
+            if !self.doubled_digraph && !self.alphabet.contains(&'J') {
+                likelihoods.push((Playfair, likelihood));
+            }
+            likelihoods.push((Digraphic, likelihood - 0.5 + undoubled(1.4)));
+        }
+        likelihoods
+    }
+}
+
+/// Identifies the cipher type of a ciphertext.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext to identify.
+///
+/// # Returns
+///
+/// The possible cipher types, from the most to the least likely, with
+/// probabilities summing to 1. The list is empty if no cipher type of the crate
+/// can produce the ciphertext.
+pub fn identify(ciphertext: &str) -> Vec<Hypothesis> {
+    let likelihoods = Features::new(ciphertext).log_likelihoods();
+    let best = likelihoods
+        .iter()
+        .map(|&(_, likelihood)| likelihood)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let total: f64 = likelihoods
+        .iter()
+        .map(|&(_, likelihood)| (likelihood - best).exp())
+        .sum();
+
+    let mut hypotheses: Vec<Hypothesis> = likelihoods
+        .into_iter()
+        .map(|(cipher, likelihood)| Hypothesis {
+            cipher,
+            probability: (likelihood - best).exp() / total,
+        })
+        .collect();
+    hypotheses.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+    hypotheses
+}
+
+/// Computes the average index of coincidence, times 26, of the columns of a text.
+fn periodic_ioc(letters: &[u8], period: usize) -> f64 {
+    let total: f64 = (0..period)
+        .map(|offset| {
+            let column: Vec<u8> = letters
+                .iter()
+                .skip(offset)
+                .step_by(period)
+                .copied()
+                .collect();
+            index_of_coincidence(&column)
+        })
+        .sum();
+    total / period as f64 * ALPHABET_SIZE as f64
+}
+
+/// Computes the cosine similarity of the letter frequencies with those of English.
+fn english_similarity(letters: &[u8]) -> f64 {
+    let mut counts = [0.0; ALPHABET_SIZE];
+    for &letter in letters {
+        counts[letter as usize] += 1.0;
+    }
+    let dot: f64 = counts
+        .iter()
+        .zip(ENGLISH_FREQUENCIES)
+        .map(|(count, frequency)| count * frequency)
+        .sum();
+    let norm = |values: &[f64]| values.iter().map(|v| v * v).sum::<f64>().sqrt();
+    match norm(&counts) {
+        0.0 => 0.0,
+        counts_norm => dot / (counts_norm * norm(&ENGLISH_FREQUENCIES)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        caesar, chaocipher, four_square, morse, nihilist, playfair, polybius_square, vigenere,
+    };
+
+    const PLAINTEXT: &str = "The old observatory stood on a hill above the town, and on clear \
+        nights the astronomer opened the great dome and pointed the telescope at the sky. \
+        She kept careful records of every star she measured, writing the positions in a \
+        thick book with a green cover. Over many years the book filled with numbers, and \
+        when she compared the oldest pages with the newest ones, she found that a few of \
+        the stars had moved. Nobody in the town understood why this mattered so much to \
+        her, but they were proud of the observatory all the same, and the children liked \
+        to visit it on the first night of every month.";
+
+    fn top(ciphertext: &str) -> CipherType {
+        identify(ciphertext)[0].cipher
+    }
+
+    #[test]
+    fn test_identify_substitutions() {
+        assert_eq!(
+            top(&caesar::encrypt(PLAINTEXT, 7)),
+            CipherType::Monoalphabetic
+        );
+        assert_eq!(
+            top(&nihilist::encrypt_transposition(PLAINTEXT, "TELESCOPE").unwrap()),
+            CipherType::Transposition
+        );
+    }
+
+    #[test]
+    fn test_identify_polyalphabetic() {
+        let ciphertext = vigenere::encrypt(&vigenere::prepare_string(PLAINTEXT), "ASTRONOMY");
+        let features = Features::new(&ciphertext);
+        assert_eq!(features.period % 9, 0);
+        assert_eq!(top(&ciphertext), CipherType::Periodic);
+
+        let chaocipher = chaocipher::Chaocipher::keyed("STAR", "GAZER");
+        assert_eq!(top(&chaocipher.encrypt(PLAINTEXT)), CipherType::Aperiodic);
+    }
+
+    #[test]
+    fn test_identify_playfair() {
+        let ciphertext = playfair::encrypt("OBSERVATORY", PLAINTEXT).unwrap();
+        let hypotheses = identify(&ciphertext);
+        assert_eq!(hypotheses[0].cipher, CipherType::Playfair);
+        assert_eq!(
+            top(&four_square::encrypt("EXAMPLE", "KEYWORD", PLAINTEXT).unwrap()),
+            CipherType::Digraphic
+        );
+        let total: f64 = hypotheses.iter().map(|h| h.probability).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_identify_symbols() {
+        assert_eq!(
+            top(&polybius_square::encrypt("the old observatory")),
+            CipherType::Polybius
+        );
+        assert_eq!(
+            top(&nihilist::encrypt(PLAINTEXT, "ZEBRAS", "RUSSIAN").unwrap()),
+            CipherType::Nihilist
+        );
+        assert_eq!(top(&morse::encode("SOS").unwrap()), CipherType::Morse);
+        assert_eq!(
+            top(&morse::encrypt_morbit("the old observatory", "WISECRACK").unwrap()),
+            CipherType::Morbit
+        );
+        assert_eq!(top("AABBA BABAA ABBAB"), CipherType::Bacon);
+        assert!(identify("").is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+//! A keyed 5x5 square of letters, shared by the digraphic ciphers.
+//!
+//! The square is filled with the distinct letters of a keyword, followed by the
+//! remaining letters of the alphabet in their natural order. Since a 5x5 square
+//! only has room for 25 letters, one letter of the alphabet has to be dropped,
+//! which is described by [`SquareAlphabet`].
+
+use crate::prelude::*;
+
+/// The size (number of rows and columns) of the square.
+pub const SIZE: usize = 5;
+
+/// Describes how the 26 letters of the alphabet are fitted into the 25 cells of the square.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SquareAlphabet {
+    /// `J` is merged into `I` (the usual Playfair convention).
+    #[default]
+    MergeJ,
+    /// `Q` is left out of the square altogether.
+    OmitQ,
+}
+
+impl SquareAlphabet {
+    /// Returns the 25 letters of the square in their natural order.
+    pub fn letters(self) -> &'static str {
+        match self {
+            SquareAlphabet::MergeJ => "ABCDEFGHIKLMNOPQRSTUVWXYZ",
+            SquareAlphabet::OmitQ => "ABCDEFGHIJKLMNOPRSTUVWXYZ",
+        }
+    }
+
+    /// Normalizes a character to a letter of this alphabet.
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - The character to be normalized.
+    ///
+    /// # Returns
+    ///
+    /// The uppercase letter that represents `c` in the square, or `None` if `c`
+    /// is not an ASCII letter or has no place in the square.
+    pub fn normalize(self, c: char) -> Option<char> {
+        match (self, c.to_ascii_uppercase()) {
+            (_, c) if !c.is_ascii_uppercase() => None,
+            (SquareAlphabet::MergeJ, 'J') => Some('I'),
+            (SquareAlphabet::OmitQ, 'Q') => None,
+            (_, c) => Some(c),
+        }
+    }
+}
+
+/// A 5x5 square filled with a keyword followed by the rest of the alphabet.
+///
+/// With the `serde` feature, the square is serialized as its 25 letters, row
+/// by row, and its alphabet, e.g. `{"letters": "PLAYF...", "alphabet": "MergeJ"}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "SquareRepr", into = "SquareRepr")
+)]
+pub struct KeySquare {
+    cells: [[char; SIZE]; SIZE],
+    alphabet: SquareAlphabet,
+}
+
+impl KeySquare {
+    /// Creates a new key square from the given keyword.
+    ///
+    /// Characters of the keyword that have no place in the square are ignored,
+    /// as are repeated letters.
+    ///
+    /// # Arguments
+    ///
+    /// * `keyword` - The keyword used to fill the beginning of the square.
+    /// * `alphabet` - The way the alphabet is fitted into the square.
+    ///
+    /// # Returns
+    ///
+    /// The keyed square.
+    pub fn new(keyword: &str, alphabet: SquareAlphabet) -> Self {
+        let mut cells = [[' '; SIZE]; SIZE];
+        let mut seen = [false; 26];
+        let letters = keyword
+            .chars()
+            .filter_map(|c| alphabet.normalize(c))
+            .chain(alphabet.letters().chars())
+            .filter(|&c| !core::mem::replace(&mut seen[(c as u8 - b'A') as usize], true));
+
+        for (idx, c) in letters.enumerate() {
+            cells[idx / SIZE][idx % SIZE] = c;
+        }
+
+        KeySquare { cells, alphabet }
+    }
+
+    /// Creates a key square from its 25 letters.
+    ///
+    /// # Arguments
+    ///
+    /// * `letters` - The letters of the square, row by row. Every letter of the
+    ///   alphabet must appear exactly once.
+    /// * `alphabet` - The way the alphabet is fitted into the square.
+    ///
+    /// # Returns
+    ///
+    /// The key square, or `None` if `letters` is not a permutation of the alphabet.
+    pub fn from_letters(letters: &str, alphabet: SquareAlphabet) -> Option<Self> {
+        let mut chars = letters.chars().map(|c| c.to_ascii_uppercase());
+        let mut cells = [[' '; SIZE]; SIZE];
+        for (idx, c) in chars.by_ref().take(SIZE * SIZE).enumerate() {
+            if alphabet.normalize(c) != Some(c) || cells.iter().flatten().any(|&cell| cell == c) {
+                return None;
+            }
+            cells[idx / SIZE][idx % SIZE] = c;
+        }
+        if chars.next().is_some() || cells[SIZE - 1][SIZE - 1] == ' ' {
+            return None;
+        }
+
+        Some(KeySquare { cells, alphabet })
+    }
+
+    /// Returns the 25 letters of the square, row by row.
+    pub fn letters(&self) -> String {
+        self.cells.iter().flatten().collect()
+    }
+
+    /// Returns the way the alphabet is fitted into this square.
+    pub fn alphabet(&self) -> SquareAlphabet {
+        self.alphabet
+    }
+
+    /// Returns the rows of the square.
+    pub fn rows(&self) -> &[[char; SIZE]; SIZE] {
+        &self.cells
+    }
+
+    /// Returns the letter at the given row and column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` or `col` is not smaller than [`SIZE`].
+    pub fn char_at(&self, row: usize, col: usize) -> char {
+        self.cells[row][col]
+    }
+
+    /// Finds the position of a character in the square.
+    ///
+    /// # Arguments
+    ///
+    /// * `ch` - The character to find. It is normalized to the square's alphabet first.
+    ///
+    /// # Returns
+    ///
+    /// The `(row, column)` of the character, or `None` if it is not in the square.
+    pub fn position(&self, ch: char) -> Option<(usize, usize)> {
+        let ch = self.alphabet.normalize(ch)?;
+        self.cells
+            .iter()
+            .enumerate()
+            .find_map(|(i, row)| row.iter().position(|&cell| cell == ch).map(|j| (i, j)))
+    }
+}
+
+impl Default for KeySquare {
+    fn default() -> Self {
+        KeySquare::new("", SquareAlphabet::default())
+    }
+}
+
+/// The serialized form of a [`KeySquare`].
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SquareRepr {
+    letters: String,
+    #[serde(default)]
+    alphabet: SquareAlphabet,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SquareRepr> for KeySquare {
+    type Error = &'static str;
+
+    fn try_from(repr: SquareRepr) -> Result<Self, Self::Error> {
+        KeySquare::from_letters(&repr.letters, repr.alphabet)
+            .ok_or("the letters of a key square must be a permutation of its alphabet")
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<KeySquare> for SquareRepr {
+    fn from(square: KeySquare) -> Self {
+        SquareRepr {
+            letters: square.letters(),
+            alphabet: square.alphabet,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_square_new() {
+        let square = KeySquare::new("PLAYFAIR EXAMPLE", SquareAlphabet::MergeJ);
+        assert_eq!(
+            square.rows(),
+            &[
+                ['P', 'L', 'A', 'Y', 'F'],
+                ['I', 'R', 'E', 'X', 'M'],
+                ['B', 'C', 'D', 'G', 'H'],
+                ['K', 'N', 'O', 'Q', 'S'],
+                ['T', 'U', 'V', 'W', 'Z'],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_key_square_omit_q() {
+        let square = KeySquare::new("KEYWORD", SquareAlphabet::OmitQ);
+        assert_eq!(square.rows()[3], ['L', 'M', 'N', 'P', 'S']);
+        assert_eq!(square.position('Q'), None);
+        assert_eq!(square.position('j'), Some((2, 4)));
+    }
+
+    #[test]
+    fn test_key_square_position() {
+        let square = KeySquare::default();
+        assert_eq!(square.position('A'), Some((0, 0)));
+        assert_eq!(square.position('j'), Some((1, 3)));
+        assert_eq!(square.position('z'), Some((4, 4)));
+        assert_eq!(square.position('1'), None);
+        assert_eq!(square.char_at(1, 3), 'I');
+    }
+
+    #[test]
+    fn test_key_square_from_letters() {
+        let square = KeySquare::new("PLAYFAIR EXAMPLE", SquareAlphabet::MergeJ);
+        assert_eq!(square.letters(), "PLAYFIREXMBCDGHKNOQSTUVWZ");
+        assert_eq!(
+            KeySquare::from_letters(&square.letters(), SquareAlphabet::MergeJ),
+            Some(square)
+        );
+
+        let alphabet = SquareAlphabet::MergeJ;
+        assert_eq!(KeySquare::from_letters("ABCDE", alphabet), None);
+        assert_eq!(
+            KeySquare::from_letters("ABCDEFGHJKLMNOPQRSTUVWXYZ", alphabet),
+            None
+        );
+        assert_eq!(
+            KeySquare::from_letters("AACDEFGHIKLMNOPQRSTUVWXYZ", alphabet),
+            None
+        );
+        assert_eq!(
+            KeySquare::from_letters("ABCDEFGHIKLMNOPQRSTUVWXYZA", alphabet),
+            None
+        );
+    }
+}
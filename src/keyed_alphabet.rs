@@ -0,0 +1,192 @@
+//! A keyed (mixed) 26-letter alphabet.
+//!
+//! A keyed alphabet starts with the distinct letters of a keyword, followed by
+//! the remaining letters of the alphabet in their natural order. Keyed alphabets
+//! are the building block of many classical ciphers, such as the Quagmires.
+
+use crate::prelude::*;
+use core::fmt;
+
+const ALPHABET_SIZE: usize = 26;
+
+/// A permutation of the 26 uppercase letters of the alphabet.
+///
+/// With the `serde` feature, the alphabet is serialized as its 26 letters, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct KeyedAlphabet {
+    letters: [u8; ALPHABET_SIZE],
+    positions: [u8; ALPHABET_SIZE],
+}
+
+impl KeyedAlphabet {
+    /// Creates a new keyed alphabet from the given keyword.
+    ///
+    /// Non-alphabetic characters and repeated letters of the keyword are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `keyword` - The keyword used to key the alphabet.
+    ///
+    /// # Returns
+    ///
+    /// The keyed alphabet.
+    pub fn new(keyword: &str) -> Self {
+        let mut seen = [false; ALPHABET_SIZE];
+        let mut letters = [0u8; ALPHABET_SIZE];
+        let mut len = 0;
+
+        let candidates = keyword
+            .bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .map(|b| b.to_ascii_uppercase() - b'A')
+            .chain(0..ALPHABET_SIZE as u8);
+        for letter in candidates {
+            if !seen[letter as usize] {
+                seen[letter as usize] = true;
+                letters[len] = letter;
+                len += 1;
+            }
+        }
+
+        Self::from_indices(letters)
+    }
+
+    /// Creates the straight (unkeyed) alphabet `A` to `Z`.
+    pub fn straight() -> Self {
+        Self::new("")
+    }
+
+    /// Creates a keyed alphabet from a complete permutation of the 26 letters.
+    ///
+    /// # Arguments
+    ///
+    /// * `letters` - A string containing every letter of the alphabet exactly once.
+    ///
+    /// # Returns
+    ///
+    /// The keyed alphabet, or `None` if `letters` is not a permutation of the alphabet.
+    pub fn from_permutation(letters: &str) -> Option<Self> {
+        if letters.len() != ALPHABET_SIZE {
+            return None;
+        }
+
+        let mut seen = [false; ALPHABET_SIZE];
+        let mut indices = [0u8; ALPHABET_SIZE];
+        for (idx, b) in letters.bytes().enumerate() {
+            if !b.is_ascii_alphabetic() {
+                return None;
+            }
+            let letter = b.to_ascii_uppercase() - b'A';
+            if core::mem::replace(&mut seen[letter as usize], true) {
+                return None;
+            }
+            indices[idx] = letter;
+        }
+
+        Some(Self::from_indices(indices))
+    }
+
+    /// Builds the alphabet from the letter indices (`A` = 0) in keyed order.
+    fn from_indices(letters: [u8; ALPHABET_SIZE]) -> Self {
+        let mut positions = [0u8; ALPHABET_SIZE];
+        for (idx, &letter) in letters.iter().enumerate() {
+            positions[letter as usize] = idx as u8;
+        }
+        KeyedAlphabet { letters, positions }
+    }
+
+    /// Returns the position of a letter in the keyed alphabet.
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - The letter to find (in either case).
+    ///
+    /// # Returns
+    ///
+    /// The position of the letter, or `None` if `c` is not an ASCII letter.
+    pub fn index_of(&self, c: char) -> Option<usize> {
+        if c.is_ascii_alphabetic() {
+            let letter = c.to_ascii_uppercase() as usize - 'A' as usize;
+            Some(self.positions[letter] as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the letter at the given position of the keyed alphabet.
+    ///
+    /// The position wraps around, so `char_at(26)` is the same as `char_at(0)`.
+    pub fn char_at(&self, idx: usize) -> char {
+        (self.letters[idx % ALPHABET_SIZE] + b'A') as char
+    }
+
+    /// Returns an iterator over the letters of the keyed alphabet, in order.
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.letters.iter().map(|&letter| (letter + b'A') as char)
+    }
+}
+
+impl Default for KeyedAlphabet {
+    fn default() -> Self {
+        Self::straight()
+    }
+}
+
+impl fmt::Display for KeyedAlphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chars().try_for_each(|c| write!(f, "{}", c))
+    }
+}
+
+impl TryFrom<String> for KeyedAlphabet {
+    type Error = &'static str;
+
+    fn try_from(letters: String) -> Result<Self, Self::Error> {
+        Self::from_permutation(&letters).ok_or("a keyed alphabet must be a permutation of A to Z")
+    }
+}
+
+impl From<KeyedAlphabet> for String {
+    fn from(alphabet: KeyedAlphabet) -> Self {
+        alphabet.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyed_alphabet_new() {
+        let alphabet = KeyedAlphabet::new("Automobile");
+        assert_eq!(alphabet.to_string(), "AUTOMBILECDFGHJKNPQRSVWXYZ");
+        assert_eq!(alphabet.index_of('h'), Some(13));
+        assert_eq!(alphabet.char_at(13), 'H');
+        assert_eq!(alphabet.char_at(26), 'A');
+        assert_eq!(alphabet.index_of('!'), None);
+    }
+
+    #[test]
+    fn test_keyed_alphabet_straight() {
+        assert_eq!(
+            KeyedAlphabet::straight().to_string(),
+            "ABCDEFGHIJKLMNOPQRSTUVWXYZ"
+        );
+    }
+
+    #[test]
+    fn test_keyed_alphabet_from_permutation() {
+        let alphabet = KeyedAlphabet::from_permutation("ZYXWVUTSRQPONMLKJIHGFEDCBA").unwrap();
+        assert_eq!(alphabet.index_of('A'), Some(25));
+        assert_eq!(KeyedAlphabet::from_permutation("ABC"), None);
+        assert_eq!(
+            KeyedAlphabet::from_permutation("AACDEFGHIJKLMNOPQRSTUVWXYZ"),
+            None
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! N-gram language models, used as fitness functions by the attacks.
+//!
+//! Cracking a cipher by searching its key needs a way to tell how much a
+//! candidate plaintext looks like the language of the message. The usual
+//! measure is the log-probability of the text under an *n-gram model*: the sum,
+//! over every group of `n` consecutive letters, of the logarithm of the
+//! frequency of that group in the language. Quadgrams (`n = 4`) give the
+//! sharpest scores; monograms and bigrams are more robust on very short or
+//! badly garbled texts.
+//!
+//! An English model of any order from 1 to 4 is trained on a sample of English
+//! prose embedded in the crate ([`NgramModel::english`]). Models for other
+//! languages (German, French, Spanish, Latin...) are loaded from plain count
+//! files ([`NgramModel::from_counts`]), with one n-gram and its count per line:
+//!
+//! ```text
+//! TION 13168375
+//! NTHE 11234972
+//! THER 10218035
+//! ```
+//!
+//! Models implement the [`Scorer`] trait, as does any function of the letters,
+//! such as [`index_of_coincidence`]. Scores are computed on texts normalized by
+//! [`crate::vigenere::prepare_string`] (uppercase ASCII letters only), or on
+//! letter indices (`A` = 0), by direct lookup in a table of `26^n` entries.
+
+use std::path::Path;
+use thiserror::Error;
+
+/// The number of letters of the alphabet.
+pub const ALPHABET_SIZE: usize = 26;
+
+/// The largest supported n-gram order.
+pub const MAX_ORDER: usize = 4;
+
+/// The relative frequencies of the letters in English text, in percent.
+pub const ENGLISH_FREQUENCIES: [f64; ALPHABET_SIZE] = [
+    8.17, 1.29, 2.78, 4.25, 12.70, 2.23, 2.02, 6.09, 6.97, 0.15, 0.77, 4.03, 2.41, 6.75, 7.51,
+    1.93, 0.10, 5.99, 6.33, 9.06, 2.76, 0.98, 2.36, 0.15, 1.97, 0.07,
+];
+
+/// A sample of English prose, used to train the English models.
+pub const ENGLISH_CORPUS: &str = include_str!("../data/english.txt");
+
+/// The count given to the n-grams missing from the training data, so that they
+/// are unlikely but possible, relative to the count of the rarest n-gram seen.
+///
+/// The floor scales with the counts, so that a table of relative frequencies
+/// gives the same model as a table of raw counts.
+const FLOOR_RATIO: f64 = 0.1;
+
+/// Error type for the language models.
+#[derive(Error, Debug, PartialEq)]
+pub enum LanguageError {
+    #[error("N-gram order must be between 1 and 4, got {0}")]
+    InvalidOrder(usize),
+    #[error("Invalid line {0} in the count file: expected an n-gram and a count")]
+    InvalidLine(usize),
+    #[error("N-grams must all have the same length and only contain letters: {0}")]
+    InvalidNgram(String),
+    #[error("The training data contains no n-gram")]
+    Empty,
+    #[error("Cannot read the count file: {0}")]
+    Io(String),
+}
+
+/// A fitness function for candidate plaintexts: the higher, the more the text
+/// looks like the language.
+pub trait Scorer {
+    /// Scores a text given as letter indices (`A` = 0).
+    fn score_letters(&self, letters: &[u8]) -> f64;
+
+    /// Scores a text normalized by [`crate::vigenere::prepare_string`].
+    ///
+    /// Any character other than an uppercase ASCII letter is skipped.
+    fn score(&self, text: &str) -> f64 {
+        self.score_letters(&letters(text))
+    }
+}
+
+impl<F: Fn(&[u8]) -> f64> Scorer for F {
+    fn score_letters(&self, letters: &[u8]) -> f64 {
+        self(letters)
+    }
+}
+
+/// The log-probabilities of the n-grams of a language, for a fixed `n`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NgramModel {
+    order: usize,
+    log_probabilities: Vec<f64>,
+}
+
+impl NgramModel {
+    /// Trains an English model on the corpus embedded in the crate.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - The length of the n-grams, from 1 to 4.
+    ///
+    /// # Returns
+    ///
+    /// The model, or an error if the order is not supported.
+    pub fn english(order: usize) -> Result<Self, LanguageError> {
+        Self::from_text(order, ENGLISH_CORPUS)
+    }
+
+    /// Trains a model on a sample text of the language.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - The length of the n-grams, from 1 to 4.
+    /// * `text` - The training text. Non-alphabetic characters are ignored,
+    ///   and n-grams span word boundaries.
+    ///
+    /// # Returns
+    ///
+    /// The model, or an error if the order is not supported or the text is too short.
+    pub fn from_text(order: usize, text: &str) -> Result<Self, LanguageError> {
+        check_order(order)?;
+        let letters: Vec<u8> = text
+            .bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .map(|b| b.to_ascii_uppercase() - b'A')
+            .collect();
+
+        let mut counts = vec![0.0; ALPHABET_SIZE.pow(order as u32)];
+        for ngram in letters.windows(order) {
+            counts[index(ngram)] += 1.0;
+        }
+        Self::from_table(order, counts)
+    }
+
+    /// Loads a model from n-gram counts.
+    ///
+    /// # Arguments
+    ///
+    /// * `counts` - One n-gram and its count per line, separated by whitespace.
+    ///   Blank lines are ignored, n-grams are case-insensitive, and their
+    ///   length gives the order of the model. Counts may also be relative
+    ///   frequencies, in any unit.
+    ///
+    /// # Returns
+    ///
+    /// The model, or an error if a line is malformed or the n-grams do not all
+    /// have the same supported length.
+    pub fn from_counts(counts: &str) -> Result<Self, LanguageError> {
+        let mut order = 0;
+        let mut table = Vec::new();
+
+        for (number, line) in counts.lines().enumerate() {
+            let mut fields = line.split_whitespace();
+            let Some(ngram) = fields.next() else {
+                continue;
+            };
+            let count: f64 = match (fields.next().map(str::parse), fields.next()) {
+                (Some(Ok(count)), None) if count >= 0.0 => count,
+                _ => return Err(LanguageError::InvalidLine(number + 1)),
+            };
+
+            if order == 0 {
+                order = ngram.len();
+                check_order(order)?;
+                table = vec![0.0; ALPHABET_SIZE.pow(order as u32)];
+            }
+            if ngram.len() != order || !ngram.bytes().all(|b| b.is_ascii_alphabetic()) {
+                return Err(LanguageError::InvalidNgram(ngram.to_string()));
+            }
+            let letters: Vec<u8> = ngram
+                .bytes()
+                .map(|b| b.to_ascii_uppercase() - b'A')
+                .collect();
+            table[index(&letters)] += count;
+        }
+
+        Self::from_table(order, table)
+    }
+
+    /// Loads a model from a count file, in the format of [`NgramModel::from_counts`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the count file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LanguageError> {
+        let counts =
+            std::fs::read_to_string(path).map_err(|error| LanguageError::Io(error.to_string()))?;
+        Self::from_counts(&counts)
+    }
+
+    /// Returns the length of the n-grams of the model.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Returns the log-probability of an n-gram.
+    ///
+    /// # Arguments
+    ///
+    /// * `ngram` - The n-gram, as letter indices (`A` = 0). Only its first
+    ///   [`NgramModel::order`] letters are used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the n-gram is shorter than the order of the model.
+    pub fn log_probability(&self, ngram: &[u8]) -> f64 {
+        self.log_probabilities[index(&ngram[..self.order])]
+    }
+
+    /// Builds the model from the counts of all the n-grams, indexed as by [`index`].
+    fn from_table(order: usize, counts: Vec<f64>) -> Result<Self, LanguageError> {
+        let total: f64 = counts.iter().sum();
+        if total == 0.0 {
+            return Err(LanguageError::Empty);
+        }
+        let floor = counts
+            .iter()
+            .copied()
+            .filter(|&count| count > 0.0)
+            .fold(f64::INFINITY, f64::min)
+            * FLOOR_RATIO;
+        let log_probabilities = counts
+            .into_iter()
+            .map(|count| count.max(floor).ln() - total.ln())
+            .collect();
+        Ok(NgramModel {
+            order,
+            log_probabilities,
+        })
+    }
+}
+
+impl Scorer for NgramModel {
+    /// Returns the sum of the log-probabilities of all the n-grams of the text.
+    fn score_letters(&self, letters: &[u8]) -> f64 {
+        letters
+            .windows(self.order)
+            .map(|ngram| self.log_probabilities[index(ngram)])
+            .sum()
+    }
+
+    /// Returns the sum of the log-probabilities of all the n-grams of the text,
+    /// without allocating.
+    fn score(&self, text: &str) -> f64 {
+        let modulus = ALPHABET_SIZE.pow(self.order as u32);
+        let mut ngram = 0;
+        let mut len = 0;
+        let mut score = 0.0;
+        for letter in text.bytes().filter(u8::is_ascii_uppercase) {
+            ngram = (ngram * ALPHABET_SIZE + (letter - b'A') as usize) % modulus;
+            len += 1;
+            if len >= self.order {
+                score += self.log_probabilities[ngram];
+            }
+        }
+        score
+    }
+}
+
+/// Computes the index of coincidence of a text given as letter indices (`A` = 0).
+///
+/// This is the probability that two letters drawn at random from the text are
+/// the same: about 0.067 for English, and 1/26 for uniformly random letters.
+pub fn index_of_coincidence(text: &[u8]) -> f64 {
+    let mut counts = [0usize; ALPHABET_SIZE];
+    for &letter in text {
+        counts[letter as usize] += 1;
+    }
+    let n = text.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let pairs: usize = counts.iter().map(|&c| c * c.saturating_sub(1)).sum();
+    pairs as f64 / (n * (n - 1)) as f64
+}
+
+fn check_order(order: usize) -> Result<(), LanguageError> {
+    if (1..=MAX_ORDER).contains(&order) {
+        Ok(())
+    } else {
+        Err(LanguageError::InvalidOrder(order))
+    }
+}
+
+/// Returns the index of an n-gram in the table of a model, reading it in base 26.
+fn index(ngram: &[u8]) -> usize {
+    ngram
+        .iter()
+        .fold(0, |index, &letter| index * ALPHABET_SIZE + letter as usize)
+}
+
+/// Returns the letter indices of the uppercase ASCII letters of a text.
+fn letters(text: &str) -> Vec<u8> {
+    text.bytes()
+        .filter(u8::is_ascii_uppercase)
+        .map(|b| b - b'A')
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vigenere::prepare_string;
+
+    #[test]
+    fn test_language_english() {
+        for order in 1..=MAX_ORDER {
+            let model = NgramModel::english(order).unwrap();
+            assert_eq!(model.order(), order);
+            let english = model.score(&prepare_string("Meet me at the old bridge"));
+            let garbled = model.score(&prepare_string("Qzxv jw kq yvf ubp xfbzgh"));
+            assert!(english > garbled, "order {order}");
+        }
+
+        let quadgrams = NgramModel::english(4).unwrap();
+        assert!(
+            quadgrams.log_probability(&[19, 7, 4, 17])
+                > quadgrams.log_probability(&[16, 25, 16, 25])
+        );
+        assert_eq!(NgramModel::english(5), Err(LanguageError::InvalidOrder(5)));
+        assert_eq!(NgramModel::english(0), Err(LanguageError::InvalidOrder(0)));
+    }
+
+    #[test]
+    fn test_language_score() {
+        // Scoring a string and its letter indices gives the same result.
+        let model = NgramModel::english(3).unwrap();
+        let text = prepare_string("The quick brown fox jumps over the lazy dog");
+        let score = model.score(&text);
+        assert!((score - model.score_letters(&letters(&text))).abs() < 1e-9);
+        assert_eq!(model.score("AB"), 0.0);
+
+        // Any function of the letters is a scorer.
+        let ioc = index_of_coincidence;
+        assert_eq!(ioc.score("AAAA"), 1.0);
+    }
+
+    #[test]
+    fn test_language_from_counts() {
+        let model = NgramModel::from_counts("ab 3\n\nBA 1\n").unwrap();
+        assert_eq!(model.order(), 2);
+        assert!((model.log_probability(&[0, 1]) - 0.75f64.ln()).abs() < 1e-9);
+        assert!((model.log_probability(&[1, 0]) - 0.25f64.ln()).abs() < 1e-9);
+        assert!(model.log_probability(&[2, 2]) < model.log_probability(&[1, 0]));
+
+        assert_eq!(
+            NgramModel::from_counts("AB 3\nABC 1"),
+            Err(LanguageError::InvalidNgram("ABC".to_string()))
+        );
+        assert_eq!(
+            NgramModel::from_counts("AB 3\nBA"),
+            Err(LanguageError::InvalidLine(2))
+        );
+        assert_eq!(
+            NgramModel::from_counts("ABCDE 3"),
+            Err(LanguageError::InvalidOrder(5))
+        );
+        assert_eq!(NgramModel::from_counts(""), Err(LanguageError::Empty));
+        assert_eq!(NgramModel::from_counts("AB 0"), Err(LanguageError::Empty));
+        assert!(matches!(
+            NgramModel::from_file("/nonexistent/quadgrams.txt"),
+            Err(LanguageError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_language_from_frequencies() {
+        let model = NgramModel::from_counts("TH 0.03\nHE 0.02\nQZ 0.00001\n").unwrap();
+        let th = model.log_probability(&[19, 7]);
+        let qz = model.log_probability(&[16, 25]);
+        let unseen = model.log_probability(&[23, 23]);
+        assert!(th < 0.0);
+        assert!(th > model.log_probability(&[7, 4]));
+        assert!(qz < th && unseen < qz);
+
+        // Frequencies give the same model as counts in the same proportions.
+        let counts = NgramModel::from_counts("TH 3000\nHE 2000\nQZ 1\n").unwrap();
+        for ngram in [[19, 7], [7, 4], [16, 25], [23, 23]] {
+            assert!((model.log_probability(&ngram) - counts.log_probability(&ngram)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_language_index_of_coincidence() {
+        assert_eq!(index_of_coincidence(&[0, 0, 0, 0]), 1.0);
+        assert_eq!(index_of_coincidence(&[0, 1, 2, 3]), 0.0);
+        assert_eq!(index_of_coincidence(&[0]), 0.0);
+    }
+}
@@ -1,12 +1,59 @@
 //! Pure Rust implementation of classical ciphers (for fun and learning!).
+//!
+//! The ciphers only need an allocator, and the crate builds in `no_std`
+//! environments when the default `std` feature is disabled. The feature is
+//! needed by the statistical tools (`language`, `segment`, `identify`,
+//! `homophonic`, the Enigma and Porta solvers), by `render`, and by what reads
+//! files or asks the operating system for randomness.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod prelude;
 
 pub mod affine;
 pub mod bacon;
+pub mod baudot;
 pub mod beaufort;
 pub mod caesar;
+pub mod chaocipher;
+pub mod cipher;
+pub mod columnar;
+pub mod digraph;
+pub mod enigma;
+pub mod formatting;
+pub mod four_square;
+#[cfg(feature = "std")]
+pub mod homophonic;
+#[cfg(feature = "std")]
+pub mod identify;
+pub mod key_square;
+pub mod keyed_alphabet;
+#[cfg(feature = "std")]
+pub mod language;
+pub mod lorenz;
+pub mod m209;
+pub mod morse;
+pub mod nihilist;
 pub mod otp;
+pub mod pipeline;
 pub mod playfair;
 pub mod polybius_square;
 pub mod porta;
+pub mod quagmire;
+#[cfg(feature = "std")]
+pub mod render;
 pub mod rot13;
+#[cfg(feature = "std")]
+pub mod segment;
+pub mod solitaire;
+pub mod straddling_checkerboard;
+pub mod stream;
+pub mod trace;
+pub mod two_square;
+pub mod vic;
 pub mod vigenere;
+
+#[cfg(feature = "std")]
+pub use identify::identify;
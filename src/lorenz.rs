@@ -0,0 +1,453 @@
+//! A simulation of the Lorenz SZ40/42 teleprinter cipher attachment.
+//!
+//! The Lorenz machine mechanizes the Vernam cipher: every five-bit ITA2 code of
+//! the message is XORed with a five-bit key code. The key is produced by twelve
+//! pinwheels whose cams can be set active or inactive:
+//!
+//! * five **chi** wheels (41, 31, 29, 26 and 23 cams), stepping with every character;
+//! * five **psi** wheels (43, 47, 51, 53 and 59 cams), stepping together only
+//!   when the motor wheels allow it;
+//! * two **mu** (motor) wheels (61 and 37 cams). The first one steps with every
+//!   character, the second one steps when the first one shows an active cam, and
+//!   the psi wheels step when the second one shows an active cam.
+//!
+//! The key of each character is the XOR of the cams under the reading heads of
+//! the chi and psi wheels, the first wheel of each set providing the first impulse.
+//! Since XOR is its own inverse, the same machine settings encrypt and decrypt.
+
+use crate::baudot::{self, BaudotError};
+use crate::prelude::*;
+use rand::Rng;
+use thiserror::Error;
+
+/// The number of cams of the chi wheels.
+pub const CHI_SIZES: [usize; 5] = [41, 31, 29, 26, 23];
+/// The number of cams of the psi wheels.
+pub const PSI_SIZES: [usize; 5] = [43, 47, 51, 53, 59];
+/// The number of cams of the mu (motor) wheels.
+pub const MU_SIZES: [usize; 2] = [61, 37];
+
+const CHI_NAMES: [&str; 5] = ["chi1", "chi2", "chi3", "chi4", "chi5"];
+const PSI_NAMES: [&str; 5] = ["psi1", "psi2", "psi3", "psi4", "psi5"];
+const MU_NAMES: [&str; 2] = ["mu1", "mu2"];
+
+/// Error type for the Lorenz cipher.
+#[derive(Error, Debug, PartialEq)]
+pub enum LorenzError {
+    #[error("Missing cam pattern for wheel {0}")]
+    MissingCams(&'static str),
+    #[error("Wheel {0} has {1} cams, got a pattern of {2}")]
+    WheelSize(&'static str, usize, usize),
+    #[error("Invalid cam pattern character: {0}")]
+    InvalidCam(char),
+    #[error("Wheel {0} has no position {1}")]
+    InvalidPosition(&'static str, usize),
+    #[error(transparent)]
+    Baudot(#[from] BaudotError),
+}
+
+/// A pinwheel: a ring of cams, each either active or inactive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Wheel {
+    cams: Vec<bool>,
+    position: usize,
+}
+
+impl Wheel {
+    /// Returns whether the cam under the reading head is active.
+    fn active(&self) -> bool {
+        self.cams[self.position]
+    }
+
+    /// Moves the wheel by one cam.
+    fn step(&mut self) {
+        self.position = (self.position + 1) % self.cams.len();
+    }
+}
+
+/// A builder for a Lorenz machine.
+///
+/// With the `serde` feature, the settings can be stored and loaded; the
+/// settings missing from the input take the defaults of [`LorenzBuilder::new`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct LorenzBuilder {
+    chi: [Option<String>; 5],
+    psi: [Option<String>; 5],
+    mu: [Option<String>; 2],
+    chi_positions: [usize; 5],
+    psi_positions: [usize; 5],
+    mu_positions: [usize; 2],
+}
+
+impl LorenzBuilder {
+    /// Creates a builder without cam patterns, all wheels starting at position 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the cam patterns of the chi wheels.
+    ///
+    /// Patterns are written with `x` or `1` for an active cam and `.` or `0`
+    /// for an inactive one, as in the Bletchley Park notation.
+    pub fn chi(mut self, patterns: [&str; 5]) -> Self {
+        self.chi = patterns.map(|p| Some(p.to_string()));
+        self
+    }
+
+    /// Sets the cam patterns of the psi wheels.
+    pub fn psi(mut self, patterns: [&str; 5]) -> Self {
+        self.psi = patterns.map(|p| Some(p.to_string()));
+        self
+    }
+
+    /// Sets the cam patterns of the mu wheels.
+    pub fn mu(mut self, patterns: [&str; 2]) -> Self {
+        self.mu = patterns.map(|p| Some(p.to_string()));
+        self
+    }
+
+    /// Sets random cam patterns on all the wheels.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The random number generator to draw the cams from.
+    pub fn random_cams(mut self, rng: &mut impl Rng) -> Self {
+        let mut random = |size: usize| -> Option<String> {
+            Some(
+                (0..size)
+                    .map(|_| if rng.gen::<bool>() { 'x' } else { '.' })
+                    .collect(),
+            )
+        };
+        self.chi = CHI_SIZES.map(&mut random);
+        self.psi = PSI_SIZES.map(&mut random);
+        self.mu = MU_SIZES.map(&mut random);
+        self
+    }
+
+    /// Sets the starting positions of the wheels (starting at 0).
+    ///
+    /// # Arguments
+    ///
+    /// * `chi` - The positions of the chi wheels.
+    /// * `psi` - The positions of the psi wheels.
+    /// * `mu` - The positions of the mu wheels.
+    pub fn positions(mut self, chi: [usize; 5], psi: [usize; 5], mu: [usize; 2]) -> Self {
+        self.chi_positions = chi;
+        self.psi_positions = psi;
+        self.mu_positions = mu;
+        self
+    }
+
+    /// Builds the machine.
+    ///
+    /// # Returns
+    ///
+    /// The machine, or an error if a cam pattern is missing, has the wrong size
+    /// or contains an invalid character, or if a position is out of range.
+    pub fn build(&self) -> Result<Lorenz, LorenzError> {
+        Ok(Lorenz {
+            chi: build_wheels(&self.chi, &CHI_SIZES, &CHI_NAMES, &self.chi_positions)?,
+            psi: build_wheels(&self.psi, &PSI_SIZES, &PSI_NAMES, &self.psi_positions)?,
+            mu: build_wheels(&self.mu, &MU_SIZES, &MU_NAMES, &self.mu_positions)?,
+        })
+    }
+}
+
+/// A Lorenz SZ40/42 machine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lorenz {
+    chi: [Wheel; 5],
+    psi: [Wheel; 5],
+    mu: [Wheel; 2],
+}
+
+impl Lorenz {
+    /// Returns a builder for a Lorenz machine.
+    pub fn builder() -> LorenzBuilder {
+        LorenzBuilder::new()
+    }
+
+    /// Returns the current positions of the chi, psi and mu wheels.
+    pub fn positions(&self) -> ([usize; 5], [usize; 5], [usize; 2]) {
+        (
+            self.chi.each_ref().map(|w| w.position),
+            self.psi.each_ref().map(|w| w.position),
+            self.mu.each_ref().map(|w| w.position),
+        )
+    }
+
+    /// Sets the positions of the wheels, e.g. to decrypt a message with the same settings.
+    pub fn set_positions(
+        &mut self,
+        chi: [usize; 5],
+        psi: [usize; 5],
+        mu: [usize; 2],
+    ) -> Result<(), LorenzError> {
+        set_wheel_positions(&mut self.chi, &CHI_NAMES, &chi)?;
+        set_wheel_positions(&mut self.psi, &PSI_NAMES, &psi)?;
+        set_wheel_positions(&mut self.mu, &MU_NAMES, &mu)
+    }
+
+    /// Returns the next key code and moves the wheels.
+    pub fn next_key(&mut self) -> u8 {
+        let key = impulses(&self.chi) ^ impulses(&self.psi);
+
+        let total_motor = self.mu[1].active();
+        let basic_motor = self.mu[0].active();
+        self.chi.iter_mut().for_each(Wheel::step);
+        if total_motor {
+            self.psi.iter_mut().for_each(Wheel::step);
+        }
+        if basic_motor {
+            self.mu[1].step();
+        }
+        self.mu[0].step();
+
+        key
+    }
+
+    /// Returns the next `len` key codes.
+    pub fn key_stream(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_key()).collect()
+    }
+
+    /// Enciphers (or deciphers) ITA2 codes by XORing them with the key.
+    ///
+    /// # Arguments
+    ///
+    /// * `codes` - The five-bit codes to be enciphered.
+    ///
+    /// # Returns
+    ///
+    /// The enciphered codes. Only the five low bits of each code are kept, see
+    /// [`Lorenz::decrypt`] to reject values that are not five-bit codes.
+    pub fn encipher(&mut self, codes: &[u8]) -> Vec<u8> {
+        codes
+            .iter()
+            .map(|&code| (code ^ self.next_key()) & 0b11111)
+            .collect()
+    }
+
+    /// Encodes a text in ITA2 and encrypts it.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The text to be encrypted.
+    ///
+    /// # Returns
+    ///
+    /// The encrypted codes, or an error if the text cannot be encoded.
+    pub fn encrypt(&mut self, plaintext: &str) -> Result<Vec<u8>, LorenzError> {
+        Ok(self.encipher(&baudot::encode(plaintext)?))
+    }
+
+    /// Decrypts codes and decodes the resulting ITA2 text.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The encrypted codes.
+    ///
+    /// # Returns
+    ///
+    /// The decrypted text, or an error if a value is not a five-bit code.
+    pub fn decrypt(&mut self, ciphertext: &[u8]) -> Result<String, LorenzError> {
+        if let Some(&code) = ciphertext.iter().find(|&&code| code > 0b11111) {
+            return Err(BaudotError::InvalidCode(code).into());
+        }
+        Ok(baudot::decode(&self.encipher(ciphertext))?)
+    }
+}
+
+/// Combines the active cams of five wheels into a code, the first wheel giving the first impulse.
+fn impulses(wheels: &[Wheel; 5]) -> u8 {
+    wheels
+        .iter()
+        .fold(0, |code, wheel| (code << 1) | wheel.active() as u8)
+}
+
+/// Builds a set of wheels from their cam patterns and starting positions.
+fn build_wheels<const N: usize>(
+    patterns: &[Option<String>; N],
+    sizes: &[usize; N],
+    names: &[&'static str; N],
+    positions: &[usize; N],
+) -> Result<[Wheel; N], LorenzError> {
+    let mut wheels = Vec::with_capacity(N);
+    for idx in 0..N {
+        let pattern = patterns[idx]
+            .as_deref()
+            .ok_or(LorenzError::MissingCams(names[idx]))?;
+        let cams = pattern
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                'x' | 'X' | '1' => Ok(true),
+                '.' | '0' => Ok(false),
+                _ => Err(LorenzError::InvalidCam(c)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if cams.len() != sizes[idx] {
+            return Err(LorenzError::WheelSize(names[idx], sizes[idx], cams.len()));
+        }
+        if positions[idx] >= sizes[idx] {
+            return Err(LorenzError::InvalidPosition(names[idx], positions[idx]));
+        }
+        wheels.push(Wheel {
+            cams,
+            position: positions[idx],
+        });
+    }
+
+    Ok(wheels
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("exactly N wheels are built")))
+}
+
+/// Sets the positions of a set of wheels.
+fn set_wheel_positions<const N: usize>(
+    wheels: &mut [Wheel; N],
+    names: &[&'static str; N],
+    positions: &[usize; N],
+) -> Result<(), LorenzError> {
+    for idx in 0..N {
+        if positions[idx] >= wheels[idx].cams.len() {
+            return Err(LorenzError::InvalidPosition(names[idx], positions[idx]));
+        }
+    }
+    for (wheel, &position) in wheels.iter_mut().zip(positions) {
+        wheel.position = position;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// A pattern alternating active and inactive cams, starting with `first`.
+    fn alternating(size: usize, first: bool) -> String {
+        (0..size)
+            .map(|i| if (i % 2 == 0) == first { 'x' } else { '.' })
+            .collect()
+    }
+
+    fn machine() -> Lorenz {
+        Lorenz::builder()
+            .random_cams(&mut StdRng::seed_from_u64(1942))
+            .positions([1, 2, 3, 4, 5], [6, 7, 8, 9, 10], [11, 12])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lorenz_round_trip() {
+        let plaintext = "ATTACK AT DAWN, 0600 HOURS.";
+        let mut sender = machine();
+        let ciphertext = sender.encrypt(plaintext).unwrap();
+        assert_ne!(ciphertext, baudot::encode(plaintext).unwrap());
+
+        let mut receiver = machine();
+        assert_eq!(receiver.decrypt(&ciphertext).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_lorenz_vernam_principle() {
+        // Two messages sent in depth: XORing the ciphertexts cancels the key.
+        let first = baudot::encode("THE QUICK BROWN FOX").unwrap();
+        let second = baudot::encode("JUMPS OVER LAZY DOG").unwrap();
+        let c1 = machine().encipher(&first);
+        let c2 = machine().encipher(&second);
+        for idx in 0..first.len() {
+            assert_eq!(c1[idx] ^ c2[idx], first[idx] ^ second[idx]);
+        }
+
+        let key = machine().key_stream(first.len());
+        for idx in 0..first.len() {
+            assert_eq!(c1[idx] ^ first[idx], key[idx]);
+        }
+    }
+
+    #[test]
+    fn test_lorenz_wheel_motion() {
+        // With all motor cams active, the psi wheels step with every character.
+        let chi = CHI_SIZES.map(|size| "x".repeat(size));
+        let psi = PSI_SIZES.map(|size| alternating(size, true));
+        let mu = MU_SIZES.map(|size| "x".repeat(size));
+        let mut lorenz = Lorenz::builder()
+            .chi(chi.each_ref().map(String::as_str))
+            .psi(psi.each_ref().map(String::as_str))
+            .mu(mu.each_ref().map(String::as_str))
+            .build()
+            .unwrap();
+        assert_eq!(
+            lorenz.key_stream(4),
+            vec![0b00000, 0b11111, 0b00000, 0b11111]
+        );
+        assert_eq!(lorenz.positions(), ([4; 5], [4; 5], [4; 2]));
+
+        // With an inactive second motor wheel, the psi wheels stand still.
+        let mu = MU_SIZES.map(|size| ".".repeat(size));
+        let mut lorenz = Lorenz::builder()
+            .chi(chi.each_ref().map(String::as_str))
+            .psi(psi.each_ref().map(String::as_str))
+            .mu(mu.each_ref().map(String::as_str))
+            .build()
+            .unwrap();
+        assert_eq!(lorenz.key_stream(3), vec![0b00000; 3]);
+        assert_eq!(lorenz.positions(), ([3; 5], [0; 5], [3, 0]));
+    }
+
+    #[test]
+    fn test_lorenz_errors() {
+        let chi = CHI_SIZES.map(|size| "x".repeat(size));
+        let builder = Lorenz::builder().chi(chi.each_ref().map(String::as_str));
+        assert_eq!(builder.build(), Err(LorenzError::MissingCams("psi1")));
+
+        let builder = builder.random_cams(&mut StdRng::seed_from_u64(0));
+        assert_eq!(
+            builder.clone().chi(["x.x", "", "", "", ""]).build(),
+            Err(LorenzError::WheelSize("chi1", 41, 3))
+        );
+        assert_eq!(
+            builder.clone().mu(["?", ""]).build(),
+            Err(LorenzError::InvalidCam('?'))
+        );
+        assert_eq!(
+            builder.positions([41, 0, 0, 0, 0], [0; 5], [0; 2]).build(),
+            Err(LorenzError::InvalidPosition("chi1", 41))
+        );
+        assert_eq!(
+            machine().encrypt("50%"),
+            Err(LorenzError::Baudot(BaudotError::UnsupportedChar('%')))
+        );
+        let mut lorenz = machine();
+        assert_eq!(
+            lorenz.decrypt(&[0b00011, 0b100000]),
+            Err(LorenzError::Baudot(BaudotError::InvalidCode(0b100000)))
+        );
+        // The key does not advance on an invalid ciphertext.
+        assert_eq!(lorenz.positions(), machine().positions());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_lorenz_serde() {
+        let builder = Lorenz::builder()
+            .random_cams(&mut StdRng::seed_from_u64(1942))
+            .positions([1, 2, 3, 4, 5], [6, 7, 8, 9, 10], [11, 12]);
+        let json = serde_json::to_string(&builder).unwrap();
+        let loaded: LorenzBuilder = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.build(), Ok(machine()));
+
+        let loaded: LorenzBuilder = serde_json::from_str(r#"{"mu_positions": [1, 2]}"#).unwrap();
+        assert_eq!(loaded.build(), Err(LorenzError::MissingCams("chi1")));
+    }
+}
@@ -0,0 +1,624 @@
+//! A simulation of the Hagelin M-209 cipher machine.
+//!
+//! The M-209 is a pin-and-lug machine used by the US Army during the Second
+//! World War. It has six key wheels, each with a pin in front of every letter
+//! of its rim; a pin can be set effective or ineffective. Behind the wheels, a
+//! cage of 27 bars carries lugs that can be set against any of the wheels.
+//!
+//! To encipher a letter, every bar having a lug against a wheel whose pin is
+//! effective is shifted. The number of shifted bars is the key `K` of the letter,
+//! which is then substituted with the Beaufort tableau on a reversed alphabet:
+//! `C = 25 + K - P (mod 26)`. The substitution is reciprocal, so the same
+//! settings encrypt and decrypt. All the wheels then advance by one letter.
+//!
+//! Since the wheels have 26, 25, 23, 21, 19 and 17 letters, their periods are
+//! coprime and the key only repeats after 101,405,850 letters.
+
+use crate::beaufort;
+use crate::prelude::*;
+use crate::trace::Step;
+use thiserror::Error;
+
+/// The letters of the key wheels, from left to right.
+pub const WHEEL_LETTERS: [&str; 6] = [
+    "ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+    "ABCDEFGHIJKLMNOPQRSTUVXYZ",
+    "ABCDEFGHIJKLMNOPQRSTUVX",
+    "ABCDEFGHIJKLMNOPQRSTU",
+    "ABCDEFGHIJKLMNOPQRS",
+    "ABCDEFGHIJKLMNOPQ",
+];
+
+/// The number of bars of the lug cage.
+pub const BAR_COUNT: usize = 27;
+
+/// The distance between the letter shown in the window and the pin read by the guide arm.
+const PIN_OFFSETS: [usize; 6] = [15, 14, 13, 12, 11, 10];
+
+/// Error type for the M-209.
+#[derive(Error, Debug, PartialEq)]
+pub enum M209Error {
+    #[error("Key wheel {0} has no letter {1}")]
+    InvalidLetter(usize, char),
+    #[error("Invalid lug setting: {0}")]
+    InvalidLug(String),
+    #[error("The lug cage only has 27 bars, got {0}")]
+    TooManyBars(usize),
+    #[error("Expected one letter per key wheel, got {0}")]
+    InvalidPositions(usize),
+    #[error("Invalid message indicator")]
+    InvalidIndicator,
+}
+
+/// A key wheel: its letters, the pins that are effective and its current position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct KeyWheel {
+    letters: &'static str,
+    pins: Vec<bool>,
+    offset: usize,
+    position: usize,
+}
+
+impl KeyWheel {
+    /// Returns whether the pin read by the guide arm is effective.
+    fn effective(&self) -> bool {
+        self.pins[(self.position + self.offset) % self.pins.len()]
+    }
+
+    /// Returns the index of a letter on the wheel.
+    fn index_of(&self, c: char) -> Option<usize> {
+        self.letters.find(c.to_ascii_uppercase())
+    }
+}
+
+/// A Hagelin M-209 machine.
+///
+/// With the `serde` feature, the machine is serialized as its key list (the
+/// effective pins of every wheel and the lug settings, in the format of
+/// [`M209::new`]), the letters of its key wheels and its letter counter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "M209Repr", into = "M209Repr")
+)]
+pub struct M209 {
+    wheels: Vec<KeyWheel>,
+    /// For every bar, a bit mask of the wheels its lugs are set against.
+    bars: Vec<u8>,
+    letter_counter: usize,
+}
+
+impl M209 {
+    /// Creates a machine from its internal settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `pins` - For every key wheel, the letters whose pins are effective.
+    /// * `lugs` - The lug settings, as space-separated bars written `a-b`, where
+    ///   `a` and `b` are the wheels (1 to 6) the two lugs are set against, or 0
+    ///   for a neutral lug. A bar can be repeated with `*n`, e.g. `0-6*11`.
+    ///   Bars that are not given are neutral.
+    ///
+    /// # Returns
+    ///
+    /// The machine with all its key wheels set to `A`, or an error if the settings are invalid.
+    pub fn new(pins: [&str; 6], lugs: &str) -> Result<Self, M209Error> {
+        let wheels = WHEEL_LETTERS
+            .iter()
+            .zip(pins)
+            .zip(PIN_OFFSETS)
+            .enumerate()
+            .map(|(idx, ((&letters, pins), offset))| {
+                let mut effective = vec![false; letters.len()];
+                for c in pins.chars().filter(|c| !c.is_whitespace()) {
+                    let pos = letters
+                        .find(c.to_ascii_uppercase())
+                        .ok_or(M209Error::InvalidLetter(idx + 1, c))?;
+                    effective[pos] = true;
+                }
+                Ok(KeyWheel {
+                    letters,
+                    pins: effective,
+                    offset,
+                    position: 0,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(M209 {
+            wheels,
+            bars: parse_lugs(lugs)?,
+            letter_counter: 0,
+        })
+    }
+
+    /// Sets the key wheels to the given letters, from left to right.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - Six letters, each present on the corresponding wheel.
+    pub fn set_key_wheels(&mut self, positions: &str) -> Result<(), M209Error> {
+        let letters: Vec<char> = positions.chars().filter(|c| !c.is_whitespace()).collect();
+        if letters.len() != self.wheels.len() {
+            return Err(M209Error::InvalidPositions(letters.len()));
+        }
+
+        let indices = self
+            .wheels
+            .iter()
+            .zip(&letters)
+            .enumerate()
+            .map(|(idx, (wheel, &c))| {
+                wheel
+                    .index_of(c)
+                    .ok_or(M209Error::InvalidLetter(idx + 1, c))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for (wheel, idx) in self.wheels.iter_mut().zip(indices) {
+            wheel.position = idx;
+        }
+        Ok(())
+    }
+
+    /// Returns the letters shown in the windows of the key wheels.
+    pub fn key_wheels(&self) -> String {
+        self.wheels
+            .iter()
+            .map(|wheel| wheel.letters.as_bytes()[wheel.position] as char)
+            .collect()
+    }
+
+    /// Returns the number of letters enciphered since the machine was created.
+    pub fn letter_counter(&self) -> usize {
+        self.letter_counter
+    }
+
+    /// Returns the key at the current position: the number of bars shifted by the effective pins.
+    pub fn key(&self) -> u8 {
+        let effective = self
+            .wheels
+            .iter()
+            .enumerate()
+            .filter(|(_, wheel)| wheel.effective())
+            .fold(0u8, |mask, (idx, _)| mask | (1 << idx));
+        self.bars
+            .iter()
+            .filter(|&&bar| bar & effective != 0)
+            .count() as u8
+    }
+
+    /// Enciphers a single letter and advances the key wheels.
+    ///
+    /// # Arguments
+    ///
+    /// * `letter` - The letter to be enciphered (in either case).
+    ///
+    /// # Returns
+    ///
+    /// The enciphered letter, or `None` if `letter` is not an ASCII letter.
+    pub fn encipher_letter(&mut self, letter: char) -> Option<char> {
+        if !letter.is_ascii_alphabetic() {
+            return None;
+        }
+
+        // The printing wheel carries a reversed alphabet, which offsets the Beaufort key by 25.
+        let letter = letter.to_ascii_uppercase() as u8 - b'A';
+        let output = beaufort::substitute(letter, 25 + self.key());
+
+        for wheel in &mut self.wheels {
+            wheel.position = (wheel.position + 1) % wheel.letters.len();
+        }
+        self.letter_counter += 1;
+
+        Some((output + b'A') as char)
+    }
+
+    /// Enciphers the letters of a text, recording every step.
+    ///
+    /// Unlike [`M209::encrypt`], spaces are not replaced and the output is not
+    /// grouped: every other character than a letter is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to be enciphered.
+    ///
+    /// # Returns
+    ///
+    /// The enciphered letters, and one step per letter with the letters of the
+    /// key wheels and the key they give.
+    pub fn encipher_traced(&mut self, text: &str) -> (String, Vec<Step>) {
+        text.chars()
+            .filter(char::is_ascii_alphabetic)
+            .map(|c| {
+                let wheels = self.key_wheels();
+                let key = self.key();
+                let output = self.encipher_letter(c).unwrap_or(c);
+                let step = Step::new(c, output, "subtract")
+                    .detail("wheels", wheels)
+                    .detail("key", key);
+                (output, step)
+            })
+            .unzip()
+    }
+
+    /// Encrypts the provided plaintext.
+    ///
+    /// Spaces are enciphered as `Z`, and other non-alphabetic characters are
+    /// dropped. The ciphertext is printed in groups of five letters.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The text to be encrypted.
+    ///
+    /// # Returns
+    ///
+    /// The ciphertext, in groups of five letters.
+    pub fn encrypt(&mut self, plaintext: &str) -> String {
+        let letters: Vec<char> = plaintext
+            .chars()
+            .map(|c| if c == ' ' { 'Z' } else { c })
+            .filter_map(|c| self.encipher_letter(c))
+            .collect();
+        letters
+            .chunks(5)
+            .map(|group| group.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Decrypts the provided ciphertext.
+    ///
+    /// Non-alphabetic characters are ignored, and deciphered `Z`s are printed as spaces.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The text to be decrypted.
+    ///
+    /// # Returns
+    ///
+    /// The plaintext.
+    pub fn decrypt(&mut self, ciphertext: &str) -> String {
+        ciphertext
+            .chars()
+            .filter_map(|c| self.encipher_letter(c))
+            .map(|c| if c == 'Z' { ' ' } else { c })
+            .collect()
+    }
+
+    /// Encrypts a message following the indicator procedure of the technical manual.
+    ///
+    /// The key wheels are first set to the system indicator on every wheel, and
+    /// the message indicator is enciphered with each of its letters doubled. The
+    /// key wheels are then set to the message indicator to encrypt the plaintext.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The text to be encrypted.
+    /// * `system_indicator` - A letter present on every wheel (`A` to `Q`).
+    /// * `message_indicator` - Six letters, the starting positions of the key wheels.
+    ///
+    /// # Returns
+    ///
+    /// The message: the system indicator twice, the enciphered indicator and the
+    /// ciphertext, in groups of five letters.
+    pub fn encrypt_message(
+        &mut self,
+        plaintext: &str,
+        system_indicator: char,
+        message_indicator: &str,
+    ) -> Result<String, M209Error> {
+        let system_indicator = system_indicator.to_ascii_uppercase();
+        // Check the message indicator before using it.
+        self.set_key_wheels(message_indicator)?;
+        self.set_key_wheels(&system_indicator.to_string().repeat(6))?;
+
+        let doubled: String = message_indicator
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(|c| [c, c])
+            .collect();
+        let indicator = self.encrypt(&doubled).replace(' ', "");
+
+        self.set_key_wheels(message_indicator)?;
+        let ciphertext = self.encrypt(plaintext).replace(' ', "");
+
+        let message: Vec<char> = format!("{0}{0}{1}{2}", system_indicator, indicator, ciphertext)
+            .chars()
+            .collect();
+        Ok(message
+            .chunks(5)
+            .map(|group| group.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+
+    /// Decrypts a message produced by [`M209::encrypt_message`].
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message, starting with the system indicator twice and the enciphered indicator.
+    ///
+    /// # Returns
+    ///
+    /// The plaintext, or an error if the indicators are missing or inconsistent.
+    pub fn decrypt_message(&mut self, message: &str) -> Result<String, M209Error> {
+        let letters: Vec<char> = message.chars().filter(|c| !c.is_whitespace()).collect();
+        if letters.len() < 14 || letters[0] != letters[1] {
+            return Err(M209Error::InvalidIndicator);
+        }
+
+        self.set_key_wheels(&letters[0].to_string().repeat(6))
+            .map_err(|_| M209Error::InvalidIndicator)?;
+        let indicator: Vec<char> = letters[2..14]
+            .iter()
+            .filter_map(|&c| self.encipher_letter(c))
+            .collect();
+        // Every letter of the message indicator is enciphered twice.
+        if indicator.len() != 12 || indicator.chunks(2).any(|pair| pair[0] != pair[1]) {
+            return Err(M209Error::InvalidIndicator);
+        }
+
+        let message_indicator: String = indicator.iter().step_by(2).collect();
+        self.set_key_wheels(&message_indicator)
+            .map_err(|_| M209Error::InvalidIndicator)?;
+        Ok(self.decrypt(&letters[14..].iter().collect::<String>()))
+    }
+}
+
+/// The serialized form of an [`M209`], checked when it is loaded.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct M209Repr {
+    pins: [String; 6],
+    lugs: String,
+    key_wheels: String,
+    #[serde(default)]
+    letter_counter: usize,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<M209Repr> for M209 {
+    type Error = M209Error;
+
+    fn try_from(repr: M209Repr) -> Result<Self, Self::Error> {
+        let mut m209 = M209::new(repr.pins.each_ref().map(String::as_str), &repr.lugs)?;
+        m209.set_key_wheels(&repr.key_wheels)?;
+        m209.letter_counter = repr.letter_counter;
+        Ok(m209)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<M209> for M209Repr {
+    fn from(m209: M209) -> Self {
+        let pins = core::array::from_fn(|idx| {
+            let wheel = &m209.wheels[idx];
+            wheel
+                .letters
+                .chars()
+                .zip(&wheel.pins)
+                .filter_map(|(c, &effective)| effective.then_some(c))
+                .collect()
+        });
+        M209Repr {
+            pins,
+            lugs: format_lugs(&m209.bars),
+            key_wheels: m209.key_wheels(),
+            letter_counter: m209.letter_counter,
+        }
+    }
+}
+
+/// Writes bars in the format of the lug settings, the inverse of [`parse_lugs`].
+///
+/// Consecutive identical bars are grouped with `*n`, and the neutral bars at the end are left out.
+#[cfg(feature = "serde")]
+fn format_lugs(bars: &[u8]) -> String {
+    let len = bars
+        .iter()
+        .rposition(|&bar| bar != 0)
+        .map_or(0, |idx| idx + 1);
+    let mut lugs = Vec::new();
+    let mut idx = 0;
+    while idx < len {
+        let count = bars[idx..len]
+            .iter()
+            .take_while(|&&bar| bar == bars[idx])
+            .count();
+        let mut wheels = (1..=6).filter(|wheel| bars[idx] & (1 << (wheel - 1)) != 0);
+        let a = wheels.next().unwrap_or(0);
+        let bar = match wheels.next() {
+            Some(b) => format!("{}-{}", a, b),
+            None => format!("0-{}", a),
+        };
+        lugs.push(match count {
+            1 => bar,
+            _ => format!("{}*{}", bar, count),
+        });
+        idx += count;
+    }
+    lugs.join(" ")
+}
+
+/// Parses the lug settings into bars, each a bit mask of the wheels its lugs are set against.
+fn parse_lugs(lugs: &str) -> Result<Vec<u8>, M209Error> {
+    let mut bars = Vec::with_capacity(BAR_COUNT);
+
+    for token in lugs.split_whitespace() {
+        let invalid = || M209Error::InvalidLug(token.to_string());
+        let (bar, count) = match token.split_once('*') {
+            Some((bar, count)) => (bar, count.parse::<usize>().map_err(|_| invalid())?),
+            None => (token, 1),
+        };
+        let (a, b) = bar.split_once('-').ok_or_else(invalid)?;
+        let (a, b) = (
+            a.parse::<u8>().map_err(|_| invalid())?,
+            b.parse::<u8>().map_err(|_| invalid())?,
+        );
+        if a > 6 || b > 6 || (a == b && a != 0) {
+            return Err(invalid());
+        }
+
+        let mask = [a, b]
+            .iter()
+            .filter(|&&wheel| wheel != 0)
+            .fold(0u8, |mask, &wheel| mask | (1 << (wheel - 1)));
+        bars.extend(core::iter::repeat_n(mask, count));
+    }
+
+    if bars.len() > BAR_COUNT {
+        return Err(M209Error::TooManyBars(bars.len()));
+    }
+    bars.resize(BAR_COUNT, 0);
+    Ok(bars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The key list of the example of the technical manual (TM 11-380).
+    const PINS: [&str; 6] = [
+        "ABDHIKMNSTVW",
+        "ADEGJKLORSUX",
+        "ABGHJLMNRSTUX",
+        "CEFHIMNPSTU",
+        "BDEFHIMNPS",
+        "ABDHKNOQ",
+    ];
+    const LUGS: &str = "3-6 0-6 1-6 1-5 4-5 0-4*4 2-0*10 2-5*2 0-5*6";
+
+    #[test]
+    fn test_m209_key() {
+        let m209 = M209::new(PINS, LUGS).unwrap();
+        // At AAAAAA, the guide arms read the pins P, O, N, M, L and K: wheels 2,
+        // 3, 4 and 6 are effective, which shifts every bar but 1-5 and 0-5: 20 bars.
+        assert_eq!(m209.key(), 20);
+
+        let mut m209 = m209;
+        // C = 25 + 20 - 0 (mod 26)
+        assert_eq!(m209.encipher_letter('A'), Some('T'));
+        assert_eq!(m209.key_wheels(), "BBBBBB");
+        assert_eq!(m209.letter_counter(), 1);
+    }
+
+    #[test]
+    fn test_m209_manual_check() {
+        // The check of the manual: with the key wheels at AAAAAA, 26 A's
+        // encipher to this ciphertext, which deciphers back to the A's.
+        let mut m209 = M209::new(PINS, LUGS).unwrap();
+        assert_eq!(
+            m209.encrypt(&"A".repeat(26)),
+            "TNJUW AUQTK CZKNU TOTBC WARMI O"
+        );
+
+        let mut m209 = M209::new(PINS, LUGS).unwrap();
+        assert_eq!(
+            m209.decrypt("TNJUW AUQTK CZKNU TOTBC WARMI O"),
+            "A".repeat(26)
+        );
+        assert_eq!(m209.letter_counter(), 26);
+    }
+
+    #[test]
+    fn test_m209_traced() {
+        let mut m209 = M209::new(PINS, LUGS).unwrap();
+        let (ciphertext, steps) = m209.encipher_traced("A-A");
+        assert_eq!(steps.len(), 2);
+        assert_eq!(
+            steps[0],
+            Step::new('A', 'T', "subtract")
+                .detail("wheels", "AAAAAA")
+                .detail("key", 20)
+        );
+        assert_eq!(steps[1].details[0].1, "BBBBBB");
+        assert_eq!(ciphertext, "TN");
+    }
+
+    #[test]
+    fn test_m209_without_lugs_is_atbash() {
+        let mut m209 = M209::new(["", "", "", "", "", ""], "").unwrap();
+        assert_eq!(m209.encrypt("ABCXYZ"), "ZYXCB A");
+    }
+
+    #[test]
+    fn test_m209_round_trip() {
+        let plaintext = "ATTACK AT DAWN";
+        let mut m209 = M209::new(PINS, LUGS).unwrap();
+        m209.set_key_wheels("GLNCEB").unwrap();
+        let ciphertext = m209.encrypt(plaintext);
+        assert!(ciphertext.split(' ').all(|group| group.len() <= 5));
+
+        m209.set_key_wheels("GLNCEB").unwrap();
+        assert_eq!(m209.decrypt(&ciphertext), plaintext);
+    }
+
+    #[test]
+    fn test_m209_message_indicator() {
+        let plaintext = "THE PIZZA HAS ARRIVED";
+        let mut m209 = M209::new(PINS, LUGS).unwrap();
+        let message = m209.encrypt_message(plaintext, 'G', "ABCDEF").unwrap();
+        assert!(message.starts_with("GG"));
+
+        let mut m209 = M209::new(PINS, LUGS).unwrap();
+        assert_eq!(
+            m209.decrypt_message(&message).unwrap(),
+            "THE PI  A HAS ARRIVED"
+        );
+        assert_eq!(
+            m209.decrypt_message("GH ABCDEFGHIJKL"),
+            Err(M209Error::InvalidIndicator)
+        );
+        // A non-letter in the indicator leaves an odd number of letters.
+        assert_eq!(
+            M209::new([""; 6], "")
+                .unwrap()
+                .decrypt_message("GGAAAAAAAAAA1AAAAAA"),
+            Err(M209Error::InvalidIndicator)
+        );
+    }
+
+    #[test]
+    fn test_m209_invalid_settings() {
+        assert_eq!(
+            M209::new(["", "W", "", "", "", ""], ""),
+            Err(M209Error::InvalidLetter(2, 'W'))
+        );
+        assert_eq!(
+            M209::new(PINS, "1-7"),
+            Err(M209Error::InvalidLug("1-7".to_string()))
+        );
+        assert_eq!(M209::new(PINS, "1-2*28"), Err(M209Error::TooManyBars(28)));
+
+        let mut m209 = M209::new(PINS, LUGS).unwrap();
+        assert_eq!(
+            m209.set_key_wheels("AAAAAZ"),
+            Err(M209Error::InvalidLetter(6, 'Z'))
+        );
+        assert_eq!(
+            m209.set_key_wheels("AAA"),
+            Err(M209Error::InvalidPositions(3))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_m209_serde() {
+        let mut m209 = M209::new(PINS, LUGS).unwrap();
+        m209.set_key_wheels("BCDEFG").unwrap();
+        m209.encrypt("ATTACK");
+        let json = serde_json::to_string(&m209).unwrap();
+        assert_eq!(
+            json,
+            r#"{"pins":["ABDHIKMNSTVW","ADEGJKLORSUX","ABGHJLMNRSTUX","CEFHIMNPSTU","BDEFHIMNPS","ABDHKNOQ"],"lugs":"3-6 0-6 1-6 1-5 4-5 0-4*4 0-2*10 2-5*2 0-5*6","key_wheels":"HIJKLM","letter_counter":6}"#
+        );
+        let mut loaded: M209 = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, m209);
+        assert_eq!(loaded.encrypt("AT DAWN"), m209.encrypt("AT DAWN"));
+
+        let invalid = json.replace("3-6", "3-7");
+        assert!(serde_json::from_str::<M209>(&invalid).is_err());
+    }
+}
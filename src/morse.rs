@@ -0,0 +1,596 @@
+//! An implementation of the International Morse code, and of the Fractionated
+//! Morse and Morbit ciphers built on it.
+//!
+//! Morse code represents characters with sequences of short (dot) and long
+//! (dash) signals. The code of [`Morse`] follows ITU-R M.1677: letters, digits,
+//! punctuation and the procedural signals (prosigns), written between angle
+//! brackets, such as `<SK>` (end of work).
+//!
+//! The Fractionated Morse and Morbit ciphers write the plaintext in Morse code,
+//! with an `x` between letters and `xx` between words, then replace groups of
+//! signals with cipher symbols: the 26 possible trigraphs (all but `xxx`) with
+//! the letters of a keyed alphabet, or the 9 digraphs with digits, as the
+//! Polybius-style coordinate ciphers do with letters.
+
+use crate::columnar::sequentialize;
+use crate::keyed_alphabet::KeyedAlphabet;
+use crate::prelude::*;
+use thiserror::Error;
+
+/// The International Morse code, with `.` for dots and `-` for dashes.
+///
+/// The prosigns `<AR>` and `<BT>` share their codes with `+` and `=`, and are
+/// decoded as the punctuation signs. `$`, `;`, `!` and `_` are not part of
+/// the ITU recommendation, but are in common use.
+const CODE: [(&str, &str); 61] = [
+    ("A", ".-"),
+    ("B", "-..."),
+    ("C", "-.-."),
+    ("D", "-.."),
+    ("E", "."),
+    ("F", "..-."),
+    ("G", "--."),
+    ("H", "...."),
+    ("I", ".."),
+    ("J", ".---"),
+    ("K", "-.-"),
+    ("L", ".-.."),
+    ("M", "--"),
+    ("N", "-."),
+    ("O", "---"),
+    ("P", ".--."),
+    ("Q", "--.-"),
+    ("R", ".-."),
+    ("S", "..."),
+    ("T", "-"),
+    ("U", "..-"),
+    ("V", "...-"),
+    ("W", ".--"),
+    ("X", "-..-"),
+    ("Y", "-.--"),
+    ("Z", "--.."),
+    ("0", "-----"),
+    ("1", ".----"),
+    ("2", "..---"),
+    ("3", "...--"),
+    ("4", "....-"),
+    ("5", "....."),
+    ("6", "-...."),
+    ("7", "--..."),
+    ("8", "---.."),
+    ("9", "----."),
+    (".", ".-.-.-"),
+    (",", "--..--"),
+    (":", "---..."),
+    ("?", "..--.."),
+    ("'", ".----."),
+    ("-", "-....-"),
+    ("/", "-..-."),
+    ("(", "-.--."),
+    (")", "-.--.-"),
+    ("\"", ".-..-."),
+    ("=", "-...-"),
+    ("+", ".-.-."),
+    ("@", ".--.-."),
+    ("!", "-.-.--"),
+    (";", "-.-.-."),
+    ("_", "..--.-"),
+    ("$", "...-..-"),
+    ("<AR>", ".-.-."),
+    ("<AS>", ".-..."),
+    ("<BT>", "-...-"),
+    ("<HH>", "........"),
+    ("<KA>", "-.-.-"),
+    ("<SK>", "...-.-"),
+    ("<SN>", "...-."),
+    ("<SOS>", "...---..."),
+];
+
+/// The separator of letters in the Fractionated Morse and Morbit ciphers.
+const SEPARATOR: char = 'x';
+
+/// The number of Morbit digraphs.
+const MORBIT_DIGRAPHS: usize = 9;
+
+/// Error type for the Morse code.
+#[derive(Error, Debug, PartialEq)]
+pub enum MorseError {
+    #[error("Character has no Morse code: {0}")]
+    UnsupportedChar(char),
+    #[error("Unknown prosign: {0}")]
+    UnknownProsign(String),
+    #[error("Invalid Morse code: {0}")]
+    InvalidCode(String),
+    #[error("The dot, the dash and the separators must be distinct and non-empty, and the word separator cannot be part of the letter separator")]
+    InvalidSymbols,
+    #[error("The Morbit keyword must have at least 9 letters")]
+    ShortKeyword,
+    #[error("Invalid ciphertext character: {0}")]
+    InvalidCiphertext(char),
+}
+
+/// The symbols used to write Morse code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Morse {
+    dot: char,
+    dash: char,
+    letter_separator: String,
+    word_separator: String,
+}
+
+impl Morse {
+    /// Creates the usual notation: `.` and `-`, a space between letters and ` / ` between words.
+    pub fn new() -> Self {
+        Morse {
+            dot: '.',
+            dash: '-',
+            letter_separator: " ".to_string(),
+            word_separator: " / ".to_string(),
+        }
+    }
+
+    /// Sets the symbol of the dot.
+    pub fn dot(mut self, dot: char) -> Self {
+        self.dot = dot;
+        self
+    }
+
+    /// Sets the symbol of the dash.
+    pub fn dash(mut self, dash: char) -> Self {
+        self.dash = dash;
+        self
+    }
+
+    /// Sets the separator of letters.
+    pub fn letter_separator(mut self, separator: &str) -> Self {
+        self.letter_separator = separator.to_string();
+        self
+    }
+
+    /// Sets the separator of words.
+    pub fn word_separator(mut self, separator: &str) -> Self {
+        self.word_separator = separator.to_string();
+        self
+    }
+
+    /// Checks that the symbols can be told apart.
+    ///
+    /// Words are split first, so the letter separator may be part of the word
+    /// separator (as in the usual notation), but not the other way around.
+    fn validate(&self) -> Result<(), MorseError> {
+        let separators = [&self.letter_separator, &self.word_separator];
+        if self.dot == self.dash
+            || separators
+                .iter()
+                .any(|s| s.is_empty() || s.contains([self.dot, self.dash]))
+            || self.letter_separator.contains(self.word_separator.as_str())
+        {
+            return Err(MorseError::InvalidSymbols);
+        }
+        Ok(())
+    }
+
+    /// Encodes a text in Morse code.
+    ///
+    /// Letters are encoded in either case, and words are separated by whitespace.
+    /// Prosigns are written between angle brackets, e.g. `<SK>`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to be encoded.
+    ///
+    /// # Returns
+    ///
+    /// The Morse code, or an error if a character has no code or the symbols are ambiguous.
+    pub fn encode(&self, text: &str) -> Result<String, MorseError> {
+        self.validate()?;
+        let words = text
+            .split_whitespace()
+            .map(|word| {
+                Ok(codes(word)?
+                    .iter()
+                    .map(|code| self.write(code))
+                    .collect::<Vec<_>>()
+                    .join(&self.letter_separator))
+            })
+            .collect::<Result<Vec<_>, MorseError>>()?;
+        Ok(words.join(&self.word_separator))
+    }
+
+    /// Decodes Morse code.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The Morse code to be decoded.
+    ///
+    /// # Returns
+    ///
+    /// The text in uppercase, with a space between words, or an error if a code is unknown.
+    pub fn decode(&self, code: &str) -> Result<String, MorseError> {
+        self.validate()?;
+        let words = code
+            .split(self.word_separator.as_str())
+            .filter(|word| !word.trim().is_empty())
+            .map(|word| {
+                word.split(self.letter_separator.as_str())
+                    .map(str::trim)
+                    .filter(|letter| !letter.is_empty())
+                    .map(|letter| {
+                        let code = self.read(letter)?;
+                        character(&code).ok_or(MorseError::InvalidCode(letter.to_string()))
+                    })
+                    .collect::<Result<String, MorseError>>()
+            })
+            .collect::<Result<Vec<_>, MorseError>>()?;
+        Ok(words.join(" "))
+    }
+
+    /// Writes a code with the dot and dash symbols.
+    fn write(&self, code: &str) -> String {
+        code.chars()
+            .map(|c| if c == '.' { self.dot } else { self.dash })
+            .collect()
+    }
+
+    /// Reads a code written with the dot and dash symbols.
+    fn read(&self, letter: &str) -> Result<String, MorseError> {
+        letter
+            .chars()
+            .map(|c| match c {
+                c if c == self.dot => Ok('.'),
+                c if c == self.dash => Ok('-'),
+                _ => Err(MorseError::InvalidCode(letter.to_string())),
+            })
+            .collect()
+    }
+}
+
+impl Default for Morse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Encodes a text in Morse code, with the usual notation of [`Morse::new`].
+///
+/// # Arguments
+///
+/// * `text` - The text to be encoded.
+///
+/// # Returns
+///
+/// The Morse code, or an error if a character has no code.
+pub fn encode(text: &str) -> Result<String, MorseError> {
+    Morse::new().encode(text)
+}
+
+/// Decodes Morse code written with the usual notation of [`Morse::new`].
+///
+/// # Arguments
+///
+/// * `code` - The Morse code to be decoded.
+///
+/// # Returns
+///
+/// The text in uppercase, or an error if a code is unknown.
+pub fn decode(code: &str) -> Result<String, MorseError> {
+    Morse::new().decode(code)
+}
+
+/// Encrypts the provided plaintext using the Fractionated Morse cipher.
+///
+/// The Morse code of the plaintext, with `x` between letters and `xx` between
+/// words, is padded with `x` to a multiple of three signals. Each trigraph is
+/// replaced with a letter of the keyed alphabet: `...` with the first letter,
+/// `..-` with the second, and so on up to `xx-`, in the order `.`, `-`, `x`.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+/// * `keyword` - The keyword of the alphabet.
+///
+/// # Returns
+///
+/// The ciphertext in uppercase, or an error if a character has no Morse code.
+pub fn encrypt_fractionated(plaintext: &str, keyword: &str) -> Result<String, MorseError> {
+    let alphabet = KeyedAlphabet::new(keyword);
+    let signals = fractionate(plaintext, 3)?;
+    Ok(signals
+        .chunks(3)
+        .map(|trigraph| alphabet.char_at(trigraph.iter().fold(0, |idx, &s| idx * 3 + s)))
+        .collect())
+}
+
+/// Decrypts the provided ciphertext using the Fractionated Morse cipher.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted. Whitespace is ignored.
+/// * `keyword` - The keyword of the alphabet.
+///
+/// # Returns
+///
+/// The plaintext in uppercase, or an error if the ciphertext is invalid.
+pub fn decrypt_fractionated(ciphertext: &str, keyword: &str) -> Result<String, MorseError> {
+    let alphabet = KeyedAlphabet::new(keyword);
+    let signals = ciphertext
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| {
+            let idx = alphabet
+                .index_of(c)
+                .ok_or(MorseError::InvalidCiphertext(c))?;
+            Ok([idx / 9, idx / 3 % 3, idx % 3])
+        })
+        .collect::<Result<Vec<_>, MorseError>>()?;
+    defractionate(&signals.concat())
+}
+
+/// Encrypts the provided plaintext using the Morbit cipher.
+///
+/// The Morse code of the plaintext, with `x` between letters and `xx` between
+/// words, is padded with `x` to a multiple of two signals. The nine digraphs
+/// `..`, `.-`, `.x`, `-.`, `--`, `-x`, `x.`, `x-` and `xx` are numbered with the
+/// alphabetical order of the first nine letters of the keyword.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+/// * `keyword` - The keyword, of at least nine letters.
+///
+/// # Returns
+///
+/// The ciphertext as digits, or an error if the keyword is too short or a character has no Morse code.
+pub fn encrypt_morbit(plaintext: &str, keyword: &str) -> Result<String, MorseError> {
+    let digits = morbit_digits(keyword)?;
+    let signals = fractionate(plaintext, 2)?;
+    Ok(signals
+        .chunks(2)
+        .map(|digraph| digits[digraph[0] * 3 + digraph[1]])
+        .collect())
+}
+
+/// Decrypts the provided ciphertext using the Morbit cipher.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The digits to be decrypted. Whitespace is ignored.
+/// * `keyword` - The keyword, of at least nine letters.
+///
+/// # Returns
+///
+/// The plaintext in uppercase, or an error if the keyword is too short or the ciphertext is invalid.
+pub fn decrypt_morbit(ciphertext: &str, keyword: &str) -> Result<String, MorseError> {
+    let digits = morbit_digits(keyword)?;
+    let signals = ciphertext
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| {
+            let idx = digits
+                .iter()
+                .position(|&d| d == c)
+                .ok_or(MorseError::InvalidCiphertext(c))?;
+            Ok([idx / 3, idx % 3])
+        })
+        .collect::<Result<Vec<_>, MorseError>>()?;
+    defractionate(&signals.concat())
+}
+
+/// Returns the Morbit digit of each digraph.
+fn morbit_digits(keyword: &str) -> Result<[char; MORBIT_DIGRAPHS], MorseError> {
+    let letters: Vec<u8> = keyword
+        .bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|b| b.to_ascii_uppercase())
+        .take(MORBIT_DIGRAPHS)
+        .collect();
+    if letters.len() < MORBIT_DIGRAPHS {
+        return Err(MorseError::ShortKeyword);
+    }
+
+    let ranks = sequentialize(&letters);
+    Ok(core::array::from_fn(|idx| {
+        char::from_digit(ranks[idx] as u32, 10).unwrap_or_default()
+    }))
+}
+
+/// Writes a text as signals (0 for a dot, 1 for a dash, 2 for a separator),
+/// padded with separators to a multiple of `group`.
+fn fractionate(text: &str, group: usize) -> Result<Vec<usize>, MorseError> {
+    let mut signals = Vec::new();
+    for (idx, word) in text.split_whitespace().enumerate() {
+        if idx > 0 {
+            signals.extend([2, 2]);
+        }
+        for (idx, code) in codes(word)?.iter().enumerate() {
+            if idx > 0 {
+                signals.push(2);
+            }
+            signals.extend(code.chars().map(|c| usize::from(c == '-')));
+        }
+    }
+    signals.resize(signals.len().div_ceil(group) * group, 2);
+    Ok(signals)
+}
+
+/// Reads a text from signals (0 for a dot, 1 for a dash, 2 for a separator).
+fn defractionate(signals: &[usize]) -> Result<String, MorseError> {
+    let morse: String = signals.iter().map(|&s| ['.', '-', SEPARATOR][s]).collect();
+    let morse = morse.trim_matches(SEPARATOR);
+    if morse.is_empty() {
+        return Ok(String::new());
+    }
+
+    let text = morse
+        .split("xx")
+        .map(|word| {
+            word.split(SEPARATOR)
+                .map(|code| character(code).ok_or(MorseError::InvalidCode(code.to_string())))
+                .collect::<Result<String, MorseError>>()
+        })
+        .collect::<Result<Vec<_>, MorseError>>()?;
+    Ok(text.join(" "))
+}
+
+/// Splits a word into characters and prosigns, and returns their codes.
+fn codes(word: &str) -> Result<Vec<&'static str>, MorseError> {
+    let mut codes = Vec::new();
+    let mut chars = word.char_indices();
+    while let Some((start, c)) = chars.next() {
+        let symbol = if c == '<' {
+            let end = word[start..]
+                .find('>')
+                .map(|end| start + end + 1)
+                .ok_or_else(|| MorseError::UnknownProsign(word[start..].to_string()))?;
+            chars.nth(end - start - 2);
+            word[start..end].to_ascii_uppercase()
+        } else {
+            c.to_ascii_uppercase().to_string()
+        };
+
+        let code = CODE
+            .iter()
+            .find(|(s, _)| *s == symbol)
+            .map(|(_, code)| *code)
+            .ok_or_else(|| match c {
+                '<' => MorseError::UnknownProsign(symbol.clone()),
+                _ => MorseError::UnsupportedChar(c),
+            })?;
+        codes.push(code);
+    }
+    Ok(codes)
+}
+
+/// Returns the character or prosign of a code written with `.` and `-`.
+fn character(code: &str) -> Option<&'static str> {
+    CODE.iter().find(|(_, c)| *c == code).map(|(s, _)| *s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_morse_encode() {
+        assert_eq!(encode("SOS").unwrap(), "... --- ...");
+        assert_eq!(
+            encode("Hello world").unwrap(),
+            ".... . .-.. .-.. --- / .-- --- .-. .-.. -.."
+        );
+        assert_eq!(
+            encode("73, QTH?").unwrap(),
+            "--... ...-- --..-- / --.- - .... ..--.."
+        );
+        assert_eq!(encode("<sos> <SK>").unwrap(), "...---... / ...-.-");
+        assert_eq!(encode("a#"), Err(MorseError::UnsupportedChar('#')));
+        assert_eq!(
+            encode("<XYZ>"),
+            Err(MorseError::UnknownProsign("<XYZ>".to_string()))
+        );
+        assert_eq!(
+            encode("<SK"),
+            Err(MorseError::UnknownProsign("<SK".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_morse_decode() {
+        assert_eq!(
+            decode(".... . .-.. .-.. --- / .-- --- .-. .-.. -..").unwrap(),
+            "HELLO WORLD"
+        );
+        assert_eq!(decode("...-.- / ........").unwrap(), "<SK> <HH>");
+        // <AR> shares its code with the plus sign.
+        assert_eq!(decode(encode("<AR>").unwrap().as_str()).unwrap(), "+");
+        assert_eq!(
+            decode(".-.-.-.-"),
+            Err(MorseError::InvalidCode(".-.-.-.-".to_string()))
+        );
+        assert_eq!(
+            decode(".- ?"),
+            Err(MorseError::InvalidCode("?".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_morse_custom_symbols() {
+        let morse = Morse::new()
+            .dot('0')
+            .dash('1')
+            .letter_separator("|")
+            .word_separator("||");
+        assert_eq!(
+            morse.encode("RUST 2021").unwrap(),
+            "010|001|000|1||00111|11111|00111|01111"
+        );
+        assert_eq!(
+            morse
+                .decode("010|001|000|1||00111|11111|00111|01111")
+                .unwrap(),
+            "RUST 2021"
+        );
+
+        assert_eq!(
+            Morse::new().dash('.').encode("E"),
+            Err(MorseError::InvalidSymbols)
+        );
+        assert_eq!(
+            Morse::new().word_separator(" ").decode("."),
+            Err(MorseError::InvalidSymbols)
+        );
+        assert_eq!(
+            Morse::new()
+                .letter_separator("//")
+                .word_separator("/")
+                .decode(".-//-"),
+            Err(MorseError::InvalidSymbols)
+        );
+    }
+
+    #[test]
+    fn test_fractionated_morse() {
+        // ".x-" is the trigraph (dot, separator, dash), the 8th of the alphabet.
+        assert_eq!(encrypt_fractionated("ET", "").unwrap(), "H");
+        // "." is padded to ".xx", the 9th trigraph.
+        assert_eq!(encrypt_fractionated("E", "").unwrap(), "I");
+
+        assert_eq!(
+            encrypt_fractionated("Come at once", "ROUNDTABLE").unwrap(),
+            "CBIILTMHVVFL"
+        );
+        assert_eq!(
+            decrypt_fractionated("CBIILTMHVVFL", "ROUNDTABLE").unwrap(),
+            "COME AT ONCE"
+        );
+
+        let ciphertext = encrypt_fractionated("Come at once, 10 PM", "ROUNDTABLE").unwrap();
+        assert!(ciphertext.chars().all(|c| c.is_ascii_uppercase()));
+        assert_eq!(
+            decrypt_fractionated(&ciphertext, "ROUNDTABLE").unwrap(),
+            "COME AT ONCE, 10 PM"
+        );
+        assert_eq!(
+            decrypt_fractionated("A1", "ROUNDTABLE"),
+            Err(MorseError::InvalidCiphertext('1'))
+        );
+    }
+
+    #[test]
+    fn test_morbit() {
+        // WISECRACK numbers the digraphs 9 5 8 4 2 7 1 3 6.
+        assert_eq!(encrypt_morbit("E", "WISECRACK").unwrap(), "8");
+        assert_eq!(encrypt_morbit("ET", "WISECRACK").unwrap(), "87");
+
+        let ciphertext = encrypt_morbit("Once upon a time", "WISECRACK").unwrap();
+        assert!(ciphertext.chars().all(|c| c.is_ascii_digit()));
+        assert_eq!(
+            decrypt_morbit(&ciphertext, "WISECRACK").unwrap(),
+            "ONCE UPON A TIME"
+        );
+        assert_eq!(encrypt_morbit("E", "SHORT"), Err(MorseError::ShortKeyword));
+        assert_eq!(
+            decrypt_morbit("80", "WISECRACK"),
+            Err(MorseError::InvalidCiphertext('0'))
+        );
+    }
+}
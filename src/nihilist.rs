@@ -0,0 +1,409 @@
+//! An implementation of the Nihilist ciphers.
+//!
+//! The Nihilist substitution cipher was used by Russian Nihilists in the 1880s.
+//! Letters are replaced with their coordinates in a keyed Polybius square, as
+//! two-digit numbers (row then column, starting at 1), the same numbering as
+//! [`crate::polybius_square::encrypt`]. A key word is converted in the same way,
+//! and its numbers are added to those of the plaintext, repeating as needed.
+//!
+//! The Nihilist transposition writes the text in a square as wide as its key,
+//! and permutes both the columns and the rows in the alphabetical order of the key.
+
+use crate::columnar::sequentialize;
+use crate::key_square::{KeySquare, SquareAlphabet, SIZE};
+use crate::prelude::*;
+use crate::trace::Step;
+use thiserror::Error;
+
+/// The numbers of the letters of the square, from the first to the last cell.
+const SQUARE_NUMBERS: core::ops::RangeInclusive<u32> = 11..=(SIZE as u32 * 11);
+
+/// Error type for the Nihilist ciphers.
+#[derive(Error, Debug, PartialEq)]
+pub enum NihilistError {
+    #[error("Key must contain at least one letter")]
+    EmptyKey,
+    #[error("Invalid number in the ciphertext: {0}")]
+    InvalidNumber(String),
+}
+
+/// Encrypts the provided plaintext using the Nihilist substitution cipher.
+///
+/// Letters are normalized to the square (`J` is merged into `I`), and other characters are dropped.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+/// * `keyword` - The keyword of the Polybius square.
+/// * `key` - The additive key.
+///
+/// # Returns
+///
+/// The ciphertext, as numbers separated by spaces, or an error if the key has no letters.
+pub fn encrypt(plaintext: &str, keyword: &str, key: &str) -> Result<String, NihilistError> {
+    let square = KeySquare::new(keyword, SquareAlphabet::MergeJ);
+    let key = numbers(&square, key);
+    if key.is_empty() {
+        return Err(NihilistError::EmptyKey);
+    }
+
+    Ok(numbers(&square, plaintext)
+        .iter()
+        .zip(key.iter().cycle())
+        .map(|(p, k)| (p + k).to_string())
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// Encrypts the provided plaintext using the Nihilist substitution cipher, recording every step.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+/// * `keyword` - The keyword of the Polybius square.
+/// * `key` - The additive key.
+///
+/// # Returns
+///
+/// The ciphertext, as [`encrypt`], and one step per letter with the number of
+/// the letter in the square and the number of the key letter added to it, or
+/// an error if the key has no letters.
+pub fn encrypt_traced(
+    plaintext: &str,
+    keyword: &str,
+    key: &str,
+) -> Result<(String, Vec<Step>), NihilistError> {
+    let square = KeySquare::new(keyword, SquareAlphabet::MergeJ);
+    let key = numbers(&square, key);
+    if key.is_empty() {
+        return Err(NihilistError::EmptyKey);
+    }
+
+    let letters: Vec<char> = plaintext
+        .chars()
+        .filter_map(|c| square.alphabet().normalize(c))
+        .collect();
+    let steps: Vec<Step> = letters
+        .iter()
+        .zip(numbers(&square, plaintext))
+        .zip(key.iter().cycle())
+        .map(|((&letter, p), k)| {
+            Step::new(letter, (p + k).to_string(), "add")
+                .detail("number", p)
+                .detail("key", k)
+        })
+        .collect();
+    let ciphertext = steps
+        .iter()
+        .map(|step| step.output.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Ok((ciphertext, steps))
+}
+
+/// Decrypts the provided ciphertext using the Nihilist substitution cipher.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The numbers to be decrypted, separated by whitespace.
+/// * `keyword` - The keyword of the Polybius square.
+/// * `key` - The additive key.
+///
+/// # Returns
+///
+/// The plaintext in uppercase, or an error if the key is empty or a number cannot be decrypted.
+pub fn decrypt(ciphertext: &str, keyword: &str, key: &str) -> Result<String, NihilistError> {
+    let square = KeySquare::new(keyword, SquareAlphabet::MergeJ);
+    let key = numbers(&square, key);
+    if key.is_empty() {
+        return Err(NihilistError::EmptyKey);
+    }
+
+    ciphertext
+        .split_whitespace()
+        .zip(key.iter().cycle())
+        .map(|(token, k)| {
+            let invalid = || NihilistError::InvalidNumber(token.to_string());
+            let number = token.parse::<u32>().map_err(|_| invalid())?;
+            let (row, col) = number
+                .checked_sub(*k)
+                .filter(|n| SQUARE_NUMBERS.contains(n))
+                .map(digits)
+                .ok_or_else(invalid)?;
+            if !(1..=SIZE as u32).contains(&row) || !(1..=SIZE as u32).contains(&col) {
+                return Err(invalid());
+            }
+            Ok(square.char_at(row as usize - 1, col as usize - 1))
+        })
+        .collect()
+}
+
+/// Finds the period of the additive key of a Nihilist substitution ciphertext.
+///
+/// When the numbers are split into columns according to the right period,
+/// every number of a column is the sum of the same key number and of a number
+/// whose digits are between 1 and 5. The tens and the units of a column thus
+/// each span at most five consecutive values. The smallest period for which
+/// every column is consistent is returned.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The numbers of the ciphertext, separated by whitespace.
+/// * `max_period` - The largest period to try.
+///
+/// # Returns
+///
+/// The period, `None` if no period up to `max_period` fits, or an error if the ciphertext is invalid.
+pub fn find_period(ciphertext: &str, max_period: usize) -> Result<Option<usize>, NihilistError> {
+    let sums = parse_numbers(ciphertext)?;
+    Ok((1..=max_period.min(sums.len()))
+        .find(|&period| (0..period).all(|offset| !column_keys(&sums, period, offset).is_empty())))
+}
+
+/// Lists, for every position of the key, the key numbers consistent with the ciphertext.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The numbers of the ciphertext, separated by whitespace.
+/// * `period` - The period of the key, e.g. found by [`find_period`].
+///
+/// # Returns
+///
+/// The possible key numbers for each position of the key, or an error if the ciphertext is invalid.
+pub fn key_candidates(ciphertext: &str, period: usize) -> Result<Vec<Vec<u32>>, NihilistError> {
+    let sums = parse_numbers(ciphertext)?;
+    Ok((0..period)
+        .map(|offset| column_keys(&sums, period, offset))
+        .collect())
+}
+
+/// Encrypts the provided plaintext using the Nihilist transposition cipher.
+///
+/// The letters are written row by row in squares as wide as the key, the last
+/// one being padded with `X`. The columns, then the rows, of each square are
+/// reordered in the alphabetical order of the key, and the square is read row by row.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted. Non-alphabetic characters are dropped.
+/// * `key` - The transposition key.
+///
+/// # Returns
+///
+/// The ciphertext in uppercase, or an error if the key has no letters.
+pub fn encrypt_transposition(plaintext: &str, key: &str) -> Result<String, NihilistError> {
+    transposition(plaintext, key, false)
+}
+
+/// Decrypts the provided ciphertext using the Nihilist transposition cipher.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted.
+/// * `key` - The transposition key.
+///
+/// # Returns
+///
+/// The plaintext in uppercase, including the padding, or an error if the key has no letters.
+pub fn decrypt_transposition(ciphertext: &str, key: &str) -> Result<String, NihilistError> {
+    transposition(ciphertext, key, true)
+}
+
+/// Core logic of the transposition: permutes the rows and columns of each square.
+fn transposition(text: &str, key: &str, inverse: bool) -> Result<String, NihilistError> {
+    let key: Vec<u8> = key
+        .bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|b| b.to_ascii_uppercase())
+        .collect();
+    if key.is_empty() {
+        return Err(NihilistError::EmptyKey);
+    }
+
+    // The column read in each position of the transposed square.
+    let ranks = sequentialize(&key);
+    let mut order = vec![0; key.len()];
+    for (col, &rank) in ranks.iter().enumerate() {
+        order[rank - 1] = col;
+    }
+
+    let width = key.len();
+    let mut letters: Vec<char> = text
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let block = width * width;
+    letters.resize(letters.len().div_ceil(block) * block, 'X');
+
+    let mut output = vec![' '; letters.len()];
+    for (square, out) in letters.chunks(block).zip(output.chunks_mut(block)) {
+        for row in 0..width {
+            for col in 0..width {
+                let source = order[row] * width + order[col];
+                if inverse {
+                    out[source] = square[row * width + col];
+                } else {
+                    out[row * width + col] = square[source];
+                }
+            }
+        }
+    }
+
+    Ok(output.into_iter().collect())
+}
+
+/// Converts the letters of a text to their numbers in the square.
+fn numbers(square: &KeySquare, text: &str) -> Vec<u32> {
+    text.chars()
+        .filter_map(|c| square.alphabet().normalize(c))
+        .filter_map(|c| square.position(c))
+        .map(|(row, col)| ((row + 1) * 10 + col + 1) as u32)
+        .collect()
+}
+
+/// Parses the numbers of a ciphertext, each the sum of two numbers of the square.
+fn parse_numbers(ciphertext: &str) -> Result<Vec<u32>, NihilistError> {
+    let sums = 2 * SQUARE_NUMBERS.start()..=2 * SQUARE_NUMBERS.end();
+    ciphertext
+        .split_whitespace()
+        .map(|token| {
+            token
+                .parse::<u32>()
+                .ok()
+                .filter(|sum| sums.contains(sum))
+                .ok_or_else(|| NihilistError::InvalidNumber(token.to_string()))
+        })
+        .collect()
+}
+
+/// Splits a number into its tens and units, as a sum of two-digit numbers.
+///
+/// Since each digit of the sum can reach 10, a units digit of 0 stands for 10.
+/// The number must be at least 10.
+fn digits(number: u32) -> (u32, u32) {
+    match number % 10 {
+        0 => (number / 10 - 1, 10),
+        units => (number / 10, units),
+    }
+}
+
+/// Returns the key numbers consistent with one column of the ciphertext.
+fn column_keys(sums: &[u32], period: usize, offset: usize) -> Vec<u32> {
+    let column: Vec<(u32, u32)> = sums
+        .iter()
+        .skip(offset)
+        .step_by(period)
+        .map(|&sum| digits(sum))
+        .collect();
+
+    let size = SIZE as u32;
+    let mut keys = Vec::new();
+    for tens in 1..=size {
+        for units in 1..=size {
+            let valid = column.iter().all(|&(t, u)| {
+                (tens + 1..=tens + size).contains(&t) && (units + 1..=units + size).contains(&u)
+            });
+            if valid {
+                keys.push(tens * 10 + units);
+            }
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CIPHERTEXT: &str = "37 106 62 36 67 47 86 26 104 53 62 77 27 55 57 66 55 36 54 27";
+
+    #[test]
+    fn test_nihilist_encrypt() {
+        assert_eq!(
+            encrypt("DYNAMITE WINTER PALACE", "ZEBRAS", "RUSSIAN").unwrap(),
+            CIPHERTEXT
+        );
+    }
+
+    #[test]
+    fn test_nihilist_encrypt_traced() {
+        let (ciphertext, steps) =
+            encrypt_traced("DYNAMITE WINTER PALACE", "ZEBRAS", "RUSSIAN").unwrap();
+        assert_eq!(ciphertext, CIPHERTEXT);
+        assert_eq!(
+            steps[0],
+            Step::new('D', "37", "add")
+                .detail("number", 23)
+                .detail("key", 14)
+        );
+        assert_eq!(steps.len(), 20);
+    }
+
+    #[test]
+    fn test_nihilist_decrypt() {
+        assert_eq!(
+            decrypt(CIPHERTEXT, "ZEBRAS", "RUSSIAN").unwrap(),
+            "DYNAMITEWINTERPALACE"
+        );
+        assert_eq!(
+            decrypt("37 5", "ZEBRAS", "RUSSIAN"),
+            Err(NihilistError::InvalidNumber("5".to_string()))
+        );
+        assert_eq!(
+            decrypt("37 1a", "ZEBRAS", "RUSSIAN"),
+            Err(NihilistError::InvalidNumber("1a".to_string()))
+        );
+        // 14 minus the key number of R (14) leaves no letter.
+        assert_eq!(
+            decrypt("14", "ZEBRAS", "RUSSIAN"),
+            Err(NihilistError::InvalidNumber("14".to_string()))
+        );
+        assert_eq!(
+            find_period("0", 5),
+            Err(NihilistError::InvalidNumber("0".to_string()))
+        );
+        assert_eq!(
+            find_period("37 111", 5),
+            Err(NihilistError::InvalidNumber("111".to_string()))
+        );
+        assert_eq!(
+            encrypt("HELLO", "ZEBRAS", "123"),
+            Err(NihilistError::EmptyKey)
+        );
+    }
+
+    #[test]
+    fn test_nihilist_find_period() {
+        let plaintext = "THE NIHILISTS USED THIS CIPHER IN THEIR PRISON CORRESPONDENCE \
+            AND KNOCKED THE NUMBERS ON THE WALLS BETWEEN THE CELLS";
+        let ciphertext = encrypt(plaintext, "ZEBRAS", "RUSSIAN").unwrap();
+        assert_eq!(find_period(&ciphertext, 20).unwrap(), Some(7));
+
+        // "RUSSIAN" is 14 51 21 21 32 15 41 in the ZEBRAS square.
+        let candidates = key_candidates(&ciphertext, 7).unwrap();
+        for (position, key) in [14, 51, 21, 21, 32, 15, 41].into_iter().enumerate() {
+            assert!(candidates[position].contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_nihilist_transposition() {
+        // "BAC" numbers its columns 2, 1, 3: rows and columns are both read in the order 2, 1, 3.
+        assert_eq!(
+            encrypt_transposition("ABCDEFGHI", "BAC").unwrap(),
+            "EDFBACHGI"
+        );
+        assert_eq!(
+            decrypt_transposition("EDFBACHGI", "BAC").unwrap(),
+            "ABCDEFGHI"
+        );
+
+        let ciphertext = encrypt_transposition("Attack at dawn", "KEY").unwrap();
+        assert_eq!(ciphertext.len(), 18);
+        assert_eq!(
+            decrypt_transposition(&ciphertext, "KEY").unwrap(),
+            "ATTACKATDAWNXXXXXX"
+        );
+    }
+}
@@ -1,17 +1,158 @@
 //! An implementation of the One-Time Pad (OTP) cipher.
 
+#[cfg(feature = "std")]
+use crate::cipher::{Cipher, CipherError, TextKind};
+use crate::prelude::*;
+#[cfg(feature = "std")]
 use rand::rngs::OsRng;
 use rand::RngCore;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, SeekFrom};
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Error type for the One-Time Pad.
+#[derive(Error, Debug, PartialEq)]
+pub enum OtpError {
+    #[error("The data is {0} bytes long but the key is {1} bytes long")]
+    LengthMismatch(usize, usize),
+}
+
+/// A reference to a one-time pad stored in a file, for use through the [`Cipher`] trait.
+///
+/// Pads are not stored along with the configuration of a cipher: the reference
+/// only names the file and the offset of the pad in it. Through the trait, a
+/// message of `n` bytes uses the `n` bytes of the pad from the offset, and the
+/// ciphertext is written in hexadecimal.
+///
+/// # Pad reuse
+///
+/// The reference does not advance on its own: every message encrypted with the
+/// same `PadRef` uses the same bytes of the pad, which makes it a two-time pad
+/// that is easily broken (see [`crate::stream::attack`]). Encrypt a single
+/// message with each reference, and use [`PadRef::next`] to get the reference
+/// to the rest of the pad.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PadRef {
+    /// The path of the pad file.
+    pub path: PathBuf,
+    /// The offset of the pad in the file, in bytes.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub offset: u64,
+}
+
+#[cfg(feature = "std")]
+impl PadRef {
+    /// Creates a reference to a pad.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the pad file.
+    /// * `offset` - The offset of the pad in the file, in bytes.
+    pub fn new(path: impl Into<PathBuf>, offset: u64) -> Self {
+        PadRef {
+            path: path.into(),
+            offset,
+        }
+    }
+
+    /// Returns the reference to the rest of the pad, after a message.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - The length of the message, in bytes.
+    ///
+    /// # Returns
+    ///
+    /// A reference to the same file, with an offset past the bytes used by the message.
+    pub fn next(&self, len: usize) -> Self {
+        PadRef {
+            path: self.path.clone(),
+            offset: self.offset + len as u64,
+        }
+    }
+
+    /// Reads the key for a message from the pad file.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - The length of the message, in bytes.
+    ///
+    /// # Returns
+    ///
+    /// The `len` bytes of the pad from the offset, or an error if the file
+    /// cannot be read or is too short.
+    pub fn read(&self, len: usize) -> Result<Vec<u8>, CipherError> {
+        let error =
+            |err: std::io::Error| CipherError::Pad(format!("{}: {}", self.path.display(), err));
+        let mut file = File::open(&self.path).map_err(error)?;
+        file.seek(SeekFrom::Start(self.offset)).map_err(error)?;
+        let mut key = vec![0u8; len];
+        file.read_exact(&mut key).map_err(error)?;
+        Ok(key)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Cipher for PadRef {
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let ciphertext = encrypt(plaintext, &self.read(plaintext.len())?);
+        Ok(ciphertext.iter().map(|b| format!("{:02X}", b)).collect())
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let digits: Vec<char> = ciphertext.chars().filter(|c| !c.is_whitespace()).collect();
+        let bytes = digits
+            .chunks(2)
+            .map(|pair| {
+                let pair: String = pair.iter().collect();
+                u8::from_str_radix(&pair, 16)
+                    .ok()
+                    .filter(|_| pair.len() == 2)
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| CipherError::Pad("the ciphertext is not hexadecimal".to_string()))?;
+        let plaintext = xor(&bytes, &self.read(bytes.len())?);
+        String::from_utf8(plaintext)
+            .map_err(|_| CipherError::Pad("the plaintext is not valid UTF-8".to_string()))
+    }
+
+    fn output_kind(&self, input: TextKind) -> Option<TextKind> {
+        (input != TextKind::Hex).then_some(TextKind::Hex)
+    }
+}
 
 /// Generates a key of the specified length using true randomness.
 ///
+/// The randomness is provided by the operating system. Without the `std`
+/// feature, use [`generate_key_with`] and a hardware random number generator.
+///
 /// # Arguments
 ///
 /// * `len` - The desired length of the key, usually derived from
-/// the length of the plaintext and/or ciphertext.
+///   the length of the plaintext and/or ciphertext.
+#[cfg(feature = "std")]
 pub fn generate_key(len: usize) -> Vec<u8> {
+    generate_key_with(&mut OsRng, len)
+}
+
+/// Generates a key of the specified length using the given random number generator.
+///
+/// The key is only as good as the generator: it must be cryptographically
+/// secure, and ideally a source of true randomness.
+///
+/// # Arguments
+///
+/// * `rng` - The random number generator.
+/// * `len` - The desired length of the key.
+pub fn generate_key_with(rng: &mut impl RngCore, len: usize) -> Vec<u8> {
     let mut key = vec![0u8; len];
-    OsRng.fill_bytes(&mut key);
+    rng.fill_bytes(&mut key);
     key
 }
 
@@ -25,8 +166,12 @@ pub fn generate_key(len: usize) -> Vec<u8> {
 /// # Returns
 ///
 /// A "ciphertext" as bytes.
+///
+/// # Panics
+///
+/// Panics if the plaintext and the key do not have the same length.
 pub fn encrypt(plaintext: &str, key: &[u8]) -> Vec<u8> {
-    encipher(plaintext.as_bytes(), key)
+    encipher(plaintext.as_bytes(), key).expect("The lengths of the data and the key do not match!")
 }
 
 /// Decrypts the given ciphertext using the specified key.
@@ -39,8 +184,13 @@ pub fn encrypt(plaintext: &str, key: &[u8]) -> Vec<u8> {
 /// # Returns
 ///
 /// The original plaintext (string).
+///
+/// # Panics
+///
+/// Panics if the ciphertext and the key do not have the same length.
 pub fn decrypt(ciphertext: &[u8], key: &[u8]) -> String {
-    let plaintext_bytes = encipher(ciphertext, key);
+    let plaintext_bytes =
+        encipher(ciphertext, key).expect("The lengths of the data and the key do not match!");
     unsafe { String::from_utf8_unchecked(plaintext_bytes) }
 }
 
@@ -51,18 +201,31 @@ pub fn decrypt(ciphertext: &[u8], key: &[u8]) -> String {
 ///
 /// * `data` - The plaintext (for encryption) or ciphertext (for decryption).
 /// * `key` - The key to use for encryption/decryption.
-fn encipher(data: &[u8], key: &[u8]) -> Vec<u8> {
+///
+/// # Returns
+///
+/// The data XORed with the key, or an error if the data and the key do not
+/// have the same length.
+pub fn encipher(data: &[u8], key: &[u8]) -> Result<Vec<u8>, OtpError> {
     if data.len() != key.len() {
-        panic!("The lengths of the data and the key do not match!");
+        return Err(OtpError::LengthMismatch(data.len(), key.len()));
     }
 
+    Ok(xor(data, key))
+}
+
+/// XORs the data with a key of the same length, e.g. a keystream.
+pub(crate) fn xor(data: &[u8], key: &[u8]) -> Vec<u8> {
     data.iter().zip(key.iter()).map(|(a, b)| a ^ b).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_otp_key_generation() {
         let key = generate_key(32);
@@ -70,6 +233,52 @@ mod tests {
         assert_eq!(key.len(), 32);
     }
 
+    #[test]
+    fn test_otp_key_generation_with_rng() {
+        let key = generate_key_with(&mut StdRng::seed_from_u64(42), 16);
+        assert_eq!(key.len(), 16);
+        assert_eq!(key, generate_key_with(&mut StdRng::seed_from_u64(42), 16));
+        assert_ne!(key, generate_key_with(&mut StdRng::seed_from_u64(43), 16));
+    }
+
+    #[test]
+    fn test_otp_encipher_length_mismatch() {
+        assert_eq!(
+            encipher(b"Hello", &[1, 2, 3]),
+            Err(OtpError::LengthMismatch(5, 3))
+        );
+        assert_eq!(encipher(b"AB", &[1, 2]), Ok(vec![b'A' ^ 1, b'B' ^ 2]));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_otp_pad_ref() {
+        let path = std::env::temp_dir().join(format!("cyphers-pad-{}.bin", std::process::id()));
+        std::fs::write(&path, generate_key(64)).unwrap();
+
+        let pad = PadRef::new(&path, 16);
+        let ciphertext = pad.encrypt("Hello, World!").unwrap();
+        assert_eq!(ciphertext.len(), 26);
+        assert!(ciphertext.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(pad.decrypt(&ciphertext).unwrap(), "Hello, World!");
+        let next = pad.next(13);
+        assert_eq!(next, PadRef::new(&path, 29));
+        assert_ne!(next.encrypt("Hello, World!").unwrap(), ciphertext);
+        assert_ne!(
+            PadRef::new(&path, 0).decrypt(&ciphertext),
+            Ok("Hello, World!".to_string())
+        );
+
+        assert!(matches!(pad.decrypt("XYZ"), Err(CipherError::Pad(_))));
+        assert!(matches!(
+            PadRef::new(&path, 60).encrypt("Hello"),
+            Err(CipherError::Pad(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(pad.encrypt("Hello"), Err(CipherError::Pad(_))));
+    }
+
+    #[cfg(feature = "std")]
     #[test]
     fn test_otp_encryption_and_decryption() {
         let plaintext = "Hello";
@@ -93,11 +302,12 @@ mod tests {
         assert_eq!(decrypted_plaintext, plaintext);
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_otp_encipherment() {
         let plaintext = "Hello";
         let key = generate_key(plaintext.len());
-        let ciphertext = encipher(plaintext.as_bytes(), &key);
+        let ciphertext = encipher(plaintext.as_bytes(), &key).unwrap();
 
         println!(
             "Plaintext: {}, \nKey: {:?}, \nCiphertext: {:?}",
@@ -108,7 +318,7 @@ mod tests {
         assert_eq!(plaintext.len(), ciphertext.len());
         assert_eq!(key.len(), ciphertext.len());
 
-        let decrypted_plaintext = encipher(&ciphertext, &key);
+        let decrypted_plaintext = encipher(&ciphertext, &key).unwrap();
         let decrypted_plaintext = unsafe { String::from_utf8_unchecked(decrypted_plaintext) };
 
         println!("\nDecrypted Plaintext: {}", decrypted_plaintext);
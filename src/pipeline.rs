@@ -0,0 +1,364 @@
+//! Product ciphers: chains of ciphers applied one after the other.
+//!
+//! A [`Pipeline`] encrypts with each of its ciphers in turn, and decrypts with
+//! them in reverse order. The kind of text written by every cipher is checked
+//! against the kind read by the next one when the pipeline is built (see
+//! [`TextKind`]), so that, for example, the digits of a Polybius square are not
+//! silently passed through a Vigenère cipher. The [`DigitsToLetters`] adapter
+//! turns digits into letters when a letter cipher must follow.
+//!
+//! Pipelines can also be written as text, with `|` between the ciphers:
+//!
+//! ```
+//! use cyphers::pipeline::Pipeline;
+//! use cyphers::cipher::Cipher;
+//!
+//! let pipeline = Pipeline::parse("vigenere(LEMON) | caesar(3)").unwrap();
+//! assert_eq!(pipeline.encrypt("Attack at dawn").unwrap(), "OAIRSYHIUQKU");
+//! assert_eq!(pipeline.decrypt("OAIRSYHIUQKU").unwrap(), "ATTACKATDAWN");
+//! ```
+//!
+//! The ciphers available in the text syntax are `caesar(shift)`, `affine(a, b)`,
+//! `vigenere(key)`, `playfair(keyword)`, `polybius` or `polybius(keyword)`,
+//! `columnar(key)` and the `to_letters` adapter. Keys cannot contain `,`, `(`,
+//! `)` or `|`.
+
+use crate::affine::Affine;
+use crate::caesar::Caesar;
+use crate::cipher::{Cipher, CipherError, CipherSpec, TextKind};
+use crate::columnar::Columnar;
+use crate::key_square::{KeySquare, SquareAlphabet};
+use crate::playfair::Playfair;
+use crate::polybius_square::PolybiusSquare;
+use crate::prelude::*;
+use crate::vigenere::Vigenere;
+use core::str::FromStr;
+use thiserror::Error;
+
+/// Error type for the construction of pipelines.
+#[derive(Error, Debug, PartialEq)]
+pub enum PipelineError {
+    #[error("Invalid pipeline: {0}")]
+    Syntax(String),
+    #[error("Unknown cipher: {0}")]
+    UnknownCipher(String),
+    #[error("Invalid arguments for {0}, expected {1}")]
+    Arguments(String, &'static str),
+    #[error("Cipher {stage} of the pipeline cannot read {input}")]
+    Mismatch { stage: usize, input: TextKind },
+    #[error(transparent)]
+    Cipher(#[from] CipherError),
+}
+
+/// An adapter writing digits as the letters `A` (0) to `J` (9).
+///
+/// Spaces are kept, so that the groups of digits remain visible.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DigitsToLetters;
+
+impl Cipher for DigitsToLetters {
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        plaintext
+            .chars()
+            .map(|c| match c {
+                '0'..='9' => Ok((c as u8 - b'0' + b'A') as char),
+                ' ' => Ok(c),
+                _ => Err(CipherError::InvalidText("expected digits")),
+            })
+            .collect()
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        ciphertext
+            .chars()
+            .map(|c| match c.to_ascii_uppercase() {
+                c @ 'A'..='J' => Ok((c as u8 - b'A' + b'0') as char),
+                ' ' => Ok(c),
+                _ => Err(CipherError::InvalidText("expected the letters A to J")),
+            })
+            .collect()
+    }
+
+    fn output_kind(&self, input: TextKind) -> Option<TextKind> {
+        (input == TextKind::Digits).then_some(TextKind::Letters)
+    }
+}
+
+/// A chain of ciphers, reading letters.
+pub struct Pipeline {
+    stages: Vec<Box<dyn Cipher>>,
+    output: TextKind,
+}
+
+impl Pipeline {
+    /// Creates an empty pipeline, which leaves the text unchanged.
+    pub fn new() -> Self {
+        Pipeline {
+            stages: Vec::new(),
+            output: TextKind::Letters,
+        }
+    }
+
+    /// Appends a cipher to the pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// * `cipher` - The cipher, which must be able to read the output of the pipeline.
+    ///
+    /// # Returns
+    ///
+    /// The pipeline, or an error if the cipher cannot read the output of the pipeline.
+    pub fn then(mut self, cipher: impl Cipher + 'static) -> Result<Self, PipelineError> {
+        self.push(Box::new(cipher))?;
+        Ok(self)
+    }
+
+    /// Appends a boxed cipher to the pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// * `cipher` - The cipher, which must be able to read the output of the pipeline.
+    ///
+    /// # Returns
+    ///
+    /// An error if the cipher cannot read the output of the pipeline, which is then unchanged.
+    pub fn push(&mut self, cipher: Box<dyn Cipher>) -> Result<(), PipelineError> {
+        self.output = cipher
+            .output_kind(self.output)
+            .ok_or(PipelineError::Mismatch {
+                stage: self.stages.len() + 1,
+                input: self.output,
+            })?;
+        self.stages.push(cipher);
+        Ok(())
+    }
+
+    /// Builds a pipeline from the specs of its ciphers.
+    ///
+    /// # Arguments
+    ///
+    /// * `specs` - The ciphers, in the order of encryption.
+    ///
+    /// # Returns
+    ///
+    /// The pipeline, or an error if a key is invalid or a cipher cannot read
+    /// the output of the previous one.
+    pub fn from_specs(specs: &[CipherSpec]) -> Result<Self, PipelineError> {
+        let mut pipeline = Pipeline::new();
+        for spec in specs {
+            pipeline.push(spec.build()?)?;
+        }
+        Ok(pipeline)
+    }
+
+    /// Builds a pipeline from its text form, e.g. `vigenere(KEY) | caesar(3)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The ciphers, in the order of encryption, separated by `|`.
+    ///
+    /// # Returns
+    ///
+    /// The pipeline, or an error if the text is invalid, a key is invalid or a
+    /// cipher cannot read the output of the previous one.
+    pub fn parse(spec: &str) -> Result<Self, PipelineError> {
+        Self::from_specs(&parse_specs(spec)?)
+    }
+
+    /// Returns the number of ciphers of the pipeline.
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Returns whether the pipeline has no ciphers.
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromStr for Pipeline {
+    type Err = PipelineError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        Self::parse(spec)
+    }
+}
+
+impl Cipher for Pipeline {
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        self.stages
+            .iter()
+            .try_fold(plaintext.to_string(), |text, cipher| cipher.encrypt(&text))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        self.stages
+            .iter()
+            .rev()
+            .try_fold(ciphertext.to_string(), |text, cipher| cipher.decrypt(&text))
+    }
+
+    fn output_kind(&self, input: TextKind) -> Option<TextKind> {
+        (input == TextKind::Letters).then_some(self.output)
+    }
+}
+
+/// Parses the text form of a pipeline into the specs of its ciphers.
+///
+/// # Arguments
+///
+/// * `spec` - The ciphers, in the order of encryption, separated by `|`, e.g.
+///   `polybius | columnar(ZEBRAS)`. Names are case-insensitive.
+///
+/// # Returns
+///
+/// The specs, or an error if the text is invalid. The keys are not checked.
+pub fn parse_specs(spec: &str) -> Result<Vec<CipherSpec>, PipelineError> {
+    spec.split('|')
+        .map(|stage| parse_stage(stage.trim()))
+        .collect()
+}
+
+/// Parses a cipher and its arguments, e.g. `affine(5, 8)`.
+fn parse_stage(stage: &str) -> Result<CipherSpec, PipelineError> {
+    let (name, args) = match stage.split_once('(') {
+        Some((name, rest)) => {
+            let args = rest
+                .strip_suffix(')')
+                .ok_or_else(|| PipelineError::Syntax(format!("missing `)` in `{}`", stage)))?;
+            (name.trim(), args.split(',').map(str::trim).collect())
+        }
+        None => (stage, Vec::new()),
+    };
+    if name.is_empty() {
+        return Err(PipelineError::Syntax(format!(
+            "missing cipher name in `{}`",
+            stage
+        )));
+    }
+
+    let name = name.to_ascii_lowercase();
+    let arguments = |expected| PipelineError::Arguments(name.clone(), expected);
+    let number = |arg: &str| arg.parse::<i32>().map_err(|_| arguments("integers"));
+    Ok(match (name.as_str(), args.as_slice()) {
+        ("caesar", [shift]) => CipherSpec::Caesar(Caesar {
+            shift: number(shift)?,
+        }),
+        ("caesar", _) => return Err(arguments("a shift")),
+        ("affine", [a, b]) => CipherSpec::Affine(Affine {
+            a: number(a)?,
+            b: number(b)?,
+        }),
+        ("affine", _) => return Err(arguments("two integers a and b")),
+        ("vigenere", [key]) => CipherSpec::Vigenere(Vigenere {
+            key: key.to_string(),
+        }),
+        ("vigenere", _) => return Err(arguments("a key")),
+        ("playfair", [keyword]) => CipherSpec::Playfair(Playfair::new(keyword)),
+        ("playfair", _) => return Err(arguments("a keyword")),
+        ("polybius", []) => CipherSpec::Polybius(PolybiusSquare::default()),
+        ("polybius", [keyword]) => CipherSpec::Polybius(PolybiusSquare {
+            square: KeySquare::new(keyword, SquareAlphabet::MergeJ),
+        }),
+        ("polybius", _) => return Err(arguments("a keyword or nothing")),
+        ("columnar", [key]) => CipherSpec::Columnar(Columnar {
+            key: key.to_string(),
+        }),
+        ("columnar", _) => return Err(arguments("a key")),
+        ("to_letters", []) => CipherSpec::ToLetters,
+        ("to_letters", _) => return Err(arguments("no arguments")),
+        _ => return Err(PipelineError::UnknownCipher(name)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{caesar, columnar, polybius_square, vigenere};
+
+    #[test]
+    fn test_pipeline_encrypt_decrypt() {
+        let pipeline = Pipeline::parse("vigenere(KEY) | caesar(3)").unwrap();
+        let letters = vigenere::prepare_string("Attack at dawn");
+        assert_eq!(
+            pipeline.encrypt("Attack at dawn").unwrap(),
+            caesar::encrypt(&vigenere::encrypt(&letters, "KEY"), 3)
+        );
+
+        // Polybius, then a transposition of its digits.
+        let pipeline: Pipeline = "Polybius | COLUMNAR(ZEBRAS)".parse().unwrap();
+        let ciphertext = pipeline.encrypt("Attack at dawn").unwrap();
+        assert_eq!(
+            ciphertext,
+            columnar::encrypt(&polybius_square::encrypt("Attack at dawn"), "ZEBRAS").unwrap()
+        );
+        assert_eq!(pipeline.decrypt(&ciphertext).unwrap(), "ATTACKATDAWN");
+
+        let pipeline = Pipeline::new()
+            .then(Caesar { shift: 1 })
+            .unwrap()
+            .then(Pipeline::parse("caesar(2) | affine(5, 8)").unwrap())
+            .unwrap();
+        assert_eq!(pipeline.len(), 2);
+        let ciphertext = pipeline.encrypt("Hello, World!").unwrap();
+        assert_eq!(pipeline.decrypt(&ciphertext).unwrap(), "Hello, World!");
+        assert_eq!(Pipeline::new().encrypt("Hello").unwrap(), "Hello");
+    }
+
+    #[test]
+    fn test_pipeline_text_kinds() {
+        assert_eq!(
+            Pipeline::parse("polybius | vigenere(KEY)").err(),
+            Some(PipelineError::Mismatch {
+                stage: 2,
+                input: TextKind::Digits
+            })
+        );
+
+        let pipeline = Pipeline::parse("polybius | to_letters | vigenere(KEY)").unwrap();
+        let ciphertext = pipeline.encrypt("Hello World").unwrap();
+        assert!(ciphertext.chars().all(|c| c.is_ascii_uppercase()));
+        assert_eq!(pipeline.decrypt(&ciphertext).unwrap(), "HELLOWORLD");
+
+        assert_eq!(DigitsToLetters.encrypt("2315 09").unwrap(), "CDBF AJ");
+        assert_eq!(DigitsToLetters.decrypt("CDBF AJ").unwrap(), "2315 09");
+        assert!(DigitsToLetters.decrypt("XYZ").is_err());
+    }
+
+    #[test]
+    fn test_pipeline_parse_errors() {
+        assert_eq!(
+            Pipeline::parse("enigma").err(),
+            Some(PipelineError::UnknownCipher("enigma".to_string()))
+        );
+        assert_eq!(
+            Pipeline::parse("caesar(x)").err(),
+            Some(PipelineError::Arguments("caesar".to_string(), "integers"))
+        );
+        assert_eq!(
+            Pipeline::parse("affine(5)").err(),
+            Some(PipelineError::Arguments(
+                "affine".to_string(),
+                "two integers a and b"
+            ))
+        );
+        assert!(matches!(
+            Pipeline::parse("vigenere(KEY"),
+            Err(PipelineError::Syntax(_))
+        ));
+        assert!(matches!(
+            Pipeline::parse("caesar(3) |"),
+            Err(PipelineError::Syntax(_))
+        ));
+        assert!(matches!(
+            Pipeline::parse("affine(2, 3)"),
+            Err(PipelineError::Cipher(CipherError::Affine(_)))
+        ));
+    }
+}
@@ -1,17 +1,59 @@
 //! An implementation of the Playfair cipher.
 
-use std::collections::HashSet;
-use thiserror::Error;
+use crate::cipher::{Cipher, CipherError};
+use crate::digraph::{
+    collect_output, create_digraphs, prepare_text, DigraphError, DigraphRules, Substitution,
+};
+use crate::formatting::Formatting;
+use crate::key_square::{KeySquare, SIZE};
+use crate::prelude::*;
+use crate::trace::Step;
 
 /// Error type for the Playfair cipher functions.
-#[derive(Error, Debug, PartialEq)]
-pub enum PlayfairError {
-    #[error("Keyword and text cannot be empty")]
-    EmptyInput,
-    #[error("No valid characters in the input text")]
-    InvalidText,
-    #[error("Character not found in matrix")]
-    CharNotFound,
+pub type PlayfairError = DigraphError;
+
+/// The key square of a Playfair cipher, for use through the [`Cipher`] trait.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Playfair {
+    /// The key square. Its alphabet decides how `J` or `Q` are handled.
+    pub square: KeySquare,
+}
+
+impl Playfair {
+    /// Creates a Playfair key from a keyword, with the square of [`create_matrix`].
+    pub fn new(keyword: &str) -> Self {
+        Playfair {
+            square: create_matrix(keyword),
+        }
+    }
+
+    /// Returns the digraph rules matching the alphabet of the square.
+    fn rules(&self) -> DigraphRules {
+        DigraphRules {
+            alphabet: self.square.alphabet(),
+            ..DigraphRules::PLAYFAIR
+        }
+    }
+}
+
+impl Cipher for Playfair {
+    // The letters of a square, used as a keyword, give the same square back.
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        Ok(encrypt_with_rules(
+            &self.square.letters(),
+            plaintext,
+            &self.rules(),
+        )?)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        Ok(decrypt_with_rules(
+            &self.square.letters(),
+            ciphertext,
+            &self.rules(),
+        )?)
+    }
 }
 
 /// Modes of operation for the Playfair cipher.
@@ -32,7 +74,7 @@ enum Mode {
 ///
 /// The encrypted version of the input string.
 pub fn encrypt(keyword: &str, text: &str) -> Result<String, PlayfairError> {
-    playfair_cipher(Mode::Encrypt, keyword, text)
+    encrypt_with_rules(keyword, text, &DigraphRules::PLAYFAIR)
 }
 
 /// Decrypts a given ciphertext string using the Playfair cipher.
@@ -46,119 +88,168 @@ pub fn encrypt(keyword: &str, text: &str) -> Result<String, PlayfairError> {
 ///
 /// The decrypted version of the input string.
 pub fn decrypt(keyword: &str, text: &str) -> Result<String, PlayfairError> {
-    playfair_cipher(Mode::Decrypt, keyword, text)
+    decrypt_with_rules(keyword, text, &DigraphRules::PLAYFAIR)
 }
 
-/// Encrypts or decrypts a given string based on the specified mode using the Playfair cipher.
-///
-/// This is a helper function that performs the actual transformation of characters.
+/// Encrypts a given plaintext string using the Playfair cipher and custom digraph rules.
 ///
 /// # Arguments
 ///
-/// * `mode` - The operation mode (`Encrypt` or `Decrypt`).
-/// * `keyword` - The key to create the matrix.
-/// * `text` - The input string to be processed.
+/// * `keyword` - The key to be used for creating the matrix.
+/// * `text` - The input string to be encrypted.
+/// * `rules` - The rules used to prepare the text and split it into digraphs.
 ///
 /// # Returns
 ///
-/// The processed version of the input string based on the mode.
-fn playfair_cipher(mode: Mode, keyword: &str, text: &str) -> Result<String, PlayfairError> {
-    if keyword.is_empty() || text.is_empty() {
-        return Err(PlayfairError::EmptyInput);
-    }
-
-    let matrix = create_matrix(keyword);
-    let prepared_text = prepare_text(text);
-    let digraphs = create_digraphs(&prepared_text)?;
-
-    let result_text: Result<String, _> = digraphs
-        .iter()
-        .map(|digraph| process_digraph(&matrix, digraph, &mode))
-        .collect();
-
-    result_text
+/// The encrypted version of the input string.
+pub fn encrypt_with_rules(
+    keyword: &str,
+    text: &str,
+    rules: &DigraphRules,
+) -> Result<String, PlayfairError> {
+    playfair_cipher(Mode::Encrypt, keyword, text, rules, false).map(|(text, _)| text)
 }
 
-/// Creates a 5x5 matrix for the Playfair cipher using the given keyword.
+/// Decrypts a given ciphertext string using the Playfair cipher and custom digraph rules.
 ///
 /// # Arguments
 ///
-/// * `keyword` - The keyword used to create the matrix.
+/// * `keyword` - The key to be used for creating the matrix.
+/// * `text` - The encrypted input string to be decrypted.
+/// * `rules` - The rules used to prepare the text and split it into digraphs.
 ///
 /// # Returns
 ///
-/// A 5x5 matrix filled with characters.
-fn create_matrix(keyword: &str) -> [[char; 5]; 5] {
-    let mut matrix = [[' '; 5]; 5];
-    let mut alphabet: HashSet<char> = "ABCDEFGHIKLMNOPQRSTUVWXYZ".chars().collect();
-    let mut key_chars: Vec<char> = keyword
-        .to_uppercase()
-        .chars()
-        .filter(|c| alphabet.contains(c))
-        .collect();
-    key_chars.dedup();
-
-    for (idx, &char) in key_chars.iter().enumerate() {
-        alphabet.remove(&char);
-        matrix[idx / 5][idx % 5] = char;
-    }
+/// The decrypted version of the input string.
+pub fn decrypt_with_rules(
+    keyword: &str,
+    text: &str,
+    rules: &DigraphRules,
+) -> Result<String, PlayfairError> {
+    playfair_cipher(Mode::Decrypt, keyword, text, rules, false).map(|(text, _)| text)
+}
 
-    for (idx, &char) in alphabet.iter().enumerate() {
-        matrix[(key_chars.len() + idx) / 5][(key_chars.len() + idx) % 5] = char;
-    }
+/// Encrypts a given plaintext string using the Playfair cipher, laying out the result as requested.
+///
+/// With [`Formatting::Preserve`], the fillers inserted between doubled letters
+/// shift the following letters, and the extra letters are appended at the end.
+///
+/// # Arguments
+///
+/// * `keyword` - The key to be used for creating the matrix.
+/// * `text` - The input string to be encrypted.
+/// * `formatting` - How case, spaces and punctuation appear in the result.
+///
+/// # Returns
+///
+/// The encrypted version of the input string.
+pub fn encrypt_with_formatting(
+    keyword: &str,
+    text: &str,
+    formatting: Formatting,
+) -> Result<String, PlayfairError> {
+    formatting.try_apply(text, |letters| encrypt(keyword, letters))
+}
 
-    matrix
+/// Decrypts a given ciphertext string using the Playfair cipher, laying out the result as requested.
+///
+/// # Arguments
+///
+/// * `keyword` - The key to be used for creating the matrix.
+/// * `text` - The encrypted input string to be decrypted.
+/// * `formatting` - How case, spaces and punctuation appear in the result.
+///
+/// # Returns
+///
+/// The decrypted version of the input string, including the fillers.
+pub fn decrypt_with_formatting(
+    keyword: &str,
+    text: &str,
+    formatting: Formatting,
+) -> Result<String, PlayfairError> {
+    formatting.try_apply(text, |letters| decrypt(keyword, letters))
 }
 
-/// Prepares the input text for the Playfair cipher.
+/// Encrypts a given plaintext string using the Playfair cipher, recording every step.
 ///
 /// # Arguments
 ///
-/// * `text` - The input text to be prepared.
+/// * `keyword` - The key to be used for creating the matrix.
+/// * `text` - The input string to be encrypted.
 ///
 /// # Returns
 ///
-/// A string with 'J' replaced by 'I' and non-alphabetic characters removed.
-fn prepare_text(text: &str) -> String {
-    text.to_uppercase()
-        .replace('J', "I")
-        .chars()
-        .filter(|c| c.is_alphabetic())
-        .collect()
+/// The encrypted version of the input string, and one step per digraph with
+/// the rule applied and the grid coordinates of the letters.
+pub fn encrypt_traced(keyword: &str, text: &str) -> Result<(String, Vec<Step>), PlayfairError> {
+    playfair_cipher(Mode::Encrypt, keyword, text, &DigraphRules::PLAYFAIR, true)
 }
 
-/// Creates digraphs from the input text.
+/// Decrypts a given ciphertext string using the Playfair cipher, recording every step.
 ///
 /// # Arguments
 ///
-/// * `text` - The input text to be converted into digraphs.
+/// * `keyword` - The key to be used for creating the matrix.
+/// * `text` - The encrypted input string to be decrypted.
 ///
 /// # Returns
 ///
-/// A vector of digraph tuples or an error if there are no valid characters in the input text.
-fn create_digraphs(text: &str) -> Result<Vec<(char, char)>, PlayfairError> {
-    let mut digraphs = Vec::new();
-    let chars: Vec<_> = text.chars().collect();
-    let mut i = 0;
-
-    while i < chars.len() {
-        let first = chars[i];
-        let second = if i + 1 < chars.len() && chars[i + 1] != first {
-            i += 1;
-            chars[i]
-        } else {
-            'X'
-        };
+/// The decrypted version of the input string, and one step per digraph with
+/// the rule applied and the grid coordinates of the letters.
+pub fn decrypt_traced(keyword: &str, text: &str) -> Result<(String, Vec<Step>), PlayfairError> {
+    playfair_cipher(Mode::Decrypt, keyword, text, &DigraphRules::PLAYFAIR, true)
+}
 
-        digraphs.push((first, second));
-        i += 1;
+/// Encrypts or decrypts a given string based on the specified mode using the Playfair cipher.
+///
+/// This is a helper function that performs the actual transformation of characters.
+///
+/// # Arguments
+///
+/// * `mode` - The operation mode (`Encrypt` or `Decrypt`).
+/// * `keyword` - The key to create the matrix.
+/// * `text` - The input string to be processed.
+/// * `rules` - The rules used to prepare the text and split it into digraphs.
+/// * `traced` - Whether to record the steps.
+///
+/// # Returns
+///
+/// The processed version of the input string based on the mode, and one step
+/// per digraph if `traced` is set.
+fn playfair_cipher(
+    mode: Mode,
+    keyword: &str,
+    text: &str,
+    rules: &DigraphRules,
+    traced: bool,
+) -> Result<(String, Vec<Step>), PlayfairError> {
+    if keyword.is_empty() || text.is_empty() {
+        return Err(PlayfairError::EmptyInput);
     }
 
-    if digraphs.is_empty() {
-        Err(PlayfairError::InvalidText)
-    } else {
-        Ok(digraphs)
-    }
+    let matrix = KeySquare::new(keyword, rules.alphabet);
+    let prepared_text = prepare_text(text, rules);
+    let digraphs = create_digraphs(&prepared_text, rules)?;
+
+    collect_output(
+        digraphs
+            .iter()
+            .map(|digraph| process_digraph(&matrix, digraph, &mode)),
+        traced,
+    )
+}
+
+/// Creates a 5x5 matrix for the Playfair cipher using the given keyword.
+///
+/// # Arguments
+///
+/// * `keyword` - The keyword used to create the matrix.
+///
+/// # Returns
+///
+/// A 5x5 key square filled with the keyword followed by the rest of the alphabet (with `J` merged into `I`).
+pub fn create_matrix(keyword: &str) -> KeySquare {
+    KeySquare::new(keyword, DigraphRules::PLAYFAIR.alphabet)
 }
 
 /// Finds the position of a character in the matrix.
@@ -171,16 +262,8 @@ fn create_digraphs(text: &str) -> Result<Vec<(char, char)>, PlayfairError> {
 /// # Returns
 ///
 /// The position of the character in the matrix or an error if the character is not found.
-fn find_position(matrix: &[[char; 5]; 5], ch: char) -> Result<(usize, usize), PlayfairError> {
-    for (i, row) in matrix.iter().enumerate() {
-        for (j, &char) in row.iter().enumerate() {
-            if char == ch {
-                return Ok((i, j));
-            }
-        }
-    }
-
-    Err(PlayfairError::CharNotFound)
+fn find_position(matrix: &KeySquare, ch: char) -> Result<(usize, usize), PlayfairError> {
+    matrix.position(ch).ok_or(PlayfairError::CharNotFound)
 }
 
 /// Processes a single digraph according to the Playfair cipher rules.
@@ -193,53 +276,130 @@ fn find_position(matrix: &[[char; 5]; 5], ch: char) -> Result<(usize, usize), Pl
 ///
 /// # Returns
 ///
-/// A string representing the processed digraph or an error if any character is not found in the matrix.
+/// The substitution of the digraph, naming the rule applied (`row`, `column`
+/// or `rectangle`), or an error if any character is not found in the matrix.
 fn process_digraph(
-    matrix: &[[char; 5]; 5],
+    matrix: &KeySquare,
     digraph: &(char, char),
     mode: &Mode,
-) -> Result<String, PlayfairError> {
+) -> Result<Substitution, PlayfairError> {
     let (x1, y1) = find_position(matrix, digraph.0)?;
     let (x2, y2) = find_position(matrix, digraph.1)?;
+    let step = match mode {
+        Mode::Encrypt => 1,
+        Mode::Decrypt => SIZE - 1,
+    };
 
-    if x1 == x2 {
-        let new_y1 = match mode {
-            Mode::Encrypt => (y1 + 1) % 5,
-            Mode::Decrypt => (y1 + 4) % 5,
-        };
-        let new_y2 = match mode {
-            Mode::Encrypt => (y2 + 1) % 5,
-            Mode::Decrypt => (y2 + 4) % 5,
-        };
-        Ok(format!("{}{}", matrix[x1][new_y1], matrix[x2][new_y2]))
+    let (rule, to) = if x1 == x2 {
+        ("row", [(x1, (y1 + step) % SIZE), (x2, (y2 + step) % SIZE)])
     } else if y1 == y2 {
-        let new_x1 = match mode {
-            Mode::Encrypt => (x1 + 1) % 5,
-            Mode::Decrypt => (x1 + 4) % 5,
-        };
-        let new_x2 = match mode {
-            Mode::Encrypt => (x2 + 1) % 5,
-            Mode::Decrypt => (x2 + 4) % 5,
-        };
-        Ok(format!("{}{}", matrix[new_x1][y1], matrix[new_x2][y2]))
+        (
+            "column",
+            [((x1 + step) % SIZE, y1), ((x2 + step) % SIZE, y2)],
+        )
     } else {
-        Ok(format!("{}{}", matrix[x1][y2], matrix[x2][y1]))
-    }
+        ("rectangle", [(x1, y2), (x2, y1)])
+    };
+
+    Ok(Substitution {
+        rule,
+        input: *digraph,
+        output: (
+            matrix.char_at(to[0].0, to[0].1),
+            matrix.char_at(to[1].0, to[1].1),
+        ),
+        from: [(x1, y1), (x2, y2)],
+        to,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    // #[test]
-    // fn test_playfair_encryption() {
-    //     todo!()
-    // }
+    #[test]
+    fn test_playfair_encryption() {
+        assert_eq!(
+            encrypt("playfair example", "Hide the gold in the tree stump"),
+            Ok("BMODZBXDNABEKUDMUIXMMOUVIF".to_string())
+        );
+    }
+
+    #[test]
+    fn test_playfair_decryption() {
+        assert_eq!(
+            decrypt("playfair example", "BMODZBXDNABEKUDMUIXMMOUVIF"),
+            Ok("HIDETHEGOLDINTHETREXESTUMP".to_string())
+        );
+    }
+
+    #[test]
+    fn test_playfair_encryption_is_deterministic() {
+        let ciphertext = encrypt("keyword", "HELLOPLAYFAIRCIPHER").unwrap();
+        assert_eq!(
+            decrypt("keyword", &ciphertext).unwrap(),
+            "HELXLOPLAYFAIRCIPHER"
+        );
+    }
+
+    #[test]
+    fn test_playfair_formatting() {
+        let ciphertext =
+            encrypt_with_formatting("keyword", "Hello, Playfair cipher!", Formatting::Group(5))
+                .unwrap();
+        let expected = encrypt("keyword", "HELLOPLAYFAIRCIPHER").unwrap();
+        assert_eq!(ciphertext.replace(' ', ""), expected);
+        assert_eq!(ciphertext.split(' ').next().unwrap().len(), 5);
+
+        let ciphertext = encrypt("keyword", "Meet at noon").unwrap().to_lowercase();
+        let ciphertext = format!("{} {}", &ciphertext[..4], &ciphertext[4..]);
+        assert_eq!(
+            decrypt_with_formatting("keyword", &ciphertext, Formatting::Preserve).unwrap(),
+            "meet atnoon"
+        );
+    }
+
+    #[test]
+    fn test_playfair_traced() {
+        let (ciphertext, steps) = encrypt_traced("playfair example", "Hide the gold").unwrap();
+        assert_eq!(
+            ciphertext,
+            encrypt("playfair example", "Hide the gold").unwrap()
+        );
+        assert_eq!(
+            steps[0],
+            Step::new("HI", "BM", "rectangle")
+                .detail("from", "(3,5) (2,1)")
+                .detail("to", "(3,1) (2,5)")
+        );
+        assert_eq!(steps[1].rule, "column");
+        let (_, steps) = encrypt_traced("playfair example", "play").unwrap();
+        assert_eq!(steps[0].rule, "row");
+
+        let (plaintext, steps) = decrypt_traced("playfair example", &ciphertext).unwrap();
+        assert_eq!(plaintext, "HIDETHEGOLDX");
+        assert_eq!(steps[0].output, "HI");
+    }
 
-    // #[test]
-    // fn test_playfair_decryption() {
-    //     todo!()
-    // }
+    #[test]
+    fn test_playfair_custom_rules() {
+        let rules = DigraphRules {
+            filler: 'Q',
+            ..DigraphRules::PLAYFAIR
+        };
+        let ciphertext = encrypt_with_rules("keyword", "balloon", &rules).unwrap();
+        assert_eq!(
+            decrypt_with_rules("keyword", &ciphertext, &rules).unwrap(),
+            "BALQLOON"
+        );
+    }
+
+    #[test]
+    fn test_playfair_create_matrix() {
+        let matrix = create_matrix("playfair example");
+        assert_eq!(matrix.rows()[0], ['P', 'L', 'A', 'Y', 'F']);
+        assert_eq!(find_position(&matrix, 'J'), Ok((1, 0)));
+    }
 
     #[test]
     fn test_playfair_empty_keyword() {
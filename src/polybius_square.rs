@@ -1,5 +1,65 @@
 //! An implementation of the Polybius Square cipher.
 
+use crate::cipher::{Cipher, CipherError, TextKind};
+use crate::key_square::KeySquare;
+use crate::prelude::*;
+use crate::trace::Step;
+
+/// A keyed Polybius square, for use through the [`Cipher`] trait.
+///
+/// Through the trait, every letter is written as its row and column in the
+/// square, from 1 to 5, and spaces are kept. The default square is the one
+/// used by [`encrypt`], with `J` merged into `I`. Decryption gives uppercase letters.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolybiusSquare {
+    /// The key square.
+    pub square: KeySquare,
+}
+
+impl Cipher for PolybiusSquare {
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        Ok(plaintext
+            .chars()
+            .filter_map(|c| match c {
+                ' ' => Some(" ".to_string()),
+                c => self
+                    .square
+                    .position(c)
+                    .map(|(row, col)| format!("{}{}", row + 1, col + 1)),
+            })
+            .collect())
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        ciphertext
+            .split(' ')
+            .map(|word| {
+                let digits = word
+                    .chars()
+                    .map(|c| c.to_digit(10).filter(|d| (1..=5).contains(d)))
+                    .collect::<Option<Vec<u32>>>()
+                    .filter(|digits| digits.len() % 2 == 0)
+                    .ok_or(CipherError::InvalidText(
+                        "expected pairs of digits from 1 to 5",
+                    ))?;
+                Ok(digits
+                    .chunks(2)
+                    .map(|pair| {
+                        self.square
+                            .char_at(pair[0] as usize - 1, pair[1] as usize - 1)
+                    })
+                    .collect::<String>())
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|words| words.join(" "))
+    }
+
+    fn output_kind(&self, input: TextKind) -> Option<TextKind> {
+        (input == TextKind::Letters).then_some(TextKind::Digits)
+    }
+}
+
 /// The uppercase-alphabetic grid used for encryption and decryption.
 const GRID_UPPER: [[char; 5]; 5] = [
     ['A', 'B', 'C', 'D', 'E'],
@@ -43,6 +103,31 @@ pub fn encrypt(plaintext: &str) -> String {
         .collect()
 }
 
+/// Encrypts a plaintext using the Polybius Square cipher, recording every step.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+///
+/// # Returns
+///
+/// The encrypted text, as [`encrypt`], and one step per letter or space of the
+/// plaintext with the row and column of the letter in the square, from 1.
+pub fn encrypt_traced(plaintext: &str) -> (String, Vec<Step>) {
+    let steps: Vec<Step> = clean_input(plaintext)
+        .chars()
+        .map(|ch| match find_coordinates(ch) {
+            _ if ch == ' ' => Step::new(ch, ch, "keep"),
+            Some((row, col, _)) => Step::new(ch, format!("{}{}", row + 1, col + 1), "coordinates")
+                .detail("row", row + 1)
+                .detail("column", col + 1),
+            None => Step::new(ch, "", "drop"),
+        })
+        .collect();
+    let ciphertext = steps.iter().map(|step| step.output.as_str()).collect();
+    (ciphertext, steps)
+}
+
 /// Decrypts a ciphertext using the Polybius Square cipher.
 ///
 /// # Arguments
@@ -153,8 +238,8 @@ fn decode_buffer(buffer: &str) -> Option<char> {
 /// # Returns
 ///
 /// * An option containing a tuple with the coordinates (row, col) and a character
-/// indicating the grid ('U' for uppercase and 'L' for lowercase)
-/// where the character was found, or `None` otherwise.
+///   indicating the grid ('U' for uppercase and 'L' for lowercase)
+///   where the character was found, or `None` otherwise.
 fn find_coordinates(ch: char) -> Option<(usize, usize, char)> {
     for (i, row) in GRID_UPPER.iter().enumerate() {
         for (j, &cell) in row.iter().enumerate() {
@@ -212,6 +297,21 @@ mod tests {
         assert_eq!(encrypt("RUST"), "42454344");
     }
 
+    #[test]
+    fn test_polybius_square_encrypt_traced() {
+        let (ciphertext, steps) = encrypt_traced("Hi jo!");
+        assert_eq!(ciphertext, encrypt("Hi jo!"));
+        assert_eq!(
+            steps[0],
+            Step::new('H', "23", "coordinates")
+                .detail("row", 2)
+                .detail("column", 3)
+        );
+        assert_eq!(steps[2], Step::new(' ', ' ', "keep"));
+        assert_eq!(steps[3].input, "i");
+        assert_eq!(steps.len(), 5);
+    }
+
     // #[test]
     // fn test_polybius_square_decrypt() {
     //     assert_eq!(
@@ -226,6 +326,26 @@ mod tests {
     //     assert_eq!(decrypt("424543441"), None);
     // }
 
+    #[test]
+    fn test_polybius_square_cipher() {
+        let polybius = PolybiusSquare::default();
+        assert_eq!(
+            Cipher::encrypt(&polybius, "Hello World").unwrap(),
+            encrypt("Hello World")
+        );
+        assert_eq!(
+            Cipher::decrypt(&polybius, "2315313134 5234423114").unwrap(),
+            "HELLO WORLD"
+        );
+        assert!(Cipher::decrypt(&polybius, "231").is_err());
+        assert!(Cipher::decrypt(&polybius, "2360").is_err());
+
+        let keyed = PolybiusSquare {
+            square: KeySquare::new("KEYWORD", Default::default()),
+        };
+        assert_eq!(Cipher::encrypt(&keyed, "key").unwrap(), "111213");
+    }
+
     #[test]
     fn test_polybius_square_clean_input() {
         assert_eq!(clean_input("Hello% World!"), "Hello World");
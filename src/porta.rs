@@ -1,116 +1,399 @@
 //! An implementation of the Porta cipher
+//!
+//! The Porta cipher, described by Giovanni Battista della Porta in 1563, is a
+//! periodic polyalphabetic cipher using 13 alphabets. Each pair of key letters
+//! (`A`/`B`, `C`/`D`, ..., `Y`/`Z`) selects one alphabet, which swaps every
+//! letter of the first half of the alphabet (`A` to `M`) with a letter of the
+//! second half (`N` to `Z`). Every alphabet is therefore reciprocal, and the
+//! same operation encrypts and decrypts.
+//!
+//! Like the Vigenère cipher, the Porta cipher is broken by finding the length
+//! of the key, then solving each column of letters enciphered with the same key
+//! letter as a simple substitution. The `solve` function, available with the
+//! `std` feature, does both.
 
-/// Represents the mode of operation: encryption or decryption.
-#[derive(Clone, Copy)]
-enum Mode {
-    Encrypt,
-    Decrypt,
+use crate::formatting::Formatting;
+#[cfg(feature = "std")]
+use crate::language::{index_of_coincidence, NgramModel, Scorer};
+use crate::prelude::*;
+use crate::trace::Step;
+use thiserror::Error;
+
+const HALF: u8 = 13;
+
+/// Error type for the Porta cipher.
+#[derive(Error, Debug, PartialEq)]
+pub enum PortaError {
+    #[error("Key must contain at least one letter")]
+    EmptyKey,
 }
 
 /// Encrypts the provided plaintext using the Porta cipher and the provided key.
 ///
 /// # Arguments
 ///
-/// * `plaintext` - The text to be encrypted.
-/// * `key` - The key to use for encryption.
+/// * `plaintext` - The text to be encrypted. Non-alphabetic characters are dropped.
+/// * `key` - The key to use for encryption. Non-alphabetic characters are ignored.
 ///
 /// # Returns
 ///
-/// A string representing the encrypted message (ciphertext).
-pub fn encrypt(plaintext: &str, key: &str) -> String {
-    encipher(plaintext, key, Mode::Encrypt)
+/// The encrypted text in uppercase, or an error if the key has no letters.
+pub fn encrypt(plaintext: &str, key: &str) -> Result<String, PortaError> {
+    encrypt_with_formatting(plaintext, key, Formatting::Strip)
 }
 
 /// Decrypts the provided ciphertext using the Porta cipher and the provided key.
 ///
+/// Since the cipher is reciprocal, this is the same operation as [`encrypt`].
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted. Non-alphabetic characters are dropped.
+/// * `key` - The key used for encryption. Non-alphabetic characters are ignored.
+///
+/// # Returns
+///
+/// The decrypted text in uppercase, or an error if the key has no letters.
+pub fn decrypt(ciphertext: &str, key: &str) -> Result<String, PortaError> {
+    encrypt_with_formatting(ciphertext, key, Formatting::Strip)
+}
+
+/// Encrypts the provided plaintext using the Porta cipher, laying out the result as requested.
+///
+/// Only the letters are encrypted, so that the key advances on letters only.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+/// * `key` - The key to use for encryption. Non-alphabetic characters are ignored.
+/// * `formatting` - How case, spaces and punctuation appear in the result.
+///
+/// # Returns
+///
+/// The encrypted text, or an error if the key has no letters.
+pub fn encrypt_with_formatting(
+    plaintext: &str,
+    key: &str,
+    formatting: Formatting,
+) -> Result<String, PortaError> {
+    let key = letters(key);
+    if key.is_empty() {
+        return Err(PortaError::EmptyKey);
+    }
+    Ok(formatting.apply(plaintext, |text| encipher(&letters(text), &key)))
+}
+
+/// Decrypts the provided ciphertext using the Porta cipher, laying out the result as requested.
+///
+/// Since the cipher is reciprocal, this is the same operation as [`encrypt_with_formatting`].
+///
 /// # Arguments
 ///
 /// * `ciphertext` - The text to be decrypted.
-/// * `key` - The key to use for decryption.
+/// * `key` - The key used for encryption. Non-alphabetic characters are ignored.
+/// * `formatting` - How case, spaces and punctuation appear in the result.
 ///
 /// # Returns
 ///
-/// A string representing the decrypted message (plaintext).
-pub fn decrypt(ciphertext: &str, key: &str) -> String {
-    encipher(ciphertext, key, Mode::Decrypt)
+/// The decrypted text, or an error if the key has no letters.
+pub fn decrypt_with_formatting(
+    ciphertext: &str,
+    key: &str,
+    formatting: Formatting,
+) -> Result<String, PortaError> {
+    encrypt_with_formatting(ciphertext, key, formatting)
 }
 
-/// Core logic for the encryption/decryption process.
-///
-/// This function is responsible for both encryption and decryption, depending on the mode provided.
-fn encipher(text: &str, key: &str, mode: Mode) -> String {
-    let repeated_key = prepare_key(text, key);
-    text.chars()
-        .enumerate()
-        .filter(|&(_, ch)| ch.is_ascii_alphabetic())
-        .map(|(idx, ch)| {
-            let text_idx = char_to_index(ch);
-            let key_idx = char_to_index(repeated_key.chars().nth(idx).unwrap());
-
-            match mode {
-                Mode::Encrypt => index_to_char((text_idx + key_idx) % 13, true),
-                Mode::Decrypt => {
-                    if text_idx < 13 {
-                        index_to_char((13 + text_idx - key_idx) % 13, false)
-                    } else {
-                        index_to_char((26 + text_idx - key_idx) % 13, true)
-                    }
-                }
-            }
+/// Encrypts or decrypts a text using the Porta cipher, recording every step.
+///
+/// # Arguments
+///
+/// * `text` - The text to be transformed. Non-alphabetic characters are dropped.
+/// * `key` - The key. Non-alphabetic characters are ignored.
+///
+/// # Returns
+///
+/// The transformed text in uppercase, and one step per letter with its key
+/// letter and the pair of key letters selecting the alphabet, or an error if
+/// the key has no letters.
+pub fn encrypt_traced(text: &str, key: &str) -> Result<(String, Vec<Step>), PortaError> {
+    let key = letters(key);
+    if key.is_empty() {
+        return Err(PortaError::EmptyKey);
+    }
+    Ok(letters(text)
+        .iter()
+        .zip(key.iter().cycle())
+        .map(|(&letter, &k)| {
+            let output = (substitute(letter, k) + b'A') as char;
+            let pair = k / 2 * 2 + b'A';
+            let step = Step::new((letter + b'A') as char, output, "swap")
+                .detail("key", (k + b'A') as char)
+                .detail(
+                    "alphabet",
+                    format!("{}{}", pair as char, (pair + 1) as char),
+                );
+            (output, step)
         })
-        .collect()
+        .unzip())
 }
 
-/// Prepares the key by repeating it to match the length of the text.
+/// Substitutes a letter using the Porta tableau.
 ///
-/// Returns a string of repeated keys.
-fn prepare_key(text: &str, key: &str) -> String {
-    key.to_uppercase()
-        .chars()
-        .cycle()
-        .take(text.len())
-        .collect()
+/// # Arguments
+///
+/// * `letter` - The index of the letter to substitute (`A` = 0).
+/// * `key` - The index of the key letter (`A` = 0). `A` and `B` select the
+///   same alphabet, as do `C` and `D`, and so on.
+///
+/// # Returns
+///
+/// The index of the substituted letter, in the other half of the alphabet.
+pub fn substitute(letter: u8, key: u8) -> u8 {
+    let pair = key % (2 * HALF) / 2;
+    let letter = letter % (2 * HALF);
+    if letter < HALF {
+        HALF + (letter + pair) % HALF
+    } else {
+        (letter - HALF + HALF - pair) % HALF
+    }
 }
 
-/// Converts a character to its corresponding index in the English alphabet.
-fn char_to_index(ch: char) -> usize {
-    (ch.to_ascii_uppercase() as usize) - ('A' as usize)
+/// The result of [`solve`].
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution {
+    /// The key, using the first letter of each pair (`A`, `C`, ..., `Y`).
+    pub key: String,
+    /// The decrypted text, in uppercase.
+    pub plaintext: String,
+    /// The quadgram score of the plaintext, see [`crate::language`].
+    pub score: f64,
 }
 
-/// Converts an index in the English alphabet to its corresponding character.
-fn index_to_char(index: usize, is_second_half: bool) -> char {
-    let adjusted_index = if is_second_half { index + 13 } else { index };
-    char::from_u32((adjusted_index + 'A' as usize) as u32).unwrap()
+/// The number of key lengths ranked first by [`periods`] that [`solve`] tries.
+#[cfg(feature = "std")]
+const CANDIDATE_PERIODS: usize = 3;
+
+/// Recovers the key of a Porta ciphertext, assuming an English plaintext.
+///
+/// The key lengths up to `max_period` are ranked with [`periods`], and only
+/// the three best ones are tried, together with their divisors since a
+/// multiple of the key length also gives English-like columns. For each
+/// length, the key letter of every column is the one giving the most
+/// English-like letter frequencies, and the decryption with the best quadgram
+/// score is kept. The shortest key is preferred when several lengths give the
+/// same score.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext. Non-alphabetic characters are dropped.
+/// * `max_period` - The longest key length to try.
+///
+/// # Returns
+///
+/// The best solution, or `None` if the ciphertext has no letters.
+#[cfg(feature = "std")]
+pub fn solve(ciphertext: &str, max_period: usize) -> Option<Solution> {
+    let ranked = periods(ciphertext, max_period.max(1));
+    let ciphertext: Vec<u8> = letters(ciphertext);
+    if ciphertext.is_empty() {
+        return None;
+    }
+    let monograms = NgramModel::english(1).expect("monograms are supported");
+    let quadgrams = NgramModel::english(4).expect("quadgrams are supported");
+
+    let mut candidates: Vec<usize> = ranked
+        .into_iter()
+        .filter(|&period| period <= ciphertext.len())
+        .take(CANDIDATE_PERIODS)
+        .flat_map(|period| (1..=period).filter(move |d| period.is_multiple_of(*d)))
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    let mut best: Option<Solution> = None;
+    for period in candidates {
+        let key: Vec<u8> = (0..period)
+            .map(|offset| {
+                let column: Vec<u8> = ciphertext
+                    .iter()
+                    .skip(offset)
+                    .step_by(period)
+                    .copied()
+                    .collect();
+                (0..HALF)
+                    .map(|pair| 2 * pair)
+                    .max_by(|&a, &b| {
+                        let score = |key| {
+                            let decrypted: Vec<u8> =
+                                column.iter().map(|&c| substitute(c, key)).collect();
+                            monograms.score_letters(&decrypted)
+                        };
+                        score(a).total_cmp(&score(b))
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let plaintext: Vec<u8> = ciphertext
+            .iter()
+            .zip(key.iter().cycle())
+            .map(|(&c, &k)| substitute(c, k))
+            .collect();
+        let score = quadgrams.score_letters(&plaintext);
+        // Candidates are tried from the shortest, so a tie keeps the shortest key.
+        if best.as_ref().is_none_or(|b| score > b.score) {
+            best = Some(Solution {
+                key: key.iter().map(|&k| (k + b'A') as char).collect(),
+                plaintext: plaintext.iter().map(|&p| (p + b'A') as char).collect(),
+                score,
+            });
+        }
+    }
+    best
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-
-//     #[test]
-//     fn test_porta_encrypt() {
-//         let plaintext = "HELLO";
-//         let key = "KEY";
-//         let encrypted = encrypt(plaintext, key);
-//         assert_eq!(encrypted, "RYXXO");
-//     }
-
-//     #[test]
-//     fn test_porta_decrypt() {
-//         let ciphertext = "RYXXO";
-//         let key = "KEY";
-//         let decrypted = decrypt(ciphertext, key);
-//         assert_eq!(decrypted, "HELLO");
-//     }
-
-//     #[test]
-//     fn test_porta_non_alphabetic_chars() {
-//         let plaintext = "HELLO123!";
-//         let key = "KEY";
-//         let encrypted = encrypt(plaintext, key);
-//         assert_eq!(encrypted, "RYXXO");
-
-//         let decrypted = decrypt("RYXXO123!", key);
-//         assert_eq!(decrypted, "HELLO");
-//     }
-// }
+/// Estimates the key length of a Porta ciphertext with the index of coincidence.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The ciphertext. Non-alphabetic characters are dropped.
+/// * `max_period` - The longest key length to try.
+///
+/// # Returns
+///
+/// The key lengths from 1 to `max_period`, sorted by decreasing average index
+/// of coincidence of their columns.
+#[cfg(feature = "std")]
+pub fn periods(ciphertext: &str, max_period: usize) -> Vec<usize> {
+    let ciphertext = letters(ciphertext);
+    let average = |period: usize| {
+        (0..period)
+            .map(|offset| {
+                let column: Vec<u8> = ciphertext
+                    .iter()
+                    .skip(offset)
+                    .step_by(period)
+                    .copied()
+                    .collect();
+                index_of_coincidence(&column)
+            })
+            .sum::<f64>()
+            / period as f64
+    };
+    let mut periods: Vec<(usize, f64)> = (1..=max_period)
+        .map(|period| (period, average(period)))
+        .collect();
+    periods.sort_by(|a, b| b.1.total_cmp(&a.1));
+    periods.into_iter().map(|(period, _)| period).collect()
+}
+
+/// Core logic for the encryption/decryption process, on letter indices.
+fn encipher(text: &[u8], key: &[u8]) -> String {
+    text.iter()
+        .zip(key.iter().cycle())
+        .map(|(&letter, &k)| (substitute(letter, k) + b'A') as char)
+        .collect()
+}
+
+/// Returns the letter indices (`A` = 0) of the letters of a text.
+fn letters(text: &str) -> Vec<u8> {
+    text.bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|b| b.to_ascii_uppercase() - b'A')
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_porta_encrypt() {
+        assert_eq!(
+            encrypt("defend the east wall of the castle", "FORTIFICATION").unwrap(),
+            "SYNNJSCVRNRLAHUTUKUCVRYRLANY"
+        );
+        assert_eq!(encrypt("HELLO", "KEY").unwrap(), "ZTXQM");
+    }
+
+    #[test]
+    fn test_porta_decrypt() {
+        assert_eq!(
+            decrypt("SYNNJSCVRNRLAHUTUKUCVRYRLANY", "FORTIFICATION").unwrap(),
+            "DEFENDTHEEASTWALLOFTHECASTLE"
+        );
+        assert_eq!(decrypt("ZTXQM", "KEY").unwrap(), "HELLO");
+        assert_eq!(encrypt("HELLO", ""), Err(PortaError::EmptyKey));
+    }
+
+    #[test]
+    fn test_porta_reciprocal() {
+        // Both letters of a pair select the same alphabet, and every alphabet swaps the two halves.
+        for key in 0..26 {
+            assert_eq!(substitute(0, key), substitute(0, key ^ 1));
+            for letter in 0..26 {
+                let c = substitute(letter, key);
+                assert_eq!(c < HALF, letter >= HALF);
+                assert_eq!(substitute(c, key), letter);
+            }
+        }
+    }
+
+    #[test]
+    fn test_porta_traced() {
+        let (ciphertext, steps) = encrypt_traced("Hello!", "KEY").unwrap();
+        assert_eq!(ciphertext, "ZTXQM");
+        assert_eq!(
+            steps[0],
+            Step::new('H', 'Z', "swap")
+                .detail("key", 'K')
+                .detail("alphabet", "KL")
+        );
+        assert_eq!(steps.len(), 5);
+        assert_eq!(encrypt_traced("HELLO", "1"), Err(PortaError::EmptyKey));
+    }
+
+    #[test]
+    fn test_porta_non_alphabetic_chars() {
+        assert_eq!(encrypt("HELLO123!", "KEY").unwrap(), "ZTXQM");
+        let ciphertext =
+            encrypt_with_formatting("Hello, world 123!", "KEY", Formatting::Preserve).unwrap();
+        assert_eq!(&ciphertext[..5], "Ztxqm");
+        assert!(ciphertext.ends_with(" 123!"));
+        assert_eq!(
+            decrypt_with_formatting(&ciphertext, "KEY", Formatting::Preserve).unwrap(),
+            "Hello, world 123!"
+        );
+        assert_eq!(
+            encrypt_with_formatting("Hello, world", "KEY", Formatting::Group(5)).unwrap(),
+            format!("ZTXQM {}", &encrypt("HELLOWORLD", "KEY").unwrap()[5..])
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_porta_solve() {
+        let plaintext = "The old observatory stood on a hill above the town, and on clear \
+            nights the astronomer opened the great dome and pointed the telescope at the sky. \
+            She kept careful records of every star she measured, writing the positions in a \
+            thick book with a green cover. Over many years the book filled with numbers.";
+        let ciphertext = encrypt(plaintext, "SECRET").unwrap();
+        assert_eq!(periods(&ciphertext, 12)[0] % 6, 0);
+
+        let solution = solve(&ciphertext, 12).unwrap();
+        assert_eq!(solution.key, "SECQES");
+        assert_eq!(
+            solution.plaintext,
+            letters(plaintext)
+                .iter()
+                .map(|&l| (l + b'A') as char)
+                .collect::<String>()
+        );
+        // Only the best ranked lengths are tried, so a long search finds the same key.
+        assert_eq!(solve(&ciphertext, 40).unwrap().key, "SECQES");
+        assert_eq!(solve(&ciphertext, 0).unwrap().key.len(), 1);
+        assert_eq!(solve("123", 5), None);
+    }
+}
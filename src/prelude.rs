@@ -0,0 +1,6 @@
+//! The allocating types of the standard prelude, for `no_std` builds.
+
+pub(crate) use alloc::boxed::Box;
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec::Vec;
+pub(crate) use alloc::{format, vec};
@@ -0,0 +1,493 @@
+//! An implementation of the Quagmire I, II, III and IV periodic ciphers.
+//!
+//! The Quagmires are Vigenère-like periodic ciphers defined by the American
+//! Cryptogram Association (ACA). Instead of the straight alphabet, they use
+//! keyed alphabets on the plaintext side, the ciphertext side, or both:
+//!
+//! * Quagmire I: keyed plaintext alphabet, straight ciphertext alphabet.
+//! * Quagmire II: straight plaintext alphabet, keyed ciphertext alphabet.
+//! * Quagmire III: the same keyed alphabet on both sides.
+//! * Quagmire IV: two different keyed alphabets.
+//!
+//! The ciphertext alphabet is slid against the plaintext alphabet once for
+//! every letter of an indicator key, so that the indicator letter appears
+//! under a reference letter of the plaintext alphabet. As in the examples of
+//! the ACA, the reference letter is `A` for Quagmire I and II, and the first
+//! letter of the keyed plaintext alphabet for Quagmire III and IV, which is
+//! how the Kryptos sculpture is enciphered.
+
+use crate::keyed_alphabet::KeyedAlphabet;
+use crate::prelude::*;
+use crate::trace::Step;
+use thiserror::Error;
+
+const ALPHABET_SIZE: usize = 26;
+
+/// Error type for the Quagmire ciphers.
+#[derive(Error, Debug, PartialEq)]
+pub enum QuagmireError {
+    #[error("Indicator key cannot be empty")]
+    EmptyIndicator,
+    #[error("Indicator key must only contain letters")]
+    InvalidIndicator,
+    #[error("Reference letter must be a letter")]
+    InvalidReference,
+}
+
+/// The four Quagmire variants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Variant {
+    I,
+    II,
+    III,
+    IV,
+}
+
+/// Represents the mode of operation: encryption or decryption.
+#[derive(Clone, Copy)]
+enum Mode {
+    Encrypt,
+    Decrypt,
+}
+
+/// A fully keyed Quagmire cipher.
+///
+/// With the `serde` feature, the cipher is serialized as its variant, both
+/// alphabets, the indicator key and the reference letter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "QuagmireRepr", into = "QuagmireRepr")
+)]
+pub struct Quagmire {
+    variant: Variant,
+    plain: KeyedAlphabet,
+    cipher: KeyedAlphabet,
+    indicator: Vec<char>,
+    reference: char,
+}
+
+impl Quagmire {
+    /// Creates a Quagmire I cipher (keyed plaintext alphabet, straight ciphertext alphabet).
+    ///
+    /// # Arguments
+    ///
+    /// * `keyword` - The keyword of the plaintext alphabet.
+    /// * `indicator` - The indicator key, selecting one ciphertext alphabet per letter.
+    pub fn one(keyword: &str, indicator: &str) -> Result<Self, QuagmireError> {
+        Self::new(
+            Variant::I,
+            KeyedAlphabet::new(keyword),
+            KeyedAlphabet::straight(),
+            indicator,
+        )
+    }
+
+    /// Creates a Quagmire II cipher (straight plaintext alphabet, keyed ciphertext alphabet).
+    ///
+    /// # Arguments
+    ///
+    /// * `keyword` - The keyword of the ciphertext alphabet.
+    /// * `indicator` - The indicator key, selecting one ciphertext alphabet per letter.
+    pub fn two(keyword: &str, indicator: &str) -> Result<Self, QuagmireError> {
+        Self::new(
+            Variant::II,
+            KeyedAlphabet::straight(),
+            KeyedAlphabet::new(keyword),
+            indicator,
+        )
+    }
+
+    /// Creates a Quagmire III cipher (the same keyed alphabet on both sides).
+    ///
+    /// # Arguments
+    ///
+    /// * `keyword` - The keyword of both alphabets.
+    /// * `indicator` - The indicator key, selecting one ciphertext alphabet per letter.
+    pub fn three(keyword: &str, indicator: &str) -> Result<Self, QuagmireError> {
+        let alphabet = KeyedAlphabet::new(keyword);
+        Self::new(Variant::III, alphabet, alphabet, indicator)
+    }
+
+    /// Creates a Quagmire IV cipher (two different keyed alphabets).
+    ///
+    /// # Arguments
+    ///
+    /// * `plain_keyword` - The keyword of the plaintext alphabet.
+    /// * `cipher_keyword` - The keyword of the ciphertext alphabet.
+    /// * `indicator` - The indicator key, selecting one ciphertext alphabet per letter.
+    pub fn four(
+        plain_keyword: &str,
+        cipher_keyword: &str,
+        indicator: &str,
+    ) -> Result<Self, QuagmireError> {
+        Self::new(
+            Variant::IV,
+            KeyedAlphabet::new(plain_keyword),
+            KeyedAlphabet::new(cipher_keyword),
+            indicator,
+        )
+    }
+
+    /// Creates a Quagmire cipher from its variant, alphabets and indicator key.
+    fn new(
+        variant: Variant,
+        plain: KeyedAlphabet,
+        cipher: KeyedAlphabet,
+        indicator: &str,
+    ) -> Result<Self, QuagmireError> {
+        if indicator.is_empty() {
+            return Err(QuagmireError::EmptyIndicator);
+        }
+        if !indicator.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(QuagmireError::InvalidIndicator);
+        }
+
+        let reference = match variant {
+            Variant::I | Variant::II => 'A',
+            Variant::III | Variant::IV => plain.char_at(0),
+        };
+        Ok(Quagmire {
+            variant,
+            plain,
+            cipher,
+            indicator: indicator.chars().map(|c| c.to_ascii_uppercase()).collect(),
+            reference,
+        })
+    }
+
+    /// Sets the plaintext letter under which the indicator key is written.
+    ///
+    /// By default, it is `A` for Quagmire I and II, and the first letter of the
+    /// keyed plaintext alphabet for Quagmire III and IV.
+    ///
+    /// # Arguments
+    ///
+    /// * `reference` - The reference letter of the plaintext alphabet.
+    pub fn with_reference(mut self, reference: char) -> Result<Self, QuagmireError> {
+        if !reference.is_ascii_alphabetic() {
+            return Err(QuagmireError::InvalidReference);
+        }
+        self.reference = reference.to_ascii_uppercase();
+        Ok(self)
+    }
+
+    /// Returns the variant of this Quagmire cipher.
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Returns the plaintext alphabet.
+    pub fn plain_alphabet(&self) -> &KeyedAlphabet {
+        &self.plain
+    }
+
+    /// Returns the ciphertext alphabet.
+    pub fn cipher_alphabet(&self) -> &KeyedAlphabet {
+        &self.cipher
+    }
+
+    /// Encrypts the provided plaintext.
+    ///
+    /// Non-alphabetic characters are left unchanged and do not advance the indicator key.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The text to be encrypted.
+    ///
+    /// # Returns
+    ///
+    /// The encrypted text, in uppercase.
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        self.encipher(plaintext, Mode::Encrypt)
+    }
+
+    /// Decrypts the provided ciphertext.
+    ///
+    /// Non-alphabetic characters are left unchanged and do not advance the indicator key.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The text to be decrypted.
+    ///
+    /// # Returns
+    ///
+    /// The decrypted text, in uppercase.
+    pub fn decrypt(&self, ciphertext: &str) -> String {
+        self.encipher(ciphertext, Mode::Decrypt)
+    }
+
+    /// Encrypts the provided plaintext, recording every step.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The text to be encrypted.
+    ///
+    /// # Returns
+    ///
+    /// The encrypted text, and one step per character with the indicator
+    /// letter and the offset of the ciphertext alphabet for the letters.
+    pub fn encrypt_traced(&self, plaintext: &str) -> (String, Vec<Step>) {
+        self.encipher_traced(plaintext, Mode::Encrypt)
+    }
+
+    /// Decrypts the provided ciphertext, recording every step.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The text to be decrypted.
+    ///
+    /// # Returns
+    ///
+    /// The decrypted text, and one step per character with the indicator
+    /// letter and the offset of the ciphertext alphabet for the letters.
+    pub fn decrypt_traced(&self, ciphertext: &str) -> (String, Vec<Step>) {
+        self.encipher_traced(ciphertext, Mode::Decrypt)
+    }
+
+    /// Core logic for the encryption/decryption process.
+    fn encipher(&self, text: &str, mode: Mode) -> String {
+        self.substitutions(text, mode)
+            .map(|(_, output, _)| output)
+            .collect()
+    }
+
+    /// Encrypts or decrypts a text, recording the alphabet used for every letter.
+    fn encipher_traced(&self, text: &str, mode: Mode) -> (String, Vec<Step>) {
+        self.substitutions(text, mode)
+            .map(|(input, output, key)| {
+                let step = match key {
+                    Some((indicator, offset)) => Step::new(input, output, "slide")
+                        .detail("indicator", indicator)
+                        .detail("offset", offset),
+                    None => Step::new(input, output, "keep"),
+                };
+                (output, step)
+            })
+            .unzip()
+    }
+
+    /// Substitutes every character of a text.
+    ///
+    /// # Returns
+    ///
+    /// For every character, the character, its substitute, and for the letters
+    /// the indicator letter and the offset of the ciphertext alphabet.
+    fn substitutions<'a>(
+        &'a self,
+        text: &'a str,
+        mode: Mode,
+    ) -> impl Iterator<Item = (char, char, Option<(char, usize)>)> + 'a {
+        // The position of the reference letter in the plaintext alphabet.
+        let reference = self.plain.index_of(self.reference).unwrap_or_default();
+        let mut letter_count = 0;
+
+        text.chars().map(move |c| {
+            if !c.is_ascii_alphabetic() {
+                return (c, c, None);
+            }
+
+            let indicator = self.indicator[letter_count % self.indicator.len()];
+            letter_count += 1;
+            // The offset of the ciphertext alphabet that puts the indicator under the reference.
+            let offset =
+                self.cipher.index_of(indicator).unwrap_or_default() + ALPHABET_SIZE - reference;
+
+            let output = match mode {
+                Mode::Encrypt => {
+                    let idx = self.plain.index_of(c).unwrap_or_default();
+                    self.cipher.char_at(idx + offset)
+                }
+                Mode::Decrypt => {
+                    let idx = self.cipher.index_of(c).unwrap_or_default();
+                    self.plain.char_at(idx + 2 * ALPHABET_SIZE - offset)
+                }
+            };
+            (c, output, Some((indicator, offset % ALPHABET_SIZE)))
+        })
+    }
+}
+
+/// The serialized form of a [`Quagmire`], checked when it is loaded.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QuagmireRepr {
+    variant: Variant,
+    plain: KeyedAlphabet,
+    cipher: KeyedAlphabet,
+    indicator: String,
+    reference: char,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<QuagmireRepr> for Quagmire {
+    type Error = QuagmireError;
+
+    fn try_from(repr: QuagmireRepr) -> Result<Self, Self::Error> {
+        Quagmire::new(repr.variant, repr.plain, repr.cipher, &repr.indicator)?
+            .with_reference(repr.reference)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Quagmire> for QuagmireRepr {
+    fn from(quagmire: Quagmire) -> Self {
+        QuagmireRepr {
+            variant: quagmire.variant,
+            plain: quagmire.plain,
+            cipher: quagmire.cipher,
+            indicator: quagmire.indicator.into_iter().collect(),
+            reference: quagmire.reference,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quagmire_three() {
+        let quagmire = Quagmire::three("AUTOMOBILE", "HIGHWAY").unwrap();
+        assert_eq!(quagmire.encrypt("ATTACK"), "HEJHBK");
+        assert_eq!(quagmire.decrypt("HEJHBK"), "ATTACK");
+    }
+
+    #[test]
+    fn test_quagmire_traced() {
+        let quagmire = Quagmire::three("AUTOMOBILE", "HIGHWAY").unwrap();
+        let (ciphertext, steps) = quagmire.encrypt_traced("AT TACK");
+        assert_eq!(ciphertext, "HE JHBK");
+        assert_eq!(
+            steps[0],
+            Step::new('A', 'H', "slide")
+                .detail("indicator", 'H')
+                .detail("offset", 13)
+        );
+        assert_eq!(steps[2], Step::new(' ', ' ', "keep"));
+        assert_eq!(
+            steps[3].details[0],
+            ("indicator".to_string(), "G".to_string())
+        );
+        assert_eq!(quagmire.decrypt_traced(&ciphertext).0, "AT TACK");
+    }
+
+    #[test]
+    fn test_quagmire_indicator_under_reference() {
+        // Whatever the keywords, the plaintext reference letter encrypts to the indicator letter.
+        for (quagmire, reference) in [
+            (Quagmire::one("SPRINGFEVER", "FLOWER").unwrap(), "AAAAAA"),
+            (Quagmire::two("SPRINGFEVER", "FLOWER").unwrap(), "AAAAAA"),
+            (Quagmire::three("SPRINGFEVER", "FLOWER").unwrap(), "SSSSSS"),
+            (
+                Quagmire::four("SENIORITY", "EXPERIENCE", "FLOWER").unwrap(),
+                "SSSSSS",
+            ),
+            (
+                Quagmire::four("SENIORITY", "EXPERIENCE", "FLOWER")
+                    .unwrap()
+                    .with_reference('a')
+                    .unwrap(),
+                "AAAAAA",
+            ),
+        ] {
+            assert_eq!(quagmire.encrypt(reference), "FLOWER");
+        }
+
+        let quagmire = Quagmire::four("SENIORITY", "EXPERIENCE", "PERCENTAGE").unwrap();
+        assert_eq!(quagmire.encrypt("SSSSSSSSSS"), "PERCENTAGE");
+    }
+
+    #[test]
+    fn test_quagmire_kryptos() {
+        // The first section of the Kryptos sculpture, with its original misspelling.
+        let quagmire = Quagmire::three("KRYPTOS", "PALIMPSEST").unwrap();
+        let plaintext = "BETWEENSUBTLESHADINGANDTHEABSENCEOFLIGHTLIESTHENUANCEOFIQLUSION";
+        let ciphertext = "EMUFPHZLRFAXYUSDJKZLDKRNSHGNFIVJYQTQUXQBQVYUVLLTREVJYQTMKYRDMFD";
+        assert_eq!(quagmire.decrypt(ciphertext), plaintext);
+        assert_eq!(quagmire.encrypt(plaintext), ciphertext);
+    }
+
+    #[test]
+    fn test_quagmire_aca_keywords() {
+        // The keywords of the ACA examples, with the indicator under `A` for
+        // Quagmire I and II and under `S` for Quagmire III and IV. The
+        // ciphertexts are checked against an independent implementation; the
+        // published test of the convention is Kryptos, above.
+        let plaintext = "DONTLETTHEMACHINESWIN";
+        let cases = [
+            (
+                Quagmire::one("SPRINGFEVER", "FLOWER").unwrap(),
+                "IUJHLPQWSUMRHPIRCISFJ",
+            ),
+            (
+                Quagmire::two("SPRINGFEVER", "FLOWER").unwrap(),
+                "ANGKOFZAZSQRVXSACTRYG",
+            ),
+            (
+                Quagmire::three("SPRINGFEVER", "FLOWER").unwrap(),
+                "OVWLXASBGIYCMIUSJRRQW",
+            ),
+            (
+                Quagmire::four("SENIORITY", "EXPERIENCE", "PERCENTAGE").unwrap(),
+                "JINHOCEJZXTBJTRAUABRI",
+            ),
+        ];
+        for (quagmire, ciphertext) in cases {
+            assert_eq!(quagmire.encrypt(plaintext), ciphertext);
+            assert_eq!(quagmire.decrypt(ciphertext), plaintext);
+        }
+    }
+
+    #[test]
+    fn test_quagmire_two_with_straight_cipher_alphabet_is_vigenere() {
+        let quagmire = Quagmire::two("", "KEY").unwrap();
+        assert_eq!(quagmire.encrypt("HELLO"), "RIJVS");
+    }
+
+    #[test]
+    fn test_quagmire_round_trip() {
+        let plaintext = "THE QUICK BROWN FOX JUMPS OVER THE LAZY DOG";
+        for quagmire in [
+            Quagmire::one("SPRINGFEVER", "FLOWER").unwrap(),
+            Quagmire::two("SPRINGFEVER", "FLOWER").unwrap(),
+            Quagmire::three("AUTOMOBILE", "HIGHWAY").unwrap(),
+            Quagmire::four("SENIORITY", "EXPERIENCE", "PERCENTAGE").unwrap(),
+        ] {
+            let ciphertext = quagmire.encrypt(plaintext);
+            assert_ne!(ciphertext, plaintext);
+            assert_eq!(quagmire.decrypt(&ciphertext), plaintext);
+        }
+    }
+
+    #[test]
+    fn test_quagmire_errors() {
+        assert_eq!(Quagmire::one("KEY", ""), Err(QuagmireError::EmptyIndicator));
+        assert_eq!(
+            Quagmire::one("KEY", "FLOWER1"),
+            Err(QuagmireError::InvalidIndicator)
+        );
+        assert_eq!(
+            Quagmire::one("KEY", "FLOWER").unwrap().with_reference('1'),
+            Err(QuagmireError::InvalidReference)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_quagmire_serde() {
+        let quagmire = Quagmire::three("KRYPTOS", "PALIMPSEST").unwrap();
+        let json = serde_json::to_string(&quagmire).unwrap();
+        assert_eq!(
+            json,
+            r#"{"variant":"III","plain":"KRYPTOSABCDEFGHIJLMNQUVWXZ","cipher":"KRYPTOSABCDEFGHIJLMNQUVWXZ","indicator":"PALIMPSEST","reference":"K"}"#
+        );
+        assert_eq!(serde_json::from_str::<Quagmire>(&json).unwrap(), quagmire);
+
+        let invalid = json.replace("PALIMPSEST", "PALIMPSEST1");
+        assert!(serde_json::from_str::<Quagmire>(&invalid).is_err());
+    }
+}
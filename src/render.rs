@@ -0,0 +1,490 @@
+//! Printable tables and grids of the ciphers, for working exercises by hand.
+//!
+//! Every table is built from the same code the ciphers use, so that a handout
+//! always matches the library: the Vigenère tabula recta, the Porta tableau,
+//! the key squares of Playfair and of the keyed Polybius square, and Caesar
+//! cipher wheels. Each one can be rendered as aligned text, as a Markdown
+//! table or as an SVG picture, see [`Format`].
+//!
+//! ```
+//! use cyphers::playfair::create_matrix;
+//! use cyphers::render::{self, Format};
+//!
+//! let square = render::key_square(&create_matrix("PLAYFAIR EXAMPLE"));
+//! assert_eq!(
+//!     square.render(Format::Text),
+//!     "P L A Y F\nI R E X M\nB C D G H\nK N O Q S\nT U V W Z"
+//! );
+//! ```
+
+use crate::key_square::KeySquare;
+use crate::porta;
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+const ALPHABET_SIZE: u8 = 26;
+
+/// The side of a cell of an SVG grid, in pixels.
+const CELL: usize = 28;
+
+/// The radius of the outer ring of an SVG cipher wheel, in pixels.
+const WHEEL_RADIUS: f64 = 160.0;
+
+/// The width of a ring of an SVG cipher wheel, in pixels.
+const RING: f64 = 36.0;
+
+/// The output formats of the tables.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Plain text with aligned columns, for terminals and printouts.
+    #[default]
+    Text,
+    /// A Markdown table.
+    Markdown,
+    /// A standalone SVG picture.
+    Svg,
+}
+
+/// A grid of letters, with optional column and row labels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid {
+    /// The labels of the columns, empty if the grid has none.
+    pub columns: Vec<String>,
+    /// The labels of the rows, empty if the grid has none.
+    pub rows: Vec<String>,
+    /// The cells, row by row.
+    pub cells: Vec<Vec<String>>,
+}
+
+impl Grid {
+    /// Renders the grid in the given format.
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Text => self.to_text(),
+            Format::Markdown => self.to_markdown(),
+            Format::Svg => self.to_svg(),
+        }
+    }
+
+    /// Renders the grid as text, with the labels separated from the cells by lines.
+    pub fn to_text(&self) -> String {
+        let label_width = self.rows.iter().map(|l| l.chars().count()).max();
+        let width = self
+            .columns
+            .iter()
+            .chain(self.cells.iter().flatten())
+            .map(|cell| cell.chars().count())
+            .max()
+            .unwrap_or_default();
+        let line = |label: Option<&str>, cells: &[String]| {
+            let cells: Vec<String> = cells
+                .iter()
+                .map(|cell| format!("{:width$}", cell, width = width))
+                .collect();
+            let cells = cells.join(" ");
+            let line = match (label, label_width) {
+                (Some(label), Some(label_width)) => {
+                    format!(
+                        "{:label_width$} | {}",
+                        label,
+                        cells,
+                        label_width = label_width
+                    )
+                }
+                _ => cells,
+            };
+            line.trim_end().to_string()
+        };
+
+        let mut lines = Vec::new();
+        if !self.columns.is_empty() {
+            lines.push(line(Some(""), &self.columns));
+            let cells_width = self.columns.len() * (width + 1) - 1;
+            lines.push(match label_width {
+                Some(label_width) => {
+                    format!("{}-+-{}", "-".repeat(label_width), "-".repeat(cells_width))
+                }
+                None => "-".repeat(cells_width),
+            });
+        }
+        for (idx, cells) in self.cells.iter().enumerate() {
+            lines.push(line(self.rows.get(idx).map(String::as_str), cells));
+        }
+        lines.join("\n")
+    }
+
+    /// Renders the grid as a Markdown table, with the row labels in bold.
+    ///
+    /// Markdown tables always have a header, which is left blank if the grid
+    /// has no column labels.
+    pub fn to_markdown(&self) -> String {
+        let columns = self.cells.first().map_or(self.columns.len(), Vec::len);
+        let labelled = !self.rows.is_empty();
+        let row = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+
+        let mut header: Vec<String> = if labelled {
+            vec![String::new()]
+        } else {
+            vec![]
+        };
+        header.extend((0..columns).map(|idx| self.columns.get(idx).cloned().unwrap_or_default()));
+        let separator = vec!["---".to_string(); header.len()];
+
+        let mut lines = vec![row(header), row(separator)];
+        for (idx, cells) in self.cells.iter().enumerate() {
+            let mut line = Vec::new();
+            if labelled {
+                line.push(format!(
+                    "**{}**",
+                    self.rows.get(idx).map_or("", String::as_str)
+                ));
+            }
+            line.extend(cells.iter().cloned());
+            lines.push(row(line));
+        }
+        lines.join("\n")
+    }
+
+    /// Renders the grid as an SVG picture, with the labels in bold on a grey background.
+    pub fn to_svg(&self) -> String {
+        let left = usize::from(!self.rows.is_empty());
+        let top = usize::from(!self.columns.is_empty());
+        let columns = self.cells.first().map_or(self.columns.len(), Vec::len);
+        let width = (left + columns) * CELL;
+        let height = (top + self.cells.len()) * CELL;
+
+        let mut svg = svg_header(width, height);
+        let mut cell = |x: usize, y: usize, text: &str, label: bool| {
+            let (px, py) = (x * CELL, y * CELL);
+            let _ = write!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"black\"/>",
+                px,
+                py,
+                CELL,
+                CELL,
+                if label { "#e0e0e0" } else { "white" }
+            );
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\"{}>{}</text>",
+                px + CELL / 2,
+                py + CELL / 2,
+                if label { " font-weight=\"bold\"" } else { "" },
+                escape(text)
+            );
+        };
+
+        for (x, label) in self.columns.iter().enumerate() {
+            cell(left + x, 0, label, true);
+        }
+        for (y, label) in self.rows.iter().enumerate() {
+            cell(0, top + y, label, true);
+        }
+        for (y, cells) in self.cells.iter().enumerate() {
+            for (x, text) in cells.iter().enumerate() {
+                cell(left + x, top + y, text, false);
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// A Caesar cipher wheel: the plaintext alphabet on the outer ring, and the
+/// ciphertext alphabet on the inner ring, turned by the key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Wheel {
+    shift: u8,
+}
+
+impl Wheel {
+    /// Returns the shift of the wheel, from 0 to 25.
+    pub fn shift(&self) -> u8 {
+        self.shift
+    }
+
+    /// Returns the plaintext and ciphertext alphabets, in the order of the wheel.
+    pub fn rings(&self) -> (String, String) {
+        let plain = (0..ALPHABET_SIZE).map(|idx| (b'A' + idx) as char).collect();
+        let cipher = (0..ALPHABET_SIZE)
+            .map(|idx| (b'A' + (idx + self.shift) % ALPHABET_SIZE) as char)
+            .collect();
+        (plain, cipher)
+    }
+
+    /// Renders the wheel in the given format.
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Text => self.grid().to_text(),
+            Format::Markdown => self.grid().to_markdown(),
+            Format::Svg => self.to_svg(),
+        }
+    }
+
+    /// Renders the wheel as an SVG picture of two concentric rings of letters.
+    pub fn to_svg(&self) -> String {
+        let size = (2.0 * WHEEL_RADIUS) as usize + 2;
+        let center = size as f64 / 2.0;
+        let (plain, cipher) = self.rings();
+
+        let mut svg = svg_header(size, size);
+        for radius in [WHEEL_RADIUS, WHEEL_RADIUS - RING, WHEEL_RADIUS - 2.0 * RING] {
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"black\"/>",
+                center, center, radius
+            );
+        }
+        let sector = 2.0 * PI / f64::from(ALPHABET_SIZE);
+        let point = |radius: f64, angle: f64| {
+            (center + radius * angle.sin(), center - radius * angle.cos())
+        };
+        for (idx, (p, c)) in plain.chars().zip(cipher.chars()).enumerate() {
+            // The letters are centered in their sector, with A at the top.
+            let angle = sector * idx as f64;
+            let (x1, y1) = point(WHEEL_RADIUS - 2.0 * RING, angle + sector / 2.0);
+            let (x2, y2) = point(WHEEL_RADIUS, angle + sector / 2.0);
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"black\"/>",
+                x1, y1, x2, y2
+            );
+            for (radius, letter, weight) in [
+                (WHEEL_RADIUS - RING / 2.0, p, ""),
+                (WHEEL_RADIUS - 1.5 * RING, c, " font-weight=\"bold\""),
+            ] {
+                let (x, y) = point(radius, angle);
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"{:.1}\" y=\"{:.1}\"{}>{}</text>",
+                    x, y, weight, letter
+                );
+            }
+        }
+        let _ = writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\">+{}</text>",
+            center, center, self.shift
+        );
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Lays the wheel out flat, as the two rows of a grid.
+    fn grid(&self) -> Grid {
+        let (plain, cipher) = self.rings();
+        Grid {
+            columns: Vec::new(),
+            rows: vec!["Plain".to_string(), "Cipher".to_string()],
+            cells: [plain, cipher]
+                .iter()
+                .map(|ring| ring.chars().map(String::from).collect())
+                .collect(),
+        }
+    }
+}
+
+/// Builds the Vigenère tabula recta.
+///
+/// # Returns
+///
+/// A 26x26 grid, with the plaintext letters as columns and the key letters as
+/// rows: a letter is encrypted by the cell at its column in the row of the key letter.
+pub fn tabula_recta() -> Grid {
+    Grid {
+        columns: letters(0..ALPHABET_SIZE),
+        rows: letters(0..ALPHABET_SIZE),
+        cells: (0..ALPHABET_SIZE)
+            .map(|key| letters((0..ALPHABET_SIZE).map(|letter| (letter + key) % ALPHABET_SIZE)))
+            .collect(),
+    }
+}
+
+/// Builds the Porta tableau, as used by [`porta::substitute`].
+///
+/// # Returns
+///
+/// A 13x13 grid, with the first half of the alphabet as columns and a row per
+/// pair of key letters (`A,B`, `C,D`, ..., `Y,Z`). Each letter of a row is
+/// swapped with the letter of its column.
+pub fn porta_tableau() -> Grid {
+    let half = ALPHABET_SIZE / 2;
+    Grid {
+        columns: letters(0..half),
+        rows: (0..half)
+            .map(|pair| {
+                format!(
+                    "{},{}",
+                    (b'A' + 2 * pair) as char,
+                    (b'B' + 2 * pair) as char
+                )
+            })
+            .collect(),
+        cells: (0..half)
+            .map(|pair| letters((0..half).map(|letter| porta::substitute(letter, 2 * pair))))
+            .collect(),
+    }
+}
+
+/// Builds the grid of a key square, without labels, e.g. the Playfair square
+/// of [`crate::playfair::create_matrix`].
+///
+/// # Arguments
+///
+/// * `square` - The key square.
+pub fn key_square(square: &KeySquare) -> Grid {
+    Grid {
+        columns: Vec::new(),
+        rows: Vec::new(),
+        cells: square
+            .rows()
+            .iter()
+            .map(|row| row.iter().map(|c| c.to_string()).collect())
+            .collect(),
+    }
+}
+
+/// Builds the grid of a keyed Polybius square, labelled with the coordinates
+/// from 1 to 5 used by [`crate::polybius_square`] and [`crate::nihilist`].
+///
+/// # Arguments
+///
+/// * `square` - The key square.
+pub fn polybius_grid(square: &KeySquare) -> Grid {
+    let coordinates: Vec<String> = (1..=square.rows().len()).map(|n| n.to_string()).collect();
+    Grid {
+        columns: coordinates.clone(),
+        rows: coordinates,
+        ..key_square(square)
+    }
+}
+
+/// Builds a Caesar cipher wheel.
+///
+/// # Arguments
+///
+/// * `key` - The shift of the cipher, as given to [`crate::caesar::encrypt`].
+pub fn caesar_wheel(key: i32) -> Wheel {
+    Wheel {
+        shift: key.rem_euclid(i32::from(ALPHABET_SIZE)) as u8,
+    }
+}
+
+/// Converts letter indices (`A` = 0) to one-letter strings.
+fn letters(indices: impl Iterator<Item = u8>) -> Vec<String> {
+    indices
+        .map(|idx| ((b'A' + idx) as char).to_string())
+        .collect()
+}
+
+/// Starts an SVG document with centered text.
+fn svg_header(width: usize, height: usize) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" \
+         font-family=\"monospace\" font-size=\"16\" text-anchor=\"middle\" dominant-baseline=\"central\">\n",
+        w = width,
+        h = height
+    )
+}
+
+/// Escapes the special characters of XML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_square::SquareAlphabet;
+    use crate::{caesar, vigenere};
+
+    #[test]
+    fn test_render_tabula_recta() {
+        let table = tabula_recta();
+        // The row of the key letter gives the ciphertext of every plaintext letter.
+        let (key, plain) = (b'L' - b'A', b'A' - b'A');
+        assert_eq!(
+            table.cells[key as usize][plain as usize],
+            vigenere::encrypt("A", "L")
+        );
+
+        let text = table.render(Format::Text);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 28);
+        assert_eq!(
+            lines[0],
+            "  | A B C D E F G H I J K L M N O P Q R S T U V W X Y Z"
+        );
+        assert_eq!(lines[1], format!("--+-{}", "-".repeat(51)));
+        assert_eq!(
+            lines[3],
+            "B | B C D E F G H I J K L M N O P Q R S T U V W X Y Z A"
+        );
+    }
+
+    #[test]
+    fn test_render_porta_tableau() {
+        let table = porta_tableau();
+        let text = table.render(Format::Text);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "    | A B C D E F G H I J K L M");
+        assert_eq!(lines[2], "A,B | N O P Q R S T U V W X Y Z");
+        assert_eq!(lines[14], "Y,Z | Z N O P Q R S T U V W X Y");
+
+        let markdown = table.render(Format::Markdown);
+        assert!(markdown.starts_with("|  | A | B |"));
+        assert!(markdown.contains("\n| **C,D** | O | P |"));
+    }
+
+    #[test]
+    fn test_render_squares() {
+        let square = KeySquare::new("KEYWORD", SquareAlphabet::MergeJ);
+        assert_eq!(
+            polybius_grid(&square).render(Format::Text),
+            "  | 1 2 3 4 5\n\
+             --+----------\n\
+             1 | K E Y W O\n\
+             2 | R D A B C\n\
+             3 | F G H I L\n\
+             4 | M N P Q S\n\
+             5 | T U V X Z"
+        );
+        assert_eq!(
+            key_square(&square)
+                .render(Format::Markdown)
+                .lines()
+                .take(3)
+                .collect::<Vec<_>>(),
+            [
+                "|  |  |  |  |  |",
+                "| --- | --- | --- | --- | --- |",
+                "| K | E | Y | W | O |"
+            ]
+        );
+
+        let svg = polybius_grid(&square).render(Format::Svg);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect ").count(), 35);
+    }
+
+    #[test]
+    fn test_render_caesar_wheel() {
+        let wheel = caesar_wheel(-23);
+        assert_eq!(wheel.shift(), 3);
+        let (plain, cipher) = wheel.rings();
+        assert_eq!(cipher, caesar::encrypt(&plain, 3));
+        assert_eq!(
+            wheel.render(Format::Text),
+            "Plain  | A B C D E F G H I J K L M N O P Q R S T U V W X Y Z\n\
+             Cipher | D E F G H I J K L M N O P Q R S T U V W X Y Z A B C"
+        );
+
+        let svg = wheel.render(Format::Svg);
+        assert_eq!(svg.matches("<circle ").count(), 3);
+        assert_eq!(svg.matches("<text ").count(), 53);
+    }
+}
@@ -1,5 +1,8 @@
 //! An implementation of the ROT13 cipher
 
+use crate::prelude::*;
+use crate::trace::Step;
+
 const ROTATION: u8 = 13;
 const ALPHABET_SIZE: u8 = 26;
 
@@ -20,24 +23,48 @@ const ALPHABET_SIZE: u8 = 26;
 /// assert_eq!(rot13("Uryyb, Jbeyq!"), "Hello, World!");
 /// ```
 pub fn rot13(input: &str) -> String {
+    // Transform each character in the input string, and collect them into a new string.
+    input.chars().map(rot13_char).collect()
+}
+
+/// Performs a ROT13 transformation on a given string slice, recording every step.
+///
+/// # Arguments
+///
+/// * `input` - The text to be transformed.
+///
+/// # Returns
+///
+/// The transformed text, and one step per character: `rotate` for the letters,
+/// `keep` for the other characters.
+pub fn rot13_traced(input: &str) -> (String, Vec<Step>) {
     input
         .chars()
-        // Iterate over each character in the input string.
         .map(|c| {
-            if c.is_ascii_alphabetic() {
-                // Determine the ASCII value of the base character ('a' for lowercase, 'A' for uppercase).
-                let base = if c.is_ascii_lowercase() { b'a' } else { b'A' };
-                // Calculate the ROT13 offset for the current character.
-                let offset = (c as u8 - base + ROTATION) % ALPHABET_SIZE;
-                // Convert the offset back to a char and return it.
-                (base + offset) as char
+            let output = rot13_char(c);
+            let rule = if c.is_ascii_alphabetic() {
+                "rotate"
             } else {
-                // If the character is not an ASCII alphabetic character, leave it unchanged.
-                c
-            }
+                "keep"
+            };
+            (output, Step::new(c, output, rule))
         })
-        // Collect the modified characters into a new string and return it.
-        .collect()
+        .unzip()
+}
+
+/// Rotates an ASCII letter by 13 places, leaving the other characters unchanged.
+fn rot13_char(c: char) -> char {
+    if c.is_ascii_alphabetic() {
+        // Determine the ASCII value of the base character ('a' for lowercase, 'A' for uppercase).
+        let base = if c.is_ascii_lowercase() { b'a' } else { b'A' };
+        // Calculate the ROT13 offset for the current character.
+        let offset = (c as u8 - base + ROTATION) % ALPHABET_SIZE;
+        // Convert the offset back to a char and return it.
+        (base + offset) as char
+    } else {
+        // If the character is not an ASCII alphabetic character, leave it unchanged.
+        c
+    }
 }
 
 #[cfg(test)]
@@ -75,4 +102,12 @@ mod tests {
         let input = "ROT13 is Idempotent!";
         assert_eq!(rot13(&rot13(input)), input);
     }
+
+    #[test]
+    fn test_rot13_traced() {
+        let (output, steps) = rot13_traced("Hi!");
+        assert_eq!(output, "Uv!");
+        assert_eq!(steps[0], Step::new('H', 'U', "rotate"));
+        assert_eq!(steps[2], Step::new('!', '!', "keep"));
+    }
 }
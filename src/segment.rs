@@ -0,0 +1,373 @@
+//! Word segmentation of decrypted text.
+//!
+//! Most ciphers of the crate drop spaces and punctuation, so that a decryption
+//! comes out as a run-on block of letters such as `MEETMEATTHEBRIDGEX`. The
+//! [`segment`] function puts the spaces back: among all the ways of splitting
+//! the letters into words, it finds the most probable one under a word model
+//! with the Viterbi algorithm.
+//!
+//! The [`WordModel`] combines the frequencies of single words (unigrams) with
+//! those of pairs of consecutive words (bigrams), so that `THE MEN` is preferred
+//! to `THEM EN`. Words missing from the model are possible, but their
+//! probability decreases quickly with their length.
+//!
+//! A [`Segmentation`] also reports its readability, the share of the letters
+//! belonging to known words: a value close to 1 means the decryption is most
+//! likely right.
+
+use crate::language::ENGLISH_CORPUS;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A list of common English words, added to the words of the corpus.
+const ENGLISH_WORDS: &str = include_str!("../data/words.txt");
+
+/// The longest word considered by the segmentation.
+const MAX_WORD_LEN: usize = 24;
+
+/// The weight of the bigram probability, interpolated with the unigram probability.
+const BIGRAM_WEIGHT: f64 = 0.7;
+
+/// The frequencies of the words of a language and of the pairs of consecutive words.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordModel {
+    unigrams: HashMap<String, f64>,
+    bigrams: HashMap<String, HashMap<String, f64>>,
+    total: f64,
+}
+
+impl WordModel {
+    /// Trains an English model on the corpus embedded in the crate, and adds a
+    /// list of common words, each counted once.
+    pub fn english() -> Self {
+        let mut model = Self::from_text(ENGLISH_CORPUS);
+        for word in ENGLISH_WORDS.split_whitespace() {
+            let word = word.to_ascii_uppercase();
+            if !model.contains(&word) {
+                model.add_word(&word, 1.0);
+            }
+        }
+        model
+    }
+
+    /// Trains a model on a sample text of the language.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The training text. Words are runs of letters; pairs of words
+    ///   are not counted across sentence punctuation (`.`, `!`, `?`, `;`, `:`).
+    pub fn from_text(text: &str) -> Self {
+        let mut model = WordModel {
+            unigrams: HashMap::new(),
+            bigrams: HashMap::new(),
+            total: 0.0,
+        };
+        for sentence in text.split(['.', '!', '?', ';', ':']) {
+            let words: Vec<String> = sentence
+                .split(|c: char| !c.is_ascii_alphabetic())
+                .filter(|word| !word.is_empty())
+                .map(str::to_ascii_uppercase)
+                .collect();
+            for word in &words {
+                model.add_word(word, 1.0);
+            }
+            for pair in words.windows(2) {
+                *model
+                    .bigrams
+                    .entry(pair[0].clone())
+                    .or_default()
+                    .entry(pair[1].clone())
+                    .or_default() += 1.0;
+            }
+        }
+        model
+    }
+
+    /// Returns whether a word, in uppercase, is known to the model.
+    pub fn contains(&self, word: &str) -> bool {
+        self.unigrams.contains_key(word)
+    }
+
+    /// Returns the log-probability of a word, in uppercase.
+    ///
+    /// An unknown word of `n` letters gets a probability of `10 / (N * 10^n)`,
+    /// where `N` is the number of words of the training data.
+    pub fn log_probability(&self, word: &str) -> f64 {
+        match self.unigrams.get(word) {
+            Some(&count) => (count / self.total).ln(),
+            None => 10f64.ln() * (1.0 - word.len() as f64) - self.total.ln(),
+        }
+    }
+
+    /// Returns the log-probability of a word following another, in uppercase.
+    ///
+    /// The bigram probability is interpolated with the unigram probability, so
+    /// that pairs missing from the training data remain possible.
+    ///
+    /// # Arguments
+    ///
+    /// * `previous` - The previous word, if any.
+    /// * `word` - The word.
+    pub fn conditional_log_probability(&self, previous: Option<&str>, word: &str) -> f64 {
+        let unigram = self.log_probability(word);
+        let Some(previous) = previous else {
+            return unigram;
+        };
+        let pair = self
+            .bigrams
+            .get(previous)
+            .and_then(|followers| followers.get(word));
+        match (pair, self.unigrams.get(previous)) {
+            (Some(&pair), Some(&count)) => {
+                (BIGRAM_WEIGHT * pair / count + (1.0 - BIGRAM_WEIGHT) * unigram.exp()).ln()
+            }
+            _ => (1.0 - BIGRAM_WEIGHT).ln() + unigram,
+        }
+    }
+
+    fn add_word(&mut self, word: &str, count: f64) {
+        *self.unigrams.entry(word.to_string()).or_default() += count;
+        self.total += count;
+    }
+}
+
+/// A text split into words.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segmentation {
+    /// The words, in uppercase.
+    pub words: Vec<String>,
+    /// The log-probability of the words under the model.
+    pub score: f64,
+    /// The share of the letters belonging to words known to the model, from 0 to 1.
+    pub readability: f64,
+}
+
+impl fmt::Display for Segmentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.words.join(" "))
+    }
+}
+
+/// Splits run-on text into words, optionally removing the filler letters of digraphic ciphers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segmenter {
+    model: WordModel,
+    filler: Option<char>,
+}
+
+impl Segmenter {
+    /// Creates a segmenter using a word model, without filler removal.
+    pub fn new(model: WordModel) -> Self {
+        Segmenter {
+            model,
+            filler: None,
+        }
+    }
+
+    /// Creates a segmenter using the English word model.
+    pub fn english() -> Self {
+        Self::new(WordModel::english())
+    }
+
+    /// Removes a filler letter before segmenting, as inserted by Playfair and the
+    /// other digraphic ciphers (see [`strip_filler`]).
+    pub fn strip_filler(mut self, filler: char) -> Self {
+        self.filler = Some(filler.to_ascii_uppercase());
+        self
+    }
+
+    /// Splits a text into its most probable sequence of words.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text. Non-alphabetic characters are dropped.
+    ///
+    /// # Returns
+    ///
+    /// The words in uppercase, with their score and readability.
+    pub fn segment(&self, text: &str) -> Segmentation {
+        let mut letters: String = text
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        if let Some(filler) = self.filler {
+            letters = strip_filler(&letters, filler);
+            // A final filler is padding, unless it ends a known word.
+            if letters.ends_with(filler) {
+                let segmentation = self.viterbi(&letters);
+                let last = segmentation.words.last().map_or("", String::as_str);
+                if last.len() == 1 || !self.model.contains(last) {
+                    letters.pop();
+                } else {
+                    return segmentation;
+                }
+            }
+        }
+        self.viterbi(&letters)
+    }
+
+    /// Finds the most probable segmentation of uppercase letters.
+    ///
+    /// `best[end][len]` is the score of the best segmentation of the letters
+    /// before `end` whose last word has `len` letters.
+    fn viterbi(&self, letters: &str) -> Segmentation {
+        let n = letters.len();
+        let mut best = vec![vec![f64::NEG_INFINITY; MAX_WORD_LEN + 1]; n + 1];
+        let mut back = vec![vec![0; MAX_WORD_LEN + 1]; n + 1];
+
+        for end in 1..=n {
+            for len in 1..=MAX_WORD_LEN.min(end) {
+                let start = end - len;
+                let word = &letters[start..end];
+                if start == 0 {
+                    best[end][len] = self.model.conditional_log_probability(None, word);
+                    continue;
+                }
+                for previous in 1..=MAX_WORD_LEN.min(start) {
+                    let score = best[start][previous]
+                        + self.model.conditional_log_probability(
+                            Some(&letters[start - previous..start]),
+                            word,
+                        );
+                    if score > best[end][len] {
+                        best[end][len] = score;
+                        back[end][len] = previous;
+                    }
+                }
+            }
+        }
+
+        let mut words = Vec::new();
+        let mut score = 0.0;
+        if n > 0 {
+            let (mut len, &last) = best[n]
+                .iter()
+                .enumerate()
+                .skip(1)
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .expect("the table has a column per word length");
+            score = last;
+            let mut end = n;
+            while end > 0 {
+                words.push(letters[end - len..end].to_string());
+                let previous = back[end][len];
+                end -= len;
+                len = previous;
+            }
+            words.reverse();
+        }
+
+        let known: usize = words
+            .iter()
+            .filter(|word| self.model.contains(word))
+            .map(String::len)
+            .sum();
+        Segmentation {
+            words,
+            score,
+            readability: if n == 0 { 0.0 } else { known as f64 / n as f64 },
+        }
+    }
+}
+
+/// Splits a text into its most probable sequence of English words.
+///
+/// # Arguments
+///
+/// * `text` - The text. Non-alphabetic characters are dropped.
+///
+/// # Returns
+///
+/// The words in uppercase, with their score and readability.
+pub fn segment(text: &str) -> Segmentation {
+    Segmenter::english().segment(text)
+}
+
+/// Removes the filler letters inserted between doubled letters by digraphic ciphers.
+///
+/// Playfair splits a doubled letter falling in a digraph with a filler, so that
+/// `BALLOON` is enciphered as `BA LX LO ON`. A filler is removed when it is the
+/// second letter of a digraph and lies between two identical letters, so that
+/// words such as `TAXA` or `ANNEXE` are left intact. A word such as `EXE` whose
+/// `X` falls in second position remains ambiguous and loses its `X`.
+///
+/// # Arguments
+///
+/// * `text` - The decrypted text, in uppercase, starting at the first letter of a digraph.
+/// * `filler` - The filler letter, usually `X`.
+pub fn strip_filler(text: &str, filler: char) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .iter()
+        .enumerate()
+        .filter(|&(idx, &c)| {
+            !(c == filler
+                && idx % 2 == 1
+                && idx + 1 < chars.len()
+                && chars[idx - 1] == chars[idx + 1])
+        })
+        .map(|(_, &c)| c)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playfair;
+
+    #[test]
+    fn test_segment() {
+        let segmentation = segment("WEWILLATTACKTHEBRIDGEATMIDNIGHT");
+        assert_eq!(
+            segmentation.to_string(),
+            "WE WILL ATTACK THE BRIDGE AT MIDNIGHT"
+        );
+        assert_eq!(segmentation.readability, 1.0);
+
+        let segmentation = segment("wearediscoveredfleeatonce");
+        assert_eq!(segmentation.to_string(), "WE ARE DISCOVERED FLEE AT ONCE");
+
+        let garbled = segment("QZXVJWKQYVFUBP");
+        assert!(garbled.readability < 0.5);
+        assert!(garbled.score < segment("ATTACKTHEHILLS").score);
+
+        assert_eq!(segment("").words, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_segment_fillers() {
+        assert_eq!(strip_filler("BALXLOON", 'X'), "BALLOON");
+        assert_eq!(strip_filler("BOXES", 'X'), "BOXES");
+        // Only the second letter of a digraph can be a filler.
+        assert_eq!(strip_filler("TAXA", 'X'), "TAXA");
+        assert_eq!(strip_filler("ANNEXE", 'X'), "ANNEXE");
+        assert_eq!(strip_filler("THEEXE", 'X'), "THEEXE");
+        assert_eq!(strip_filler("EXE", 'X'), "EE");
+
+        let segmenter = Segmenter::english().strip_filler('X');
+        assert_eq!(segmenter.segment("HELXLOWORLDX").to_string(), "HELLO WORLD");
+        assert_eq!(segmenter.segment("SENDTHEBOX").to_string(), "SEND THE BOX");
+
+        // Decrypting Playfair leaves the fillers in.
+        let ciphertext = playfair::encrypt("PLAYFAIR", "We will attack at dawn").unwrap();
+        let plaintext = playfair::decrypt("PLAYFAIR", &ciphertext).unwrap();
+        assert_eq!(plaintext, "WEWILXLATXTACKATDAWN");
+        assert_eq!(
+            segmenter.segment(&plaintext).to_string(),
+            "WE WILL ATTACK AT DAWN"
+        );
+    }
+
+    #[test]
+    fn test_segment_word_model() {
+        let model = WordModel::from_text("The men ran. Them end.");
+        assert!(model.contains("MEN"));
+        assert!(!model.contains("EN"));
+        assert!(model.log_probability("THE") > model.log_probability("QQQ"));
+        assert!(
+            model.conditional_log_probability(Some("THE"), "MEN")
+                > model.conditional_log_probability(Some("RAN"), "MEN")
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! An implementation of Bruce Schneier's Solitaire cipher.
+//!
+//! Solitaire (called Pontifex in Neal Stephenson's novel *Cryptonomicon*) is an
+//! output-feedback stream cipher computed by hand with a deck of 52 cards and
+//! two jokers. The order of the deck is the key. Every step of the algorithm
+//! shuffles the deck in a deterministic way and produces a key number between 1
+//! and 26, which is added to a letter of the plaintext, as in a Vigenère cipher
+//! with a running key.
+//!
+//! Where [`crate::otp`] needs a truly random key as long as the message, the
+//! Solitaire keystream is generated from a short key that is easy to carry: an
+//! innocent-looking deck of cards, or a passphrase used to shuffle it.
+//!
+//! Cards are numbered in bridge order: clubs (1 to 13), diamonds (14 to 26),
+//! hearts (27 to 39) and spades (40 to 52). The jokers are [`JOKER_A`] (53) and
+//! [`JOKER_B`] (54), and both count as 53.
+
+use crate::prelude::*;
+use thiserror::Error;
+
+/// The number of cards in the deck, including the two jokers.
+pub const DECK_SIZE: usize = 54;
+
+/// The first joker.
+pub const JOKER_A: u8 = 53;
+
+/// The second joker.
+pub const JOKER_B: u8 = 54;
+
+const ALPHABET_SIZE: u8 = 26;
+
+/// Error type for the Solitaire cipher.
+#[derive(Error, Debug, PartialEq)]
+pub enum SolitaireError {
+    #[error("The deck must contain each of the 54 cards exactly once")]
+    InvalidDeck,
+}
+
+/// Represents the mode of operation: encryption or decryption.
+#[derive(Clone, Copy)]
+enum Mode {
+    Encrypt,
+    Decrypt,
+}
+
+/// A deck of cards generating the Solitaire keystream.
+///
+/// With the `serde` feature, the deck is serialized as its cards, from top to bottom.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "Vec<u8>", into = "Vec<u8>")
+)]
+pub struct Solitaire {
+    deck: Vec<u8>,
+}
+
+impl Solitaire {
+    /// Creates an unkeyed deck: the cards in bridge order, followed by the jokers A and B.
+    pub fn new() -> Self {
+        Solitaire {
+            deck: (1..=DECK_SIZE as u8).collect(),
+        }
+    }
+
+    /// Creates a deck keyed with a passphrase.
+    ///
+    /// Starting from the unkeyed deck, every letter of the passphrase performs a
+    /// step of the algorithm, without output, followed by a second count cut
+    /// using the value of the letter (`A` = 1) instead of the bottom card.
+    ///
+    /// # Arguments
+    ///
+    /// * `passphrase` - The passphrase. Non-alphabetic characters are ignored.
+    pub fn from_passphrase(passphrase: &str) -> Self {
+        let mut solitaire = Self::new();
+        for letter in passphrase.bytes().filter(u8::is_ascii_alphabetic) {
+            solitaire.shuffle();
+            solitaire.count_cut(letter.to_ascii_uppercase() - b'A' + 1);
+        }
+        solitaire
+    }
+
+    /// Creates a deck in a given order.
+    ///
+    /// # Arguments
+    ///
+    /// * `deck` - The cards from top to bottom, numbered from 1 to 54.
+    ///
+    /// # Returns
+    ///
+    /// The deck, or an error if it is not a permutation of the 54 cards.
+    pub fn from_deck(deck: &[u8]) -> Result<Self, SolitaireError> {
+        let mut sorted = deck.to_vec();
+        sorted.sort_unstable();
+        if !sorted.iter().copied().eq(1..=DECK_SIZE as u8) {
+            return Err(SolitaireError::InvalidDeck);
+        }
+        Ok(Solitaire {
+            deck: deck.to_vec(),
+        })
+    }
+
+    /// Returns the cards of the deck, from top to bottom.
+    pub fn deck(&self) -> &[u8] {
+        &self.deck
+    }
+
+    /// Generates the next key number, shuffling the deck.
+    ///
+    /// # Returns
+    ///
+    /// The key number, between 1 and 26.
+    pub fn next_key(&mut self) -> u8 {
+        loop {
+            self.shuffle();
+            let card = self.deck[value(self.deck[0]) as usize];
+            if card < JOKER_A {
+                return (card - 1) % ALPHABET_SIZE + 1;
+            }
+        }
+    }
+
+    /// Generates a number of key numbers.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - The number of key numbers to generate.
+    pub fn key_stream(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_key()).collect()
+    }
+
+    /// Encrypts the provided plaintext with the next numbers of the keystream.
+    ///
+    /// Non-alphabetic characters are dropped, and the plaintext is padded with
+    /// `X` to a multiple of five letters.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The text to be encrypted.
+    ///
+    /// # Returns
+    ///
+    /// The encrypted text, in uppercase.
+    pub fn encrypt(&mut self, plaintext: &str) -> String {
+        let mut letters = letters(plaintext);
+        letters.resize(letters.len().div_ceil(5) * 5, b'X');
+        self.encipher(&letters, Mode::Encrypt)
+    }
+
+    /// Decrypts the provided ciphertext with the next numbers of the keystream.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The text to be decrypted. Non-alphabetic characters are dropped.
+    ///
+    /// # Returns
+    ///
+    /// The decrypted text in uppercase, including the padding.
+    pub fn decrypt(&mut self, ciphertext: &str) -> String {
+        self.encipher(&letters(ciphertext), Mode::Decrypt)
+    }
+
+    /// Core logic for the encryption/decryption process.
+    fn encipher(&mut self, letters: &[u8], mode: Mode) -> String {
+        letters
+            .iter()
+            .map(|&letter| {
+                let key = match mode {
+                    Mode::Encrypt => self.next_key(),
+                    Mode::Decrypt => ALPHABET_SIZE - self.next_key(),
+                };
+                ((letter - b'A' + key) % ALPHABET_SIZE + b'A') as char
+            })
+            .collect()
+    }
+
+    /// Performs the first four steps of the algorithm: the joker moves, the triple cut and the count cut.
+    fn shuffle(&mut self) {
+        self.move_down(JOKER_A);
+        self.move_down(JOKER_B);
+        self.move_down(JOKER_B);
+        self.triple_cut();
+        self.count_cut(value(self.deck[DECK_SIZE - 1]));
+    }
+
+    /// Moves a card one position down. The bottom card moves just below the top card.
+    fn move_down(&mut self, card: u8) {
+        let position = self.position(card);
+        if position == DECK_SIZE - 1 {
+            self.deck.remove(position);
+            self.deck.insert(1, card);
+        } else {
+            self.deck.swap(position, position + 1);
+        }
+    }
+
+    /// Swaps the cards above the first joker with the cards below the second joker.
+    fn triple_cut(&mut self) {
+        let (a, b) = (self.position(JOKER_A), self.position(JOKER_B));
+        let (top, bottom) = (a.min(b), a.max(b));
+        let mut deck = self.deck[bottom + 1..].to_vec();
+        deck.extend_from_slice(&self.deck[top..=bottom]);
+        deck.extend_from_slice(&self.deck[..top]);
+        self.deck = deck;
+    }
+
+    /// Moves the given number of cards from the top to just above the bottom card.
+    fn count_cut(&mut self, count: u8) {
+        self.deck[..DECK_SIZE - 1].rotate_left(count as usize % (DECK_SIZE - 1));
+    }
+
+    fn position(&self, card: u8) -> usize {
+        self.deck
+            .iter()
+            .position(|&c| c == card)
+            .unwrap_or_default()
+    }
+}
+
+impl Default for Solitaire {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TryFrom<Vec<u8>> for Solitaire {
+    type Error = SolitaireError;
+
+    fn try_from(deck: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::from_deck(&deck)
+    }
+}
+
+impl From<Solitaire> for Vec<u8> {
+    fn from(solitaire: Solitaire) -> Self {
+        solitaire.deck
+    }
+}
+
+/// Encrypts the provided plaintext with a deck keyed with a passphrase.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted. Non-alphabetic characters are dropped.
+/// * `passphrase` - The passphrase keying the deck.
+///
+/// # Returns
+///
+/// The encrypted text in uppercase, padded with `X` to a multiple of five letters.
+pub fn encrypt(plaintext: &str, passphrase: &str) -> String {
+    Solitaire::from_passphrase(passphrase).encrypt(plaintext)
+}
+
+/// Decrypts the provided ciphertext with a deck keyed with a passphrase.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted. Non-alphabetic characters are dropped.
+/// * `passphrase` - The passphrase keying the deck.
+///
+/// # Returns
+///
+/// The decrypted text in uppercase, including the padding.
+pub fn decrypt(ciphertext: &str, passphrase: &str) -> String {
+    Solitaire::from_passphrase(passphrase).decrypt(ciphertext)
+}
+
+/// Returns the count value of a card: the jokers both count as 53.
+fn value(card: u8) -> u8 {
+    card.min(JOKER_A)
+}
+
+/// Returns the uppercase letters of a text.
+fn letters(text: &str) -> Vec<u8> {
+    text.bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|b| b.to_ascii_uppercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solitaire_unkeyed() {
+        let mut solitaire = Solitaire::new();
+        assert_eq!(
+            solitaire.key_stream(10),
+            vec![4, 23, 10, 24, 8, 25, 18, 6, 4, 7]
+        );
+        assert_eq!(Solitaire::new().encrypt("AAAAAAAAAA"), "EXKYIZSGEH");
+    }
+
+    #[test]
+    fn test_solitaire_encrypt() {
+        assert_eq!(encrypt("AAAAAAAAAAAAAAA", "FOO"), "ITHZUJIWGRFARMW");
+        assert_eq!(encrypt("SOLITAIRE", "CRYPTONOMICON"), "KIRAKSFJAN");
+    }
+
+    #[test]
+    fn test_solitaire_decrypt() {
+        assert_eq!(decrypt("KIRAKSFJAN", "CRYPTONOMICON"), "SOLITAIREX");
+        assert_eq!(decrypt("ITHZUJIWGRFARMW", "FOO"), "A".repeat(15));
+    }
+
+    #[test]
+    fn test_solitaire_deck() {
+        // The deck keeps its cards, and a deck in a given order gives the same keystream.
+        let mut solitaire = Solitaire::from_passphrase("Cryptonomicon");
+        let mut copy = Solitaire::from_deck(solitaire.deck()).unwrap();
+        assert_eq!(solitaire.key_stream(100), copy.key_stream(100));
+
+        let mut deck = solitaire.deck().to_vec();
+        deck.sort_unstable();
+        assert_eq!(deck, (1..=54).collect::<Vec<u8>>());
+
+        assert_eq!(
+            Solitaire::from_deck(&[1, 2, 3]),
+            Err(SolitaireError::InvalidDeck)
+        );
+        assert_eq!(
+            Solitaire::from_deck(&[1; DECK_SIZE]),
+            Err(SolitaireError::InvalidDeck)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_solitaire_serde() {
+        let mut solitaire = Solitaire::from_passphrase("FOO");
+        solitaire.key_stream(10);
+        let json = serde_json::to_string(&solitaire).unwrap();
+        let mut copy: Solitaire = serde_json::from_str(&json).unwrap();
+        assert_eq!(copy, solitaire);
+        assert_eq!(copy.key_stream(100), solitaire.key_stream(100));
+        assert!(serde_json::from_str::<Solitaire>("[1, 2, 3]").is_err());
+    }
+}
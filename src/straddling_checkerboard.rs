@@ -0,0 +1,394 @@
+//! An implementation of the straddling checkerboard.
+//!
+//! A straddling checkerboard converts letters to digits, giving one-digit codes
+//! to the most frequent letters and two-digit codes to the others. The board has
+//! ten columns, each labelled with a digit, and three rows:
+//!
+//! * the top row holds eight symbols and two blank cells;
+//! * the two other rows hold ten symbols each, and are labelled with the digits
+//!   of the columns of the blank cells.
+//!
+//! A symbol of the top row is encoded with the digit of its column, and a symbol
+//! of another row with the digit of its row followed by the digit of its column.
+//! Since no one-digit code is the label of a row, the digits can be decoded
+//! without separators.
+//!
+//! The board holds 28 symbols: the 26 letters, a full stop and a figure sign.
+//! Digits of the plaintext are encoded as the figure sign followed by the digit itself.
+
+use crate::keyed_alphabet::KeyedAlphabet;
+use crate::prelude::*;
+use crate::trace::Step;
+use thiserror::Error;
+
+/// The number of columns of the board.
+pub const COLUMNS: usize = 10;
+
+/// The number of symbols on the board.
+pub const SYMBOLS: usize = 3 * COLUMNS - 2;
+
+/// The full stop symbol.
+pub const FULL_STOP: char = '.';
+
+/// The figure sign, announcing that the next digit stands for itself.
+pub const FIGURE_SIGN: char = '/';
+
+/// Error type for the straddling checkerboard.
+#[derive(Error, Debug, PartialEq)]
+pub enum CheckerboardError {
+    #[error("The board needs 28 distinct symbols")]
+    InvalidAlphabet,
+    #[error("The blank cells must be two different columns")]
+    InvalidBlanks,
+    #[error("The column header must be a permutation of the ten digits")]
+    InvalidHeader,
+    #[error("Character cannot be encoded on this board: {0}")]
+    UnsupportedChar(char),
+    #[error("Not a digit: {0}")]
+    InvalidDigit(char),
+    #[error("The digits end in the middle of a code")]
+    Truncated,
+}
+
+/// A straddling checkerboard.
+///
+/// With the `serde` feature, the board is serialized as its 28 symbols, the
+/// columns of its blank cells and its column digits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "CheckerboardRepr", into = "CheckerboardRepr")
+)]
+pub struct StraddlingCheckerboard {
+    /// The digit labelling each column.
+    header: [u8; COLUMNS],
+    /// The symbols, row by row. The blank cells of the top row are `None`.
+    cells: [[Option<char>; COLUMNS]; 3],
+    /// The columns of the blank cells, which label the second and third rows.
+    blanks: [usize; 2],
+}
+
+impl StraddlingCheckerboard {
+    /// Creates a board from its symbols and the position of its blank cells.
+    ///
+    /// # Arguments
+    ///
+    /// * `alphabet` - The 28 symbols of the board, in order: the eight symbols of
+    ///   the top row, then the second row and the third row.
+    /// * `blanks` - The columns (0 to 9) of the two blank cells of the top row.
+    ///
+    /// # Returns
+    ///
+    /// The board, with the columns labelled 0 to 9, or an error if the symbols or blanks are invalid.
+    pub fn new(alphabet: &str, blanks: [usize; 2]) -> Result<Self, CheckerboardError> {
+        let mut symbols: Vec<char> = alphabet.chars().map(|c| c.to_ascii_uppercase()).collect();
+        let len = symbols.len();
+        symbols.sort_unstable();
+        symbols.dedup();
+        if len != SYMBOLS
+            || symbols.len() != SYMBOLS
+            || alphabet.chars().any(|c| c.is_ascii_digit())
+        {
+            return Err(CheckerboardError::InvalidAlphabet);
+        }
+        if blanks[0] == blanks[1] || blanks.iter().any(|&b| b >= COLUMNS) {
+            return Err(CheckerboardError::InvalidBlanks);
+        }
+
+        let mut symbols = alphabet.chars().map(|c| c.to_ascii_uppercase());
+        let mut cells = [[None; COLUMNS]; 3];
+        for (row, cells) in cells.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().enumerate() {
+                if row > 0 || !blanks.contains(&col) {
+                    *cell = symbols.next();
+                }
+            }
+        }
+
+        Ok(StraddlingCheckerboard {
+            header: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+            cells,
+            blanks,
+        })
+    }
+
+    /// Creates a board filled with a keyed alphabet, followed by the full stop and the figure sign.
+    ///
+    /// # Arguments
+    ///
+    /// * `keyword` - The keyword of the alphabet.
+    /// * `blanks` - The columns (0 to 9) of the two blank cells of the top row.
+    pub fn keyed(keyword: &str, blanks: [usize; 2]) -> Result<Self, CheckerboardError> {
+        let alphabet = format!(
+            "{}{}{}",
+            KeyedAlphabet::new(keyword),
+            FULL_STOP,
+            FIGURE_SIGN
+        );
+        Self::new(&alphabet, blanks)
+    }
+
+    /// Labels the columns with the given digits instead of 0 to 9.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The digit of each column, a permutation of 0 to 9.
+    pub fn with_header(mut self, header: [u8; COLUMNS]) -> Result<Self, CheckerboardError> {
+        let mut seen = [false; COLUMNS];
+        for &digit in &header {
+            if digit as usize >= COLUMNS || core::mem::replace(&mut seen[digit as usize], true) {
+                return Err(CheckerboardError::InvalidHeader);
+            }
+        }
+        self.header = header;
+        Ok(self)
+    }
+
+    /// Returns the code of a symbol: its row label (if any) followed by its column digit.
+    fn code(&self, symbol: char) -> Option<String> {
+        self.cells.iter().enumerate().find_map(|(row, cells)| {
+            let col = cells.iter().position(|&cell| cell == Some(symbol))?;
+            let digit = (self.header[col] + b'0') as char;
+            Some(match row {
+                0 => digit.to_string(),
+                _ => format!("{}{}", self.header[self.blanks[row - 1]], digit),
+            })
+        })
+    }
+
+    /// Encodes a text into digits.
+    ///
+    /// Letters are encoded in either case, whitespace is ignored, and digits are
+    /// encoded as the figure sign followed by the digit.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to be encoded.
+    ///
+    /// # Returns
+    ///
+    /// The digits, or an error if a character is not on the board.
+    pub fn encode(&self, text: &str) -> Result<String, CheckerboardError> {
+        let mut digits = String::with_capacity(text.len() * 2);
+        for c in text.chars().filter(|c| !c.is_whitespace()) {
+            digits.push_str(&self.encode_char(c)?);
+        }
+        Ok(digits)
+    }
+
+    /// Encodes a text into digits, recording every step.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to be encoded.
+    ///
+    /// # Returns
+    ///
+    /// The digits, as [`StraddlingCheckerboard::encode`], and one step per
+    /// character naming how it was encoded: `single` for the letters of the top
+    /// row, `double` for the other rows and `figure` for the digits.
+    pub fn encode_traced(&self, text: &str) -> Result<(String, Vec<Step>), CheckerboardError> {
+        let steps = text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| {
+                let code = self.encode_char(c)?;
+                let rule = match code.len() {
+                    _ if c.is_ascii_digit() => "figure",
+                    1 => "single",
+                    _ => "double",
+                };
+                Ok(Step::new(c, code, rule))
+            })
+            .collect::<Result<Vec<Step>, CheckerboardError>>()?;
+        let digits = steps.iter().map(|step| step.output.as_str()).collect();
+        Ok((digits, steps))
+    }
+
+    /// Encodes a character: a letter or symbol as its code, a digit as the figure sign and the digit.
+    fn encode_char(&self, c: char) -> Result<String, CheckerboardError> {
+        if c.is_ascii_digit() {
+            let mut figure = self
+                .code(FIGURE_SIGN)
+                .ok_or(CheckerboardError::UnsupportedChar(c))?;
+            figure.push(c);
+            Ok(figure)
+        } else {
+            self.code(c.to_ascii_uppercase())
+                .ok_or(CheckerboardError::UnsupportedChar(c))
+        }
+    }
+
+    /// Decodes digits into a text.
+    ///
+    /// # Arguments
+    ///
+    /// * `digits` - The digits to be decoded. Whitespace is ignored.
+    ///
+    /// # Returns
+    ///
+    /// The decoded text, or an error if a character is not a digit or the digits end in the middle of a code.
+    pub fn decode(&self, digits: &str) -> Result<String, CheckerboardError> {
+        let mut digits = digits.chars().filter(|c| !c.is_whitespace()).map(|c| {
+            c.to_digit(10)
+                .map(|d| d as u8)
+                .ok_or(CheckerboardError::InvalidDigit(c))
+        });
+        let mut next_digit = || digits.next().transpose();
+        let mut text = String::new();
+
+        while let Some(digit) = next_digit()? {
+            let col = self.column(digit);
+            let symbol = if self.blanks.contains(&col) {
+                let row = if self.blanks[0] == col { 1 } else { 2 };
+                let digit = next_digit()?.ok_or(CheckerboardError::Truncated)?;
+                self.cells[row][self.column(digit)]
+            } else {
+                self.cells[0][col]
+            };
+
+            match symbol {
+                Some(FIGURE_SIGN) => {
+                    let digit = next_digit()?.ok_or(CheckerboardError::Truncated)?;
+                    text.push((digit + b'0') as char);
+                }
+                Some(symbol) => text.push(symbol),
+                None => unreachable!("blank cells are row labels"),
+            }
+        }
+
+        Ok(text)
+    }
+
+    /// Returns the column labelled with the given digit.
+    fn column(&self, digit: u8) -> usize {
+        self.header
+            .iter()
+            .position(|&d| d == digit)
+            .unwrap_or_default()
+    }
+}
+
+/// The serialized form of a [`StraddlingCheckerboard`], checked when it is loaded.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CheckerboardRepr {
+    alphabet: String,
+    blanks: [usize; 2],
+    header: [u8; COLUMNS],
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<CheckerboardRepr> for StraddlingCheckerboard {
+    type Error = CheckerboardError;
+
+    fn try_from(repr: CheckerboardRepr) -> Result<Self, Self::Error> {
+        StraddlingCheckerboard::new(&repr.alphabet, repr.blanks)?.with_header(repr.header)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<StraddlingCheckerboard> for CheckerboardRepr {
+    fn from(board: StraddlingCheckerboard) -> Self {
+        CheckerboardRepr {
+            alphabet: board.cells.iter().flatten().flatten().collect(),
+            blanks: board.blanks,
+            header: board.header,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The classic board with "ET AON RIS" on the top row.
+    fn board() -> StraddlingCheckerboard {
+        StraddlingCheckerboard::new("ETAONRISBCDFGHJKLMPQ/UVWXYZ.", [2, 6]).unwrap()
+    }
+
+    #[test]
+    fn test_straddling_checkerboard_encode() {
+        let board = board();
+        assert_eq!(board.encode("E").unwrap(), "0");
+        assert_eq!(board.encode("B").unwrap(), "20");
+        assert_eq!(board.encode("U").unwrap(), "63");
+        assert_eq!(board.encode("Attack at dawn").unwrap(), "3113212731223655");
+        assert_eq!(board.encode("5").unwrap(), "625");
+        assert_eq!(
+            board.encode("A?"),
+            Err(CheckerboardError::UnsupportedChar('?'))
+        );
+    }
+
+    #[test]
+    fn test_straddling_checkerboard_encode_traced() {
+        let (digits, steps) = board().encode_traced("Eb 5").unwrap();
+        assert_eq!(digits, "020625");
+        assert_eq!(
+            steps,
+            vec![
+                Step::new('E', "0", "single"),
+                Step::new('b', "20", "double"),
+                Step::new('5', "625", "figure"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_straddling_checkerboard_round_trip() {
+        let board = StraddlingCheckerboard::keyed("SUBWAY", [3, 7])
+            .unwrap()
+            .with_header([3, 9, 0, 1, 7, 2, 8, 6, 4, 5])
+            .unwrap();
+        let digits = board.encode("MEET AT 1900. BRING 2 MAPS").unwrap();
+        assert_eq!(board.decode(&digits).unwrap(), "MEETAT1900.BRING2MAPS");
+    }
+
+    #[test]
+    fn test_straddling_checkerboard_decode_errors() {
+        let board = board();
+        assert_eq!(
+            board.decode("31x"),
+            Err(CheckerboardError::InvalidDigit('x'))
+        );
+        assert_eq!(board.decode("312"), Err(CheckerboardError::Truncated));
+        assert_eq!(board.decode("62"), Err(CheckerboardError::Truncated));
+    }
+
+    #[test]
+    fn test_straddling_checkerboard_invalid() {
+        assert_eq!(
+            StraddlingCheckerboard::new("ABC", [2, 6]),
+            Err(CheckerboardError::InvalidAlphabet)
+        );
+        assert_eq!(
+            StraddlingCheckerboard::keyed("", [2, 2]),
+            Err(CheckerboardError::InvalidBlanks)
+        );
+        assert_eq!(
+            board().with_header([0; 10]),
+            Err(CheckerboardError::InvalidHeader)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_straddling_checkerboard_serde() {
+        let board = board().with_header([3, 1, 4, 5, 9, 2, 6, 8, 7, 0]).unwrap();
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(
+            json,
+            r#"{"alphabet":"ETAONRISBCDFGHJKLMPQ/UVWXYZ.","blanks":[2,6],"header":[3,1,4,5,9,2,6,8,7,0]}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<StraddlingCheckerboard>(&json).unwrap(),
+            board
+        );
+
+        let invalid = json.replace("[2,6]", "[2,2]");
+        assert!(serde_json::from_str::<StraddlingCheckerboard>(&invalid).is_err());
+    }
+}
@@ -0,0 +1,676 @@
+//! Keystream generators of historical stream ciphers.
+//!
+//! A stream cipher works like the [`crate::otp`]: the data is XORed with a
+//! keystream. Instead of a truly random pad as long as the message, the
+//! keystream is expanded from a short key by a deterministic generator, which
+//! makes the cipher practical, but only as secure as the generator.
+//!
+//! Three generators are provided, all implementing [`Keystream`]:
+//!
+//! * [`Rc4`], the byte-oriented generator designed by Ron Rivest in 1987, with
+//!   the optional dropping of its biased first bytes (RC4-drop\[N\]);
+//! * [`Lfsr`], a linear-feedback shift register in its Fibonacci or Galois form;
+//! * [`A51`], the generator of GSM telephony, combining three irregularly
+//!   clocked shift registers.
+//!
+//! Bit-oriented generators pack their bits into bytes most significant bit first.
+//!
+//! LFSR keystreams can be recovered from known plaintext, see [`attack`].
+
+pub mod attack;
+
+use crate::otp;
+use crate::prelude::*;
+use thiserror::Error;
+
+/// Error type for the stream ciphers.
+#[derive(Error, Debug, PartialEq)]
+pub enum StreamError {
+    #[error("RC4 keys must be 1 to 256 bytes long, got {0}")]
+    InvalidKeyLength(usize),
+    #[error("LFSR taps must be distinct and between 1 and 64")]
+    InvalidTaps,
+    #[error("The LFSR state must be non-zero and fit in the register")]
+    InvalidState,
+    #[error("The A5/1 frame number must fit in 22 bits, got {0:#x}")]
+    InvalidFrame(u32),
+    #[error("The RC4 state must be a permutation of the 256 bytes")]
+    InvalidPermutation,
+    #[error("The A5/1 registers must fit in 19, 22 and 23 bits")]
+    InvalidRegisters,
+}
+
+/// A generator of keystream bytes.
+pub trait Keystream {
+    /// Generates the next byte of the keystream.
+    fn next_byte(&mut self) -> u8;
+
+    /// Generates a number of keystream bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - The number of bytes to generate.
+    fn keystream(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_byte()).collect()
+    }
+
+    /// Encrypts or decrypts data by XORing it with the next bytes of the keystream.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The plaintext (for encryption) or ciphertext (for decryption).
+    ///
+    /// # Returns
+    ///
+    /// The ciphertext (for encryption) or plaintext (for decryption).
+    fn encipher(&mut self, data: &[u8]) -> Vec<u8> {
+        let key = self.keystream(data.len());
+        otp::xor(data, &key)
+    }
+}
+
+/// Packs eight bits into a byte, the first bit being the most significant.
+fn pack_bits(mut next_bit: impl FnMut() -> u8) -> u8 {
+    (0..8).fold(0, |byte, _| (byte << 1) | next_bit())
+}
+
+/// The RC4 keystream generator.
+///
+/// With the `serde` feature, the generator is serialized as its internal state,
+/// so that it can be resumed where it stopped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "Rc4Repr", into = "Rc4Repr")
+)]
+pub struct Rc4 {
+    state: [u8; 256],
+    i: u8,
+    j: u8,
+}
+
+impl Rc4 {
+    /// Creates a generator with the key scheduling algorithm of RC4.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key, from 1 to 256 bytes long.
+    ///
+    /// # Returns
+    ///
+    /// The generator, or an error if the key length is invalid.
+    pub fn new(key: &[u8]) -> Result<Self, StreamError> {
+        if key.is_empty() || key.len() > 256 {
+            return Err(StreamError::InvalidKeyLength(key.len()));
+        }
+
+        let mut state = [0u8; 256];
+        for (idx, s) in state.iter_mut().enumerate() {
+            *s = idx as u8;
+        }
+        let mut j = 0u8;
+        for idx in 0..256 {
+            j = j
+                .wrapping_add(state[idx])
+                .wrapping_add(key[idx % key.len()]);
+            state.swap(idx, j as usize);
+        }
+
+        Ok(Rc4 { state, i: 0, j: 0 })
+    }
+
+    /// Creates a generator that discards the first bytes of its keystream (RC4-drop\[N\]).
+    ///
+    /// The first bytes of the RC4 keystream are biased and leak information
+    /// about the key, which was used to break WEP. Dropping them (768 or 3072
+    /// bytes are common choices) avoids these biases.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key, from 1 to 256 bytes long.
+    /// * `drop` - The number of keystream bytes to discard.
+    pub fn with_drop(key: &[u8], drop: usize) -> Result<Self, StreamError> {
+        let mut rc4 = Self::new(key)?;
+        for _ in 0..drop {
+            rc4.next_byte();
+        }
+        Ok(rc4)
+    }
+}
+
+impl Keystream for Rc4 {
+    fn next_byte(&mut self) -> u8 {
+        self.i = self.i.wrapping_add(1);
+        self.j = self.j.wrapping_add(self.state[self.i as usize]);
+        self.state.swap(self.i as usize, self.j as usize);
+        let idx = self.state[self.i as usize].wrapping_add(self.state[self.j as usize]);
+        self.state[idx as usize]
+    }
+}
+
+/// The serialized form of an [`Rc4`] generator, checked when it is loaded.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Rc4Repr {
+    state: Vec<u8>,
+    i: u8,
+    j: u8,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<Rc4Repr> for Rc4 {
+    type Error = StreamError;
+
+    fn try_from(repr: Rc4Repr) -> Result<Self, Self::Error> {
+        let mut sorted = repr.state.clone();
+        sorted.sort_unstable();
+        if !sorted.iter().copied().eq(0..=255) {
+            return Err(StreamError::InvalidPermutation);
+        }
+        Ok(Rc4 {
+            state: repr
+                .state
+                .try_into()
+                .map_err(|_| StreamError::InvalidPermutation)?,
+            i: repr.i,
+            j: repr.j,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Rc4> for Rc4Repr {
+    fn from(rc4: Rc4) -> Self {
+        Rc4Repr {
+            state: rc4.state.to_vec(),
+            i: rc4.i,
+            j: rc4.j,
+        }
+    }
+}
+
+/// The two forms of a linear-feedback shift register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LfsrKind {
+    /// The feedback bit is the XOR of the tapped bits, and is shifted in at the top.
+    Fibonacci,
+    /// The output bit is XORed into the tapped bits as the register shifts.
+    Galois,
+}
+
+/// A binary linear-feedback shift register.
+///
+/// The register is described by its feedback polynomial, given as the
+/// exponents of its non-constant terms: the taps `[16, 14, 13, 11]` stand for
+/// `x^16 + x^14 + x^13 + x^11 + 1`. The largest tap is the width of the
+/// register. The register shifts right, and outputs its lowest bit.
+///
+/// With the same taps, both forms generate sequences with the same linear
+/// recurrence, and a primitive polynomial gives the maximal period `2^width - 1`.
+/// In the Fibonacci form, the first `width` output bits are the initial state,
+/// from the lowest bit up.
+///
+/// With the `serde` feature, the register is serialized as its form, its taps
+/// and its current state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "LfsrRepr", into = "LfsrRepr")
+)]
+pub struct Lfsr {
+    kind: LfsrKind,
+    taps: Vec<u32>,
+    width: u32,
+    mask: u64,
+    state: u64,
+}
+
+impl Lfsr {
+    /// Creates a register in the Fibonacci form.
+    ///
+    /// # Arguments
+    ///
+    /// * `taps` - The exponents of the feedback polynomial.
+    /// * `state` - The initial state of the register.
+    ///
+    /// # Returns
+    ///
+    /// The register, or an error if the taps or the state are invalid.
+    pub fn fibonacci(taps: &[u32], state: u64) -> Result<Self, StreamError> {
+        Self::new(LfsrKind::Fibonacci, taps, state)
+    }
+
+    /// Creates a register in the Galois form.
+    ///
+    /// # Arguments
+    ///
+    /// * `taps` - The exponents of the feedback polynomial.
+    /// * `state` - The initial state of the register.
+    ///
+    /// # Returns
+    ///
+    /// The register, or an error if the taps or the state are invalid.
+    pub fn galois(taps: &[u32], state: u64) -> Result<Self, StreamError> {
+        Self::new(LfsrKind::Galois, taps, state)
+    }
+
+    fn new(kind: LfsrKind, taps: &[u32], state: u64) -> Result<Self, StreamError> {
+        let mut sorted = taps.to_vec();
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+        sorted.dedup();
+        if sorted.len() != taps.len() || sorted.iter().any(|&tap| !(1..=64).contains(&tap)) {
+            return Err(StreamError::InvalidTaps);
+        }
+
+        let width = sorted.first().copied().ok_or(StreamError::InvalidTaps)?;
+        if state == 0 || (width < 64 && state >> width != 0) {
+            return Err(StreamError::InvalidState);
+        }
+
+        // The bits XORed into the feedback (Fibonacci) or toggled by the output (Galois).
+        let mask = sorted
+            .iter()
+            .map(|&tap| match kind {
+                LfsrKind::Fibonacci => 1 << (width - tap),
+                LfsrKind::Galois => 1 << (tap - 1),
+            })
+            .fold(0, |mask, bit| mask | bit);
+
+        Ok(Lfsr {
+            kind,
+            taps: sorted,
+            width,
+            mask,
+            state,
+        })
+    }
+
+    /// Returns the form of the register.
+    pub fn kind(&self) -> LfsrKind {
+        self.kind
+    }
+
+    /// Returns the exponents of the feedback polynomial, in decreasing order.
+    pub fn taps(&self) -> &[u32] {
+        &self.taps
+    }
+
+    /// Returns the number of bits of the register.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the current state of the register.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// Shifts the register once.
+    ///
+    /// # Returns
+    ///
+    /// The output bit, 0 or 1.
+    pub fn next_bit(&mut self) -> u8 {
+        let output = (self.state & 1) as u8;
+        match self.kind {
+            LfsrKind::Fibonacci => {
+                let feedback = (self.state & self.mask).count_ones() as u64 & 1;
+                self.state = (self.state >> 1) | (feedback << (self.width - 1));
+            }
+            LfsrKind::Galois => {
+                self.state >>= 1;
+                if output == 1 {
+                    self.state ^= self.mask;
+                }
+            }
+        }
+        output
+    }
+}
+
+impl Keystream for Lfsr {
+    fn next_byte(&mut self) -> u8 {
+        pack_bits(|| self.next_bit())
+    }
+}
+
+/// The serialized form of an [`Lfsr`], checked when it is loaded.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LfsrRepr {
+    kind: LfsrKind,
+    taps: Vec<u32>,
+    state: u64,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<LfsrRepr> for Lfsr {
+    type Error = StreamError;
+
+    fn try_from(repr: LfsrRepr) -> Result<Self, Self::Error> {
+        Lfsr::new(repr.kind, &repr.taps, repr.state)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Lfsr> for LfsrRepr {
+    fn from(lfsr: Lfsr) -> Self {
+        LfsrRepr {
+            kind: lfsr.kind,
+            taps: lfsr.taps,
+            state: lfsr.state,
+        }
+    }
+}
+
+/// The number of keystream bits of each direction of an A5/1 frame.
+pub const A51_BURST_BITS: usize = 114;
+
+/// The width, feedback taps and clocking bit of each A5/1 register.
+const A51_REGISTERS: [(u32, u32, u32); 3] =
+    [(19, 0x07_2000, 8), (22, 0x30_0000, 10), (23, 0x70_0080, 10)];
+
+/// The A5/1 keystream generator of GSM.
+///
+/// Three shift registers of 19, 22 and 23 bits are clocked irregularly: each
+/// register has a clocking bit, and only the registers whose clocking bit
+/// agrees with the majority of the three are clocked. The output is the XOR of
+/// the top bits of the registers.
+///
+/// With the `serde` feature, the generator is serialized as the contents of its registers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "A51Repr")
+)]
+pub struct A51 {
+    registers: [u32; 3],
+}
+
+impl A51 {
+    /// Creates a generator for a frame of a conversation.
+    ///
+    /// The 64 bits of the session key (the lowest bit of each byte first), then
+    /// the 22 bits of the frame number (lowest first), are XORed into the
+    /// registers, all clocked regularly. The registers are then clocked 100
+    /// times with the majority rule, discarding the output.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The 64-bit session key.
+    /// * `frame` - The 22-bit frame number.
+    ///
+    /// # Returns
+    ///
+    /// The generator, or an error if the frame number does not fit in 22 bits.
+    pub fn new(key: [u8; 8], frame: u32) -> Result<Self, StreamError> {
+        if frame >> 22 != 0 {
+            return Err(StreamError::InvalidFrame(frame));
+        }
+
+        let mut a51 = A51 { registers: [0; 3] };
+        let key_bits = (0..64).map(|idx| (key[idx / 8] >> (idx % 8)) & 1);
+        let frame_bits = (0..22).map(|idx| ((frame >> idx) & 1) as u8);
+        for bit in key_bits.chain(frame_bits) {
+            a51.clock_all();
+            for register in a51.registers.iter_mut() {
+                *register ^= bit as u32;
+            }
+        }
+        for _ in 0..100 {
+            a51.clock();
+        }
+
+        Ok(a51)
+    }
+
+    /// Generates the next keystream bit.
+    ///
+    /// # Returns
+    ///
+    /// The output bit, 0 or 1.
+    pub fn next_bit(&mut self) -> u8 {
+        self.clock();
+        self.registers
+            .iter()
+            .zip(A51_REGISTERS)
+            .fold(0, |bit, (&register, (width, _, _))| {
+                bit ^ (register >> (width - 1)) as u8 & 1
+            })
+    }
+
+    /// Generates the keystreams of both directions of a frame.
+    ///
+    /// # Returns
+    ///
+    /// The 114 bits encrypting the uplink (A to B), then the 114 bits of the
+    /// downlink (B to A), each packed in 15 bytes whose last 6 bits are zero.
+    pub fn bursts(&mut self) -> (Vec<u8>, Vec<u8>) {
+        let mut burst = || {
+            let mut bytes = vec![0u8; A51_BURST_BITS.div_ceil(8)];
+            for idx in 0..A51_BURST_BITS {
+                bytes[idx / 8] |= self.next_bit() << (7 - idx % 8);
+            }
+            bytes
+        };
+        let a_to_b = burst();
+        (a_to_b, burst())
+    }
+
+    /// Clocks the registers whose clocking bit agrees with the majority.
+    fn clock(&mut self) {
+        let bits: [u32; 3] = core::array::from_fn(|idx| {
+            let (_, _, clocking) = A51_REGISTERS[idx];
+            (self.registers[idx] >> clocking) & 1
+        });
+        let majority = u32::from(bits.iter().sum::<u32>() >= 2);
+        for (idx, &bit) in bits.iter().enumerate() {
+            if bit == majority {
+                self.clock_one(idx);
+            }
+        }
+    }
+
+    fn clock_all(&mut self) {
+        for idx in 0..3 {
+            self.clock_one(idx);
+        }
+    }
+
+    /// Shifts one register left, feeding back the parity of its taps.
+    fn clock_one(&mut self, idx: usize) {
+        let (width, taps, _) = A51_REGISTERS[idx];
+        let register = &mut self.registers[idx];
+        let feedback = (*register & taps).count_ones() & 1;
+        *register = ((*register << 1) & ((1 << width) - 1)) | feedback;
+    }
+}
+
+impl Keystream for A51 {
+    fn next_byte(&mut self) -> u8 {
+        pack_bits(|| self.next_bit())
+    }
+}
+
+/// The serialized form of an [`A51`] generator, checked when it is loaded.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct A51Repr {
+    registers: [u32; 3],
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<A51Repr> for A51 {
+    type Error = StreamError;
+
+    fn try_from(repr: A51Repr) -> Result<Self, Self::Error> {
+        let fits = repr
+            .registers
+            .iter()
+            .zip(A51_REGISTERS)
+            .all(|(&register, (width, _, _))| register >> width == 0);
+        if !fits {
+            return Err(StreamError::InvalidRegisters);
+        }
+        Ok(A51 {
+            registers: repr.registers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    #[test]
+    fn test_rc4_vectors() {
+        for (key, plaintext, ciphertext) in [
+            ("Key", "Plaintext", "BBF316E8D940AF0AD3"),
+            ("Wiki", "pedia", "1021BF0420"),
+            ("Secret", "Attack at dawn", "45A01F645FC35B383552544B9BF5"),
+        ] {
+            let mut rc4 = Rc4::new(key.as_bytes()).unwrap();
+            assert_eq!(hex(&rc4.encipher(plaintext.as_bytes())), ciphertext);
+        }
+
+        let mut rc4 = Rc4::new(&[1, 2, 3, 4, 5]).unwrap();
+        assert_eq!(hex(&rc4.keystream(16)), "B2396305F03DC027CCC3524A0A1118A8");
+    }
+
+    #[test]
+    fn test_rc4_drop() {
+        let keystream = Rc4::new(b"Secret").unwrap().keystream(800);
+        let mut rc4 = Rc4::with_drop(b"Secret", 768).unwrap();
+        assert_eq!(rc4.keystream(32), &keystream[768..]);
+
+        let ciphertext = rc4.encipher(b"Attack at dawn");
+        let mut rc4 = Rc4::with_drop(b"Secret", 800).unwrap();
+        assert_eq!(rc4.encipher(&ciphertext), b"Attack at dawn");
+
+        assert_eq!(Rc4::new(b""), Err(StreamError::InvalidKeyLength(0)));
+        assert_eq!(Rc4::new(&[0; 257]), Err(StreamError::InvalidKeyLength(257)));
+    }
+
+    #[test]
+    fn test_lfsr_fibonacci() {
+        let mut lfsr = Lfsr::fibonacci(&[16, 14, 13, 11], 0xACE1).unwrap();
+        assert_eq!(lfsr.next_bit(), 1);
+        assert_eq!(lfsr.state(), 0x5670);
+
+        // x^16 + x^14 + x^13 + x^11 + 1 is primitive: the period is maximal.
+        let mut period = 1;
+        while lfsr.state() != 0xACE1 {
+            lfsr.next_bit();
+            period += 1;
+        }
+        assert_eq!(period, 65535);
+
+        // The first output bits are the initial state.
+        let mut lfsr = Lfsr::fibonacci(&[16, 14, 13, 11], 0xACE1).unwrap();
+        assert_eq!(lfsr.keystream(2), vec![0x87, 0x35]);
+    }
+
+    #[test]
+    fn test_lfsr_galois() {
+        let mut lfsr = Lfsr::galois(&[16, 14, 13, 11], 0xACE1).unwrap();
+        assert_eq!(lfsr.next_bit(), 1);
+        assert_eq!(lfsr.state(), 0xE270);
+
+        let mut period = 1;
+        while lfsr.state() != 0xACE1 {
+            lfsr.next_bit();
+            period += 1;
+        }
+        assert_eq!(period, 65535);
+
+        let ciphertext = Lfsr::galois(&[16, 14, 13, 11], 0xACE1)
+            .unwrap()
+            .encipher(b"Hello");
+        let mut lfsr = Lfsr::galois(&[11, 13, 14, 16], 0xACE1).unwrap();
+        assert_eq!(lfsr.taps(), &[16, 14, 13, 11]);
+        assert_eq!(lfsr.encipher(&ciphertext), b"Hello");
+    }
+
+    #[test]
+    fn test_lfsr_invalid() {
+        assert_eq!(Lfsr::fibonacci(&[], 1), Err(StreamError::InvalidTaps));
+        assert_eq!(Lfsr::fibonacci(&[4, 4], 1), Err(StreamError::InvalidTaps));
+        assert_eq!(Lfsr::galois(&[65, 1], 1), Err(StreamError::InvalidTaps));
+        assert_eq!(Lfsr::fibonacci(&[4, 3], 0), Err(StreamError::InvalidState));
+        assert_eq!(
+            Lfsr::fibonacci(&[4, 3], 0x10),
+            Err(StreamError::InvalidState)
+        );
+        assert!(Lfsr::galois(&[64, 63, 61, 60], u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_a51_vectors() {
+        let mut a51 = A51::new(0x1223456789ABCDEFu64.to_be_bytes(), 0x134).unwrap();
+        let (a_to_b, b_to_a) = a51.bursts();
+        assert_eq!(hex(&a_to_b), "534EAA582FE8151AB6E1855A728C00");
+        assert_eq!(hex(&b_to_a), "24FD35A35D5FB6526D32F906DF1AC0");
+
+        assert_eq!(
+            A51::new([0; 8], 1 << 22),
+            Err(StreamError::InvalidFrame(1 << 22))
+        );
+    }
+
+    #[test]
+    fn test_a51_keystream() {
+        let key = 0x1223456789ABCDEFu64.to_be_bytes();
+        let keystream = A51::new(key, 0x134).unwrap().keystream(14);
+        let (a_to_b, _) = A51::new(key, 0x134).unwrap().bursts();
+        assert_eq!(keystream, &a_to_b[..14]);
+
+        let ciphertext = A51::new(key, 7).unwrap().encipher(b"Hello, GSM");
+        assert_eq!(
+            A51::new(key, 7).unwrap().encipher(&ciphertext),
+            b"Hello, GSM"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_stream_serde() {
+        // Every generator resumes where it stopped.
+        let mut rc4 = Rc4::new(b"Key").unwrap();
+        rc4.keystream(10);
+        let mut loaded: Rc4 = serde_json::from_str(&serde_json::to_string(&rc4).unwrap()).unwrap();
+        assert_eq!(loaded.keystream(10), rc4.keystream(10));
+        assert!(serde_json::from_str::<Rc4>(r#"{"state":[0,1],"i":0,"j":0}"#).is_err());
+
+        let mut lfsr = Lfsr::galois(&[16, 14, 13, 11], 0xACE1).unwrap();
+        lfsr.keystream(3);
+        let json = serde_json::to_string(&lfsr).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"kind":"Galois","taps":[16,14,13,11],"state":{}}}"#,
+                lfsr.state()
+            )
+        );
+        let mut loaded: Lfsr = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.keystream(10), lfsr.keystream(10));
+        assert!(
+            serde_json::from_str::<Lfsr>(r#"{"kind":"Galois","taps":[4,3],"state":0}"#).is_err()
+        );
+
+        let mut a51 = A51::new(0x1223456789ABCDEFu64.to_be_bytes(), 0x134).unwrap();
+        let mut loaded: A51 = serde_json::from_str(&serde_json::to_string(&a51).unwrap()).unwrap();
+        assert_eq!(loaded.bursts(), a51.bursts());
+        assert!(serde_json::from_str::<A51>(r#"{"registers":[524288,0,0]}"#).is_err());
+    }
+}
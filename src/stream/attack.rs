@@ -0,0 +1,276 @@
+//! Known-plaintext attack on LFSR keystreams.
+//!
+//! A keystream XORed onto the data, as in [`crate::otp::encipher`], is revealed
+//! wherever the plaintext is known: XORing the known plaintext with the
+//! ciphertext gives back the keystream. When the keystream is produced by a
+//! linear-feedback shift register, the **Berlekamp–Massey** algorithm finds the
+//! shortest LFSR generating a sequence of bits, and `2n` bits are enough to
+//! recover a register of `n` bits. The register can then be run forwards and
+//! backwards to regenerate the whole keystream and decrypt the full message.
+//!
+//! The recovered register is in the Fibonacci form, whatever the form of the
+//! original one: both forms with the same taps generate the same sequences.
+
+use super::{Lfsr, StreamError};
+use crate::otp;
+use crate::prelude::*;
+use thiserror::Error;
+
+/// Error type for the attack on LFSR keystreams.
+#[derive(Error, Debug, PartialEq)]
+pub enum AttackError {
+    #[error("The known plaintext does not fit in the ciphertext at this offset")]
+    InvalidOffset,
+    #[error("The keystream is not generated by a non-singular LFSR")]
+    Degenerate,
+    #[error("A linear complexity of {0} needs at least {bits} keystream bits", bits = 2 * .0)]
+    InsufficientKeystream(usize),
+    #[error("The shortest LFSR has {0} bits, more than the supported 64")]
+    TooLong(usize),
+    #[error(transparent)]
+    Stream(#[from] StreamError),
+}
+
+/// The result of a known-plaintext attack.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recovery {
+    /// The register, in the state generating the keystream of the whole message.
+    pub lfsr: Lfsr,
+    /// The decrypted message.
+    pub plaintext: Vec<u8>,
+}
+
+/// Finds the shortest LFSR generating a sequence of bits with the Berlekamp–Massey algorithm.
+///
+/// # Arguments
+///
+/// * `bits` - The sequence, one bit (0 or 1) per element.
+///
+/// # Returns
+///
+/// The linear complexity `L` of the sequence (the length of the shortest LFSR),
+/// and its connection polynomial `c`, with `c[0] = 1`, such that
+/// `bits[k] = c[1] bits[k - 1] ^ ... ^ c[L] bits[k - L]` for every `k >= L`.
+pub fn berlekamp_massey(bits: &[u8]) -> (usize, Vec<u8>) {
+    let n = bits.len();
+    let mut connection = vec![0u8; n + 1];
+    connection[0] = 1;
+    let mut previous = connection.clone();
+    let mut complexity = 0;
+    // The number of steps since the last change of complexity.
+    let mut shift = 1;
+
+    for idx in 0..n {
+        let discrepancy = (1..=complexity).fold(bits[idx] & 1, |d, j| {
+            d ^ (connection[j] & bits[idx - j] & 1)
+        });
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+
+        let current = connection.clone();
+        for (j, &c) in previous.iter().enumerate().take(n + 1 - shift) {
+            connection[j + shift] ^= c;
+        }
+        if 2 * complexity <= idx {
+            complexity = idx + 1 - complexity;
+            previous = current;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+    }
+
+    connection.truncate(complexity + 1);
+    (complexity, connection)
+}
+
+/// Recovers the LFSR generating a fragment of keystream.
+///
+/// The fragment must contain at least twice as many bits as the register. A
+/// shorter fragment may lead to a wrong, shorter register, which cannot be
+/// detected.
+///
+/// # Arguments
+///
+/// * `bits` - The keystream fragment, one bit (0 or 1) per element.
+///
+/// # Returns
+///
+/// The register, in the state generating the fragment from its first bit, or
+/// an error if the fragment is too short or not generated by a usable LFSR.
+pub fn recover_lfsr(bits: &[u8]) -> Result<Lfsr, AttackError> {
+    let (complexity, taps) = recurrence(bits)?;
+    let state = (0..complexity).fold(0, |state, k| state | ((bits[k] & 1) as u64) << k);
+    Ok(Lfsr::fibonacci(&taps, state)?)
+}
+
+/// Decrypts a message encrypted with an LFSR keystream, given a piece of its plaintext.
+///
+/// The keystream bytes are assumed to be packed most significant bit first,
+/// as by [`super::Keystream::keystream`].
+///
+/// # Arguments
+///
+/// * `ciphertext` - The encrypted message.
+/// * `known` - A known piece of the plaintext.
+/// * `offset` - The position of the known plaintext in the message, in bytes.
+///
+/// # Returns
+///
+/// The recovered register and the decrypted message, or an error if the known
+/// plaintext does not fit or is too short to recover the register.
+pub fn known_plaintext(
+    ciphertext: &[u8],
+    known: &[u8],
+    offset: usize,
+) -> Result<Recovery, AttackError> {
+    let end = offset
+        .checked_add(known.len())
+        .filter(|&end| end <= ciphertext.len())
+        .ok_or(AttackError::InvalidOffset)?;
+
+    let fragment = unpack(&otp::xor(&ciphertext[offset..end], known));
+    let (complexity, taps) = recurrence(&fragment)?;
+
+    // Extend the fragment backwards and forwards to the whole message, using
+    // the recurrence bits[k] = XOR of bits[k - tap] (the largest tap is the complexity).
+    let start = offset * 8;
+    let mut keystream = vec![0u8; ciphertext.len() * 8];
+    keystream[start..start + fragment.len()].copy_from_slice(&fragment);
+    for k in (0..start).rev() {
+        keystream[k] = taps
+            .iter()
+            .filter(|&&tap| tap as usize != complexity)
+            .fold(keystream[k + complexity], |bit, &tap| {
+                bit ^ keystream[k + complexity - tap as usize]
+            });
+    }
+    for k in start + fragment.len()..keystream.len() {
+        keystream[k] = taps
+            .iter()
+            .fold(0, |bit, &tap| bit ^ keystream[k - tap as usize]);
+    }
+
+    Ok(Recovery {
+        lfsr: recover_lfsr(&keystream[..2 * complexity])?,
+        plaintext: otp::xor(ciphertext, &pack(&keystream)),
+    })
+}
+
+/// Runs Berlekamp–Massey and checks that the recurrence can be used as an LFSR.
+///
+/// # Returns
+///
+/// The linear complexity and the exponents of the connection polynomial (the taps).
+fn recurrence(bits: &[u8]) -> Result<(usize, Vec<u32>), AttackError> {
+    let (complexity, connection) = berlekamp_massey(bits);
+    if complexity > 64 {
+        return Err(AttackError::TooLong(complexity));
+    }
+    if 2 * complexity > bits.len() {
+        return Err(AttackError::InsufficientKeystream(complexity));
+    }
+    // The register must be non-zero, and the feedback must reach its last bit.
+    if complexity == 0 || connection[complexity] == 0 {
+        return Err(AttackError::Degenerate);
+    }
+
+    let taps = (1..=complexity)
+        .filter(|&j| connection[j] == 1)
+        .map(|j| j as u32)
+        .collect();
+    Ok((complexity, taps))
+}
+
+/// Splits bytes into bits, the most significant bit first.
+fn unpack(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|&byte| (0..8).rev().map(move |idx| (byte >> idx) & 1))
+        .collect()
+}
+
+/// Packs bits into bytes, the first bit being the most significant.
+fn pack(bits: &[u8]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|chunk| chunk.iter().fold(0, |byte, &bit| (byte << 1) | bit))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Keystream;
+    use super::*;
+
+    fn bits(lfsr: &mut Lfsr, len: usize) -> Vec<u8> {
+        (0..len).map(|_| lfsr.next_bit()).collect()
+    }
+
+    #[test]
+    fn test_berlekamp_massey() {
+        let mut lfsr = Lfsr::fibonacci(&[16, 14, 13, 11], 0xACE1).unwrap();
+        let (complexity, connection) = berlekamp_massey(&bits(&mut lfsr, 40));
+        assert_eq!(complexity, 16);
+        let taps: Vec<usize> = (1..=16).filter(|&j| connection[j] == 1).collect();
+        assert_eq!(taps, vec![11, 13, 14, 16]);
+
+        // A single 1 after zeros needs a register as long as the sequence.
+        assert_eq!(berlekamp_massey(&[0, 0, 0, 1]).0, 4);
+        assert_eq!(berlekamp_massey(&[0, 0, 0]), (0, vec![1]));
+    }
+
+    #[test]
+    fn test_recover_lfsr() {
+        let mut lfsr = Lfsr::fibonacci(&[16, 14, 13, 11], 0xACE1).unwrap();
+        let recovered = recover_lfsr(&bits(&mut lfsr, 32)).unwrap();
+        assert_eq!(recovered.taps(), &[16, 14, 13, 11]);
+        assert_eq!(recovered.state(), 0xACE1);
+
+        // The Galois form gives the same taps, with a Fibonacci state.
+        let mut galois = Lfsr::galois(&[32, 22, 2, 1], 0xDEADBEEF).unwrap();
+        let fragment = bits(&mut galois, 64);
+        let mut recovered = recover_lfsr(&fragment).unwrap();
+        assert_eq!(recovered.taps(), &[32, 22, 2, 1]);
+        assert_eq!(bits(&mut recovered, 64), fragment);
+        assert_eq!(bits(&mut recovered, 1000), bits(&mut galois, 1000));
+    }
+
+    #[test]
+    fn test_known_plaintext() {
+        let message = b"From: headquarters. Subject: the attack will start at dawn, \
+            move the tanks to the river before midnight.";
+        let mut lfsr = Lfsr::galois(&[31, 28], 0x1234_5678).unwrap();
+        let ciphertext = lfsr.clone().encipher(message);
+
+        // "Subject: " gives 72 keystream bits, enough for a 31-bit register.
+        let recovery = known_plaintext(&ciphertext, b"Subject: ", 20).unwrap();
+        assert_eq!(recovery.plaintext, message);
+        assert_eq!(recovery.lfsr.taps(), &[31, 28]);
+        assert_eq!(
+            recovery.lfsr.clone().keystream(message.len()),
+            lfsr.keystream(message.len())
+        );
+    }
+
+    #[test]
+    fn test_attack_errors() {
+        let ciphertext = Lfsr::fibonacci(&[31, 28], 1)
+            .unwrap()
+            .encipher(b"Attack at dawn");
+        assert_eq!(
+            known_plaintext(&ciphertext, b"dawn!", 10),
+            Err(AttackError::InvalidOffset)
+        );
+        assert_eq!(
+            known_plaintext(b"abc", b"a", usize::MAX),
+            Err(AttackError::InvalidOffset)
+        );
+        assert_eq!(
+            recover_lfsr(&[0, 0, 0, 1]),
+            Err(AttackError::InsufficientKeystream(4))
+        );
+        assert_eq!(recover_lfsr(&[0; 20]), Err(AttackError::Degenerate));
+    }
+}
@@ -0,0 +1,241 @@
+//! Step-by-step traces of the ciphers, for teaching.
+//!
+//! The `*_traced` functions of the cipher modules return, along with their
+//! result, the list of the operations performed: one [`Step`] per letter for
+//! the substitution ciphers and machines, one per digraph for the digraphic
+//! ciphers. Each step records its input and output, the rule that was applied,
+//! and the details needed to redo it by hand (key letter and shift, grid
+//! coordinates...).
+//!
+//! A trace can be serialized with the `serde` feature (or, without it,
+//! exported as JSON with `to_json`), or printed as an aligned text table with
+//! [`to_table`]:
+//!
+//! ```text
+//! #  Input  Output  Rule   Key  Shift
+//! 1  A      L       shift  L    11
+//! 2  T      X       shift  E    4
+//! ```
+
+use crate::prelude::*;
+
+/// A single operation of a cipher.
+///
+/// With the `serde` feature, a step is serialized as an object whose `details`
+/// member maps the names of the details to their values, in order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Step {
+    /// The letters consumed by the step.
+    pub input: String,
+    /// The letters produced by the step.
+    pub output: String,
+    /// The rule applied, e.g. `shift`, `row` or `rectangle`.
+    pub rule: String,
+    /// The values used by the rule, as name and value pairs, in order.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_details"))]
+    pub details: Vec<(String, String)>,
+}
+
+impl Step {
+    /// Creates a step without details.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The letters consumed by the step.
+    /// * `output` - The letters produced by the step.
+    /// * `rule` - The rule applied.
+    pub fn new(input: impl Into<String>, output: impl Into<String>, rule: &str) -> Self {
+        Step {
+            input: input.into(),
+            output: output.into(),
+            rule: rule.to_string(),
+            details: Vec::new(),
+        }
+    }
+
+    /// Adds a detail to the step.
+    pub fn detail(mut self, name: &str, value: impl ToString) -> Self {
+        self.details.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Serializes the details of a step as a map, keeping their order.
+#[cfg(feature = "serde")]
+fn serialize_details<S: serde::Serializer>(
+    details: &[(String, String)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(details.iter().map(|(name, value)| (name, value)))
+}
+
+/// Serializes a trace to JSON.
+///
+/// This is the fallback when the `serde` feature is disabled; with it, the
+/// steps can be serialized directly.
+///
+/// # Arguments
+///
+/// * `steps` - The trace.
+///
+/// # Returns
+///
+/// A JSON array with one object per step, whose `details` member maps the
+/// names of the details to their values.
+#[cfg(not(feature = "serde"))]
+pub fn to_json(steps: &[Step]) -> String {
+    let steps: Vec<String> = steps
+        .iter()
+        .map(|step| {
+            let details: Vec<String> = step
+                .details
+                .iter()
+                .map(|(name, value)| format!("{}:{}", json_string(name), json_string(value)))
+                .collect();
+            format!(
+                "{{\"input\":{},\"output\":{},\"rule\":{},\"details\":{{{}}}}}",
+                json_string(&step.input),
+                json_string(&step.output),
+                json_string(&step.rule),
+                details.join(",")
+            )
+        })
+        .collect();
+    format!("[{}]", steps.join(","))
+}
+
+/// Renders a trace as a text table, with a column per detail.
+///
+/// # Arguments
+///
+/// * `steps` - The trace.
+///
+/// # Returns
+///
+/// The table, one line per step after a header line, with aligned columns.
+pub fn to_table(steps: &[Step]) -> String {
+    let mut names: Vec<&str> = Vec::new();
+    for (name, _) in steps.iter().flat_map(|step| &step.details) {
+        if !names.contains(&name.as_str()) {
+            names.push(name);
+        }
+    }
+
+    let header = ["#", "Input", "Output", "Rule"]
+        .into_iter()
+        .map(str::to_string)
+        .chain(names.iter().map(|name| capitalize(name)))
+        .collect();
+    let mut rows: Vec<Vec<String>> = vec![header];
+
+    for (number, step) in steps.iter().enumerate() {
+        let mut row = vec![
+            (number + 1).to_string(),
+            step.input.clone(),
+            step.output.clone(),
+            step.rule.clone(),
+        ];
+        row.extend(names.iter().map(|&name| {
+            step.details
+                .iter()
+                .find(|(detail, _)| detail == name)
+                .map_or_else(String::new, |(_, value)| value.clone())
+        }));
+        rows.push(row);
+    }
+
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:width$}", cell, width = width))
+                .collect();
+            cells.join("  ").trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns a name with its first letter in uppercase.
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// Quotes and escapes a string for JSON.
+#[cfg(not(feature = "serde"))]
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<Step> {
+        vec![
+            Step::new("A", "L", "shift")
+                .detail("key", 'L')
+                .detail("shift", 11),
+            Step::new(" ", " ", "keep"),
+        ]
+    }
+
+    #[cfg(not(feature = "serde"))]
+    #[test]
+    fn test_trace_to_json() {
+        assert_eq!(
+            to_json(&sample()),
+            "[{\"input\":\"A\",\"output\":\"L\",\"rule\":\"shift\",\"details\":{\"key\":\"L\",\"shift\":\"11\"}},\
+             {\"input\":\" \",\"output\":\" \",\"rule\":\"keep\",\"details\":{}}]"
+        );
+        assert_eq!(to_json(&[]), "[]");
+        assert_eq!(json_string("say \"hi\"\n"), "\"say \\\"hi\\\"\\n\"");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_trace_serde() {
+        assert_eq!(
+            serde_json::to_string(&sample()).unwrap(),
+            "[{\"input\":\"A\",\"output\":\"L\",\"rule\":\"shift\",\"details\":{\"key\":\"L\",\"shift\":\"11\"}},\
+             {\"input\":\" \",\"output\":\" \",\"rule\":\"keep\",\"details\":{}}]"
+        );
+        assert_eq!(serde_json::to_string(&Vec::<Step>::new()).unwrap(), "[]");
+    }
+
+    #[test]
+    fn test_trace_to_table() {
+        assert_eq!(
+            to_table(&sample()),
+            "#  Input  Output  Rule   Key  Shift\n\
+             1  A      L       shift  L    11\n\
+             2                 keep"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! An implementation of the Two-square cipher (also known as the double Playfair).
+//!
+//! The Two-square cipher uses two 5x5 squares keyed with different keywords,
+//! arranged either side by side (horizontal) or one above the other (vertical).
+//! Each letter of a digraph is looked up in its own square, and the two letters
+//! are replaced by the other two corners of the rectangle they form. Because the
+//! replacement letters stay in the squares of the letters they replace, the
+//! cipher is reciprocal: encrypting a ciphertext gives back the plaintext.
+
+use crate::digraph::{
+    collect_output, create_digraphs, prepare_text, DigraphError, DigraphRules, Substitution,
+};
+use crate::key_square::KeySquare;
+use crate::prelude::*;
+use crate::trace::Step;
+
+/// The arrangement of the two squares.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    /// The first square is on the left and the second square is on the right.
+    Horizontal,
+    /// The first square is on top and the second square is at the bottom.
+    Vertical,
+}
+
+/// Encrypts a given plaintext string using the Two-square cipher.
+///
+/// # Arguments
+///
+/// * `first_key` - The keyword of the first (left or upper) square.
+/// * `second_key` - The keyword of the second (right or lower) square.
+/// * `text` - The input string to be encrypted.
+/// * `orientation` - The arrangement of the two squares.
+///
+/// # Returns
+///
+/// The encrypted version of the input string.
+pub fn encrypt(
+    first_key: &str,
+    second_key: &str,
+    text: &str,
+    orientation: Orientation,
+) -> Result<String, DigraphError> {
+    encipher_with_rules(
+        first_key,
+        second_key,
+        text,
+        orientation,
+        &DigraphRules::TWO_SQUARE,
+    )
+}
+
+/// Decrypts a given ciphertext string using the Two-square cipher.
+///
+/// # Arguments
+///
+/// * `first_key` - The keyword of the first (left or upper) square.
+/// * `second_key` - The keyword of the second (right or lower) square.
+/// * `text` - The encrypted input string to be decrypted.
+/// * `orientation` - The arrangement of the two squares.
+///
+/// # Returns
+///
+/// The decrypted version of the input string.
+pub fn decrypt(
+    first_key: &str,
+    second_key: &str,
+    text: &str,
+    orientation: Orientation,
+) -> Result<String, DigraphError> {
+    encipher_with_rules(
+        first_key,
+        second_key,
+        text,
+        orientation,
+        &DigraphRules::TWO_SQUARE,
+    )
+}
+
+/// Encrypts or decrypts a given string using the Two-square cipher and custom digraph rules.
+///
+/// Since the Two-square cipher is reciprocal, the same function is used for both operations.
+///
+/// # Arguments
+///
+/// * `first_key` - The keyword of the first (left or upper) square.
+/// * `second_key` - The keyword of the second (right or lower) square.
+/// * `text` - The input string to be processed.
+/// * `orientation` - The arrangement of the two squares.
+/// * `rules` - The rules used to prepare the text and split it into digraphs.
+///
+/// # Returns
+///
+/// The processed version of the input string.
+pub fn encipher_with_rules(
+    first_key: &str,
+    second_key: &str,
+    text: &str,
+    orientation: Orientation,
+    rules: &DigraphRules,
+) -> Result<String, DigraphError> {
+    two_square_cipher(first_key, second_key, text, orientation, rules, false).map(|(text, _)| text)
+}
+
+/// Encrypts or decrypts a given string using the Two-square cipher, recording every step.
+///
+/// Since the Two-square cipher is reciprocal, the same function is used for both operations.
+///
+/// # Arguments
+///
+/// * `first_key` - The keyword of the first (left or upper) square.
+/// * `second_key` - The keyword of the second (right or lower) square.
+/// * `text` - The input string to be processed.
+/// * `orientation` - The arrangement of the two squares.
+///
+/// # Returns
+///
+/// The processed version of the input string, and one step per digraph with
+/// the rule applied (`rectangle`, or `transparent` when the letters are left
+/// unchanged) and the grid coordinates of the letters in their squares.
+pub fn encipher_traced(
+    first_key: &str,
+    second_key: &str,
+    text: &str,
+    orientation: Orientation,
+) -> Result<(String, Vec<Step>), DigraphError> {
+    two_square_cipher(
+        first_key,
+        second_key,
+        text,
+        orientation,
+        &DigraphRules::TWO_SQUARE,
+        true,
+    )
+}
+
+/// Performs the actual Two-square transformation of the input string.
+///
+/// # Arguments
+///
+/// * `first_key` - The keyword of the first (left or upper) square.
+/// * `second_key` - The keyword of the second (right or lower) square.
+/// * `text` - The input string to be processed.
+/// * `orientation` - The arrangement of the two squares.
+/// * `rules` - The rules used to prepare the text and split it into digraphs.
+/// * `traced` - Whether to record the steps.
+///
+/// # Returns
+///
+/// The processed version of the input string, and one step per digraph if
+/// `traced` is set.
+fn two_square_cipher(
+    first_key: &str,
+    second_key: &str,
+    text: &str,
+    orientation: Orientation,
+    rules: &DigraphRules,
+    traced: bool,
+) -> Result<(String, Vec<Step>), DigraphError> {
+    if first_key.is_empty() || second_key.is_empty() || text.is_empty() {
+        return Err(DigraphError::EmptyInput);
+    }
+
+    let first_square = KeySquare::new(first_key, rules.alphabet);
+    let second_square = KeySquare::new(second_key, rules.alphabet);
+
+    let prepared_text = prepare_text(text, rules);
+    let digraphs = create_digraphs(&prepared_text, rules)?;
+    let substitutions = digraphs.iter().map(|&(a, b)| {
+        let (r1, c1) = first_square.position(a).ok_or(DigraphError::CharNotFound)?;
+        let (r2, c2) = second_square
+            .position(b)
+            .ok_or(DigraphError::CharNotFound)?;
+        let to = match orientation {
+            // Side by side: each letter keeps its column and takes the row of the other.
+            Orientation::Horizontal => [(r2, c1), (r1, c2)],
+            // Stacked: each letter keeps its row and takes the column of the other.
+            Orientation::Vertical => [(r1, c2), (r2, c1)],
+        };
+        let output = (
+            first_square.char_at(to[0].0, to[0].1),
+            second_square.char_at(to[1].0, to[1].1),
+        );
+        Ok(Substitution {
+            rule: if output == (a, b) {
+                "transparent"
+            } else {
+                "rectangle"
+            },
+            input: (a, b),
+            output,
+            from: [(r1, c1), (r2, c2)],
+            to,
+        })
+    });
+    collect_output(substitutions, traced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_square_vertical_encryption() {
+        assert_eq!(
+            encrypt(
+                "example",
+                "keyword",
+                "help me obi wan kenobi",
+                Orientation::Vertical
+            ),
+            Ok("HEDLXWSDJYANHOTKDG".to_string())
+        );
+    }
+
+    #[test]
+    fn test_two_square_vertical_decryption() {
+        assert_eq!(
+            decrypt(
+                "example",
+                "keyword",
+                "HEDLXWSDJYANHOTKDG",
+                Orientation::Vertical
+            ),
+            Ok("HELPMEOBIWANKENOBI".to_string())
+        );
+    }
+
+    #[test]
+    fn test_two_square_horizontal_round_trip() {
+        let ciphertext = encrypt(
+            "example",
+            "keyword",
+            "help me obi wan kenobi",
+            Orientation::Horizontal,
+        )
+        .unwrap();
+        assert_eq!(&ciphertext[..4], "XGNB");
+        assert_eq!(
+            decrypt("example", "keyword", &ciphertext, Orientation::Horizontal),
+            Ok("HELPMEOBIWANKENOBI".to_string())
+        );
+    }
+
+    #[test]
+    fn test_two_square_transparency() {
+        // `H` and `E` share a column in the vertical arrangement, so they are left unchanged.
+        assert_eq!(
+            encrypt("example", "keyword", "he", Orientation::Vertical),
+            Ok("HE".to_string())
+        );
+    }
+
+    #[test]
+    fn test_two_square_traced() {
+        let (ciphertext, steps) =
+            encipher_traced("example", "keyword", "help me", Orientation::Vertical).unwrap();
+        assert_eq!(ciphertext, "HEDLXW");
+        assert_eq!(steps[0].rule, "transparent");
+        assert_eq!(steps[1].rule, "rectangle");
+        assert_eq!(steps[1].input, "LP");
+        assert_eq!(steps[1].output, "DL");
+    }
+
+    #[test]
+    fn test_two_square_empty_input() {
+        assert_eq!(
+            encrypt("", "keyword", "text", Orientation::Vertical),
+            Err(DigraphError::EmptyInput)
+        );
+    }
+}
@@ -0,0 +1,575 @@
+//! An implementation of the VIC cipher.
+//!
+//! The VIC cipher was used by the Soviet spy Reino Häyhänen in the 1950s. It is
+//! a pencil-and-paper cipher that combines a straddling checkerboard with two
+//! transpositions, all keyed from four pieces of information:
+//!
+//! * a phrase known by the agent (its first 20 letters are used),
+//! * a date (its first six digits, e.g. `391945` for 3 September 1945),
+//! * the personal number of the agent,
+//! * a random five-digit message keygroup, chosen for every message and
+//!   inserted in the ciphertext.
+//!
+//! From these, chain addition and sequentialization derive the keys of a simple
+//! columnar transposition, of a disrupted transposition, and the column digits
+//! of the checkerboard.
+
+use crate::columnar::{sequentialize, transpose, untranspose};
+use crate::prelude::*;
+use crate::straddling_checkerboard::{CheckerboardError, StraddlingCheckerboard};
+use thiserror::Error;
+
+/// The number of letters of the phrase used for the keys.
+const PHRASE_LEN: usize = 20;
+
+/// The number of digits of the date used for the keys.
+const DATE_LEN: usize = 6;
+
+/// The number of digits of the message keygroup.
+const KEYGROUP_LEN: usize = 5;
+
+/// The default checkerboard: the top row holds "AT ONE SIR", a mnemonic of the most frequent letters.
+const DEFAULT_ALPHABET: &str = "ATONESIRBCDFGHJKLMPQUVWXYZ./";
+const DEFAULT_BLANKS: [usize; 2] = [2, 6];
+
+/// Error type for the VIC cipher.
+#[derive(Error, Debug, PartialEq)]
+pub enum VicError {
+    #[error("The phrase must contain at least 20 letters")]
+    ShortPhrase,
+    #[error("The date must contain at least 6 digits")]
+    InvalidDate,
+    #[error("Invalid personal number: {0}")]
+    InvalidPersonalNumber(u8),
+    #[error("The keygroup must be 5 digits")]
+    InvalidKeygroup,
+    #[error("The ciphertext must be made of digits and contain a keygroup")]
+    InvalidCiphertext,
+    #[error(transparent)]
+    Checkerboard(#[from] CheckerboardError),
+}
+
+/// The keys derived for a message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VicKeys {
+    /// The key of the first (simple columnar) transposition, as ranks starting at 1.
+    pub first_transposition: Vec<usize>,
+    /// The key of the second (disrupted) transposition, as ranks starting at 1.
+    pub second_transposition: Vec<usize>,
+    /// The digits labelling the columns of the checkerboard.
+    pub checkerboard_header: [u8; 10],
+}
+
+/// A VIC cipher, keyed with the agent's phrase, date and personal number.
+///
+/// With the `serde` feature, the cipher is serialized as the phrase and date it
+/// uses, the personal number and the checkerboard.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "VicRepr", into = "VicRepr")
+)]
+pub struct Vic {
+    phrase: Vec<u8>,
+    date: [u8; DATE_LEN],
+    personal_number: u8,
+    checkerboard: StraddlingCheckerboard,
+}
+
+impl Vic {
+    /// Creates a VIC cipher with the default checkerboard.
+    ///
+    /// # Arguments
+    ///
+    /// * `phrase` - The agent's phrase. Only its first 20 letters are used.
+    /// * `date` - The date. Only its first 6 digits are used.
+    /// * `personal_number` - The agent's personal number, from 1 to 16.
+    ///
+    /// # Returns
+    ///
+    /// The cipher, or an error if one of the keys is invalid.
+    pub fn new(phrase: &str, date: &str, personal_number: u8) -> Result<Self, VicError> {
+        let phrase: Vec<u8> = phrase
+            .bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .map(|b| b.to_ascii_uppercase())
+            .take(PHRASE_LEN)
+            .collect();
+        if phrase.len() < PHRASE_LEN {
+            return Err(VicError::ShortPhrase);
+        }
+
+        let date: Vec<u8> = date
+            .bytes()
+            .filter(u8::is_ascii_digit)
+            .map(|b| b - b'0')
+            .take(DATE_LEN)
+            .collect();
+        let date: [u8; DATE_LEN] = date.try_into().map_err(|_| VicError::InvalidDate)?;
+
+        // Both transposition widths are the personal number plus a digit, and
+        // must fit in the 50 digits drawn from the key block.
+        if !(1..=16).contains(&personal_number) {
+            return Err(VicError::InvalidPersonalNumber(personal_number));
+        }
+
+        Ok(Vic {
+            phrase,
+            date,
+            personal_number,
+            checkerboard: StraddlingCheckerboard::new(DEFAULT_ALPHABET, DEFAULT_BLANKS)?,
+        })
+    }
+
+    /// Uses another checkerboard layout. The column digits are still derived from the keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `alphabet` - The 28 symbols of the board (see [`StraddlingCheckerboard::new`]).
+    /// * `blanks` - The columns of the two blank cells of the top row.
+    pub fn with_checkerboard(
+        mut self,
+        alphabet: &str,
+        blanks: [usize; 2],
+    ) -> Result<Self, VicError> {
+        self.checkerboard = StraddlingCheckerboard::new(alphabet, blanks)?;
+        Ok(self)
+    }
+
+    /// Derives the keys of a message from its keygroup.
+    ///
+    /// # Arguments
+    ///
+    /// * `keygroup` - The five-digit message keygroup.
+    ///
+    /// # Returns
+    ///
+    /// The keys, or an error if the keygroup is not five digits.
+    pub fn keys(&self, keygroup: &str) -> Result<VicKeys, VicError> {
+        let h = self.line_h(&parse_keygroup(keygroup)?);
+        let j = sequentialize_digits(&h);
+
+        // Five rows of chain addition, the last of which keys the checkerboard.
+        let block = chain_addition(&h, 50);
+        let block = block.as_slice();
+        let last_row = &block[40..];
+
+        // The last two different digits of the last row give the widths of the transpositions.
+        let last = last_row[9];
+        let before = last_row[..9]
+            .iter()
+            .rev()
+            .find(|&&d| d != last)
+            .copied()
+            .unwrap_or(last);
+        let first_width = (self.personal_number + before) as usize;
+        let second_width = (self.personal_number + last) as usize;
+
+        // Read the block by columns, in the order given by J.
+        let columns: Vec<u8> = (1..=10)
+            .flat_map(|rank| {
+                let col = j.iter().position(|&r| r == rank).unwrap_or_default();
+                (0..5).map(move |row| block[row * 10 + col])
+            })
+            .collect();
+
+        let header = ranks_to_digits(&sequentialize_digits(last_row));
+        Ok(VicKeys {
+            first_transposition: sequentialize_digits(&columns[..first_width]),
+            second_transposition: sequentialize_digits(
+                &columns[first_width..first_width + second_width],
+            ),
+            checkerboard_header: header.try_into().unwrap_or_default(),
+        })
+    }
+
+    /// Derives the ten digits from which the key block of a message is grown
+    /// (line H of the key schedule).
+    fn line_h(&self, keygroup: &[u8; KEYGROUP_LEN]) -> Vec<u8> {
+        // Subtract the date from the keygroup, without borrowing.
+        let c: Vec<u8> = keygroup
+            .iter()
+            .zip(&self.date)
+            .map(|(&a, &b)| (a + 10 - b) % 10)
+            .collect();
+
+        // Sequentialize each half of the phrase.
+        let e1 = ranks_to_digits(&sequentialize(&self.phrase[..10]));
+        let e2 = ranks_to_digits(&sequentialize(&self.phrase[10..]));
+
+        // Extend the difference to ten digits and add it to the first half.
+        let mut f1 = c.clone();
+        f1.extend(chain_addition(&c, 5));
+        let g: Vec<u8> = e1.iter().zip(&f1).map(|(&e, &f)| (e + f) % 10).collect();
+
+        // Encode through the second half: the digit under 1 in 1234567890 is the first of E2, and so on.
+        g.iter().map(|&d| e2[(d as usize + 9) % 10]).collect()
+    }
+
+    /// Encrypts the provided plaintext.
+    ///
+    /// # Arguments
+    ///
+    /// * `plaintext` - The text to be encrypted (letters, digits, full stops and whitespace).
+    /// * `keygroup` - The five-digit message keygroup, which must be chosen at random for every message.
+    ///
+    /// # Returns
+    ///
+    /// The ciphertext, in groups of five digits, or an error if a key or character is invalid.
+    pub fn encrypt(&self, plaintext: &str, keygroup: &str) -> Result<String, VicError> {
+        let keys = self.keys(keygroup)?;
+        let checkerboard = self
+            .checkerboard
+            .clone()
+            .with_header(keys.checkerboard_header)?;
+
+        let digits: Vec<u8> = checkerboard
+            .encode(plaintext)?
+            .bytes()
+            .map(|b| b - b'0')
+            .collect();
+        let digits = transpose(&digits, &keys.first_transposition);
+        let digits = disrupted_transpose(&digits, &keys.second_transposition);
+
+        // Insert the keygroup among the groups of the message.
+        let offset = self.keygroup_offset(digits.len());
+        let mut message: Vec<u8> = digits[..offset].to_vec();
+        message.extend(parse_keygroup(keygroup)?);
+        message.extend(&digits[offset..]);
+
+        Ok(message
+            .chunks(5)
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|&d| (d + b'0') as char)
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+
+    /// Decrypts the provided ciphertext.
+    ///
+    /// # Arguments
+    ///
+    /// * `ciphertext` - The digits of the message, including the keygroup. Whitespace is ignored.
+    ///
+    /// # Returns
+    ///
+    /// The plaintext, in uppercase and without spaces, or an error if the ciphertext is invalid.
+    pub fn decrypt(&self, ciphertext: &str) -> Result<String, VicError> {
+        let message = ciphertext
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_digit(10).map(|d| d as u8))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or(VicError::InvalidCiphertext)?;
+        if message.len() < KEYGROUP_LEN {
+            return Err(VicError::InvalidCiphertext);
+        }
+
+        let offset = self.keygroup_offset(message.len() - KEYGROUP_LEN);
+        let keygroup: String = message[offset..offset + KEYGROUP_LEN]
+            .iter()
+            .map(|&d| (d + b'0') as char)
+            .collect();
+        let mut digits = message[..offset].to_vec();
+        digits.extend(&message[offset + KEYGROUP_LEN..]);
+
+        let keys = self.keys(&keygroup)?;
+        let digits = untranspose_disrupted(&digits, &keys.second_transposition);
+        let digits = untranspose(&digits, &keys.first_transposition);
+
+        let checkerboard = self
+            .checkerboard
+            .clone()
+            .with_header(keys.checkerboard_header)?;
+        let digits: String = digits.iter().map(|&d| (d + b'0') as char).collect();
+        Ok(checkerboard.decode(&digits)?)
+    }
+
+    /// Returns where the keygroup is inserted in a message of `len` digits.
+    ///
+    /// The keygroup becomes the group whose position, counted from the end of
+    /// the message, is given by the last digit of the date.
+    fn keygroup_offset(&self, len: usize) -> usize {
+        let groups = len.div_ceil(KEYGROUP_LEN);
+        let group = groups.saturating_sub(self.date[DATE_LEN - 1] as usize);
+        (group * KEYGROUP_LEN).min(len)
+    }
+}
+
+/// The serialized form of a [`Vic`] cipher, checked when it is loaded.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VicRepr {
+    phrase: String,
+    date: String,
+    personal_number: u8,
+    checkerboard: StraddlingCheckerboard,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<VicRepr> for Vic {
+    type Error = VicError;
+
+    fn try_from(repr: VicRepr) -> Result<Self, Self::Error> {
+        let vic = Vic::new(&repr.phrase, &repr.date, repr.personal_number)?;
+        Ok(Vic {
+            checkerboard: repr.checkerboard,
+            ..vic
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Vic> for VicRepr {
+    fn from(vic: Vic) -> Self {
+        VicRepr {
+            phrase: vic.phrase.iter().map(|&b| b as char).collect(),
+            date: vic.date.iter().map(|&d| (d + b'0') as char).collect(),
+            personal_number: vic.personal_number,
+            checkerboard: vic.checkerboard,
+        }
+    }
+}
+
+/// Extends a sequence of digits by chain addition (lagged Fibonacci, modulo 10).
+///
+/// Each new digit is the sum of the digit `seed.len()` places before it and of
+/// the following one, without carry.
+///
+/// # Arguments
+///
+/// * `seed` - The initial digits.
+/// * `count` - The number of digits to generate.
+///
+/// # Returns
+///
+/// The `count` generated digits, not including the seed. A seed of fewer than
+/// two digits cannot be extended, and gives no digits.
+pub fn chain_addition(seed: &[u8], count: usize) -> Vec<u8> {
+    if seed.len() < 2 {
+        return Vec::new();
+    }
+    let mut digits = seed.to_vec();
+    for idx in 0..count {
+        let next = (digits[idx] + digits[idx + 1]) % 10;
+        digits.push(next);
+    }
+    digits.split_off(seed.len())
+}
+
+/// Sequentializes digits, where 0 comes after 9.
+fn sequentialize_digits(digits: &[u8]) -> Vec<usize> {
+    let values: Vec<u8> = digits
+        .iter()
+        .map(|&d| if d == 0 { 10 } else { d })
+        .collect();
+    sequentialize(&values)
+}
+
+/// Writes ranks as digits, 10 being written 0.
+fn ranks_to_digits(ranks: &[usize]) -> Vec<u8> {
+    ranks.iter().map(|&r| (r % 10) as u8).collect()
+}
+
+/// Parses a five-digit keygroup.
+fn parse_keygroup(keygroup: &str) -> Result<[u8; KEYGROUP_LEN], VicError> {
+    let digits: Vec<u8> = keygroup
+        .chars()
+        .map(|c| c.to_digit(10).map(|d| d as u8))
+        .collect::<Option<_>>()
+        .ok_or(VicError::InvalidKeygroup)?;
+    digits.try_into().map_err(|_| VicError::InvalidKeygroup)
+}
+
+/// Returns which cells of the table of a disrupted transposition are in the triangular areas.
+///
+/// The first area starts in the first row at the column numbered 1 and extends
+/// to the end of the row; on every following row, it starts one column further
+/// to the right, until it is empty. The next area then starts on the following
+/// row at the column numbered 2, and so on.
+fn disrupted_areas(len: usize, key: &[usize]) -> Vec<bool> {
+    let width = key.len();
+    let rows = len.div_ceil(width);
+    let mut areas = vec![false; len];
+    let (mut row, mut rank) = (0, 1);
+
+    while row < rows {
+        let mut start = key.iter().position(|&r| r == rank).unwrap_or_default();
+        while row < rows {
+            for col in start..width {
+                if let Some(cell) = areas.get_mut(row * width + col) {
+                    *cell = true;
+                }
+            }
+            row += 1;
+            if start == width {
+                break;
+            }
+            start += 1;
+        }
+        rank = rank % width + 1;
+    }
+    areas
+}
+
+/// Disrupted transposition: fills the cells outside the triangular areas first, then the areas, and reads the columns in key order.
+fn disrupted_transpose(digits: &[u8], key: &[usize]) -> Vec<u8> {
+    let areas = disrupted_areas(digits.len(), key);
+    let mut table = vec![0; digits.len()];
+    let cells = (0..digits.len())
+        .filter(|&idx| !areas[idx])
+        .chain((0..digits.len()).filter(|&idx| areas[idx]));
+    for (idx, &digit) in cells.zip(digits) {
+        table[idx] = digit;
+    }
+    transpose(&table, key)
+}
+
+/// Reverses [`disrupted_transpose`].
+fn untranspose_disrupted(digits: &[u8], key: &[usize]) -> Vec<u8> {
+    let areas = disrupted_areas(digits.len(), key);
+    let table = untranspose(digits, key);
+    (0..table.len())
+        .filter(|&idx| !areas[idx])
+        .chain((0..table.len()).filter(|&idx| areas[idx]))
+        .map(|idx| table[idx])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vic() -> Vic {
+        Vic::new("Twas the night before Christmas", "391945", 6).unwrap()
+    }
+
+    #[test]
+    fn test_vic_chain_addition() {
+        assert_eq!(chain_addition(&[7, 7, 6, 5, 1], 5), vec![4, 3, 1, 6, 5]);
+        assert_eq!(chain_addition(&[1, 2], 4), vec![3, 5, 8, 3]);
+        assert_eq!(chain_addition(&[5], 3), Vec::<u8>::new());
+        assert_eq!(chain_addition(&[], 3), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_vic_sequentialize() {
+        assert_eq!(sequentialize_digits(&[0, 1, 9, 1]), vec![4, 1, 3, 2]);
+    }
+
+    #[test]
+    fn test_vic_transpositions() {
+        let key = sequentialize(b"ZEBRAS");
+        let digits: Vec<u8> = (0..23).map(|d| d % 10).collect();
+        assert_eq!(
+            transpose(&[1, 2, 3, 4, 5, 6, 7], &[2, 1, 3]),
+            vec![2, 5, 1, 4, 7, 3, 6]
+        );
+        for len in 0..digits.len() {
+            let digits = &digits[..len];
+            assert_eq!(untranspose(&transpose(digits, &key), &key), digits);
+            assert_eq!(
+                untranspose_disrupted(&disrupted_transpose(digits, &key), &key),
+                digits
+            );
+        }
+    }
+
+    #[test]
+    fn test_vic_disrupted_areas() {
+        // Width 4, the column numbered 1 is the third one.
+        let areas = disrupted_areas(16, &[2, 4, 1, 3]);
+        let rows: Vec<String> = areas
+            .chunks(4)
+            .map(|row| row.iter().map(|&a| if a { '#' } else { '.' }).collect())
+            .collect();
+        assert_eq!(rows, vec!["..##", "...#", "....", "####"]);
+    }
+
+    #[test]
+    fn test_vic_keys() {
+        // The worked example: phrase "Twas the night before Christmas", date
+        // 3 September 1945, personal number 6 and keygroup 72401.
+        let vic = vic();
+        let h = vic.line_h(&[7, 2, 4, 0, 1]);
+        assert_eq!(h, [0, 1, 3, 4, 8, 6, 4, 7, 1, 9]);
+        let block: Vec<String> = chain_addition(&h, 50)
+            .chunks(10)
+            .map(|row| row.iter().map(|d| d.to_string()).collect())
+            .collect();
+        assert_eq!(
+            block,
+            [
+                "1472401800",
+                "5196419805",
+                "6050507851",
+                "6555575367",
+                "1000228938"
+            ]
+        );
+
+        let keys = vic.keys("72401").unwrap();
+        assert_eq!(keys.first_transposition, [2, 1, 6, 3, 7, 8, 9, 4, 5]);
+        assert_eq!(
+            keys.second_transposition,
+            [3, 8, 10, 4, 5, 12, 2, 7, 13, 6, 14, 1, 11, 9]
+        );
+        assert_eq!(keys.checkerboard_header, [1, 8, 9, 0, 2, 3, 5, 7, 4, 6]);
+        assert_ne!(keys, vic.keys("72402").unwrap());
+    }
+
+    #[test]
+    fn test_vic_round_trip() {
+        let vic = vic();
+        let plaintext =
+            "WE ARE PLEASED TO HEAR OF YOUR SUCCESS IN ESTABLISHING YOUR FALSE IDENTITY. \
+            YOU WILL BE SENT SOME MONEY TO COVER EXPENSES WITHIN A MONTH 1957";
+        let ciphertext = vic.encrypt(plaintext, "72401").unwrap();
+        assert!(ciphertext.contains("72401"));
+        assert!(ciphertext.chars().all(|c| c.is_ascii_digit() || c == ' '));
+
+        let expected: String = plaintext.chars().filter(|c| !c.is_whitespace()).collect();
+        assert_eq!(vic.decrypt(&ciphertext).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_vic_errors() {
+        assert_eq!(
+            Vic::new("TOO SHORT", "391945", 6),
+            Err(VicError::ShortPhrase)
+        );
+        assert_eq!(
+            Vic::new("Twas the night before Christmas", "3945", 6),
+            Err(VicError::InvalidDate)
+        );
+        assert_eq!(
+            Vic::new("Twas the night before Christmas", "391945", 17),
+            Err(VicError::InvalidPersonalNumber(17))
+        );
+        assert_eq!(
+            vic().encrypt("HELLO", "1234"),
+            Err(VicError::InvalidKeygroup)
+        );
+        assert_eq!(vic().decrypt("12a45"), Err(VicError::InvalidCiphertext));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_vic_serde() {
+        let vic = vic();
+        let json = serde_json::to_string(&vic).unwrap();
+        assert!(json.starts_with(
+            r#"{"phrase":"TWASTHENIGHTBEFORECH","date":"391945","personal_number":6,"#
+        ));
+        assert_eq!(serde_json::from_str::<Vic>(&json).unwrap(), vic);
+
+        let invalid = json.replace(r#""personal_number":6"#, r#""personal_number":0"#);
+        assert!(serde_json::from_str::<Vic>(&invalid).is_err());
+    }
+}
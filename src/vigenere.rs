@@ -3,10 +3,82 @@
 //! This module provides functionality to encrypt and decrypt messages using
 //! the Vigenère cipher, a method of encrypting alphabetic text by using a
 //! simple form of polyalphabetic substitution.
+//!
+//! Besides the classic repeating key, the following keying modes are supported:
+//!
+//! * plaintext autokey, where the key is followed by the plaintext itself,
+//! * ciphertext autokey, where the key is followed by the ciphertext,
+//! * running key, where the key is a long text (e.g. a passage from a book),
+//! * Gronsfeld, where the key is a number and each digit is a shift,
+//! * Trithemius, where the shift simply increases by one for every letter.
+
+use crate::cipher::{Cipher, CipherError};
+use crate::formatting::Formatting;
+use crate::prelude::*;
+use crate::trace::Step;
+use thiserror::Error;
 
 const ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
 const ALPHABET_SIZE: usize = ALPHABET.len();
 
+/// Error type for the Vigenère cipher variants that validate their key.
+#[derive(Error, Debug, PartialEq)]
+pub enum VigenereError {
+    #[error("Key cannot be empty")]
+    EmptyKey,
+    #[error("Key is shorter than the text")]
+    KeyTooShort,
+    #[error("Key must only contain digits")]
+    InvalidKey,
+}
+
+/// The key of a Vigenère cipher, for use through the [`Cipher`] trait.
+///
+/// Through the trait, only the letters of the text are kept, in uppercase, as
+/// with [`Formatting::Strip`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vigenere {
+    /// The key. Non-alphabetic characters are ignored.
+    pub key: String,
+}
+
+impl Vigenere {
+    /// Creates a Vigenère key, checking that it has letters.
+    ///
+    /// # Returns
+    ///
+    /// The key, or an error if it has no letters.
+    pub fn new(key: &str) -> Result<Self, VigenereError> {
+        if prepare_string(key).is_empty() {
+            return Err(VigenereError::EmptyKey);
+        }
+        Ok(Vigenere {
+            key: key.to_string(),
+        })
+    }
+}
+
+impl Cipher for Vigenere {
+    fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
+        let key = Vigenere::new(&self.key)?;
+        Ok(encrypt_with_formatting(
+            plaintext,
+            &key.key,
+            Formatting::Strip,
+        ))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let key = Vigenere::new(&self.key)?;
+        Ok(decrypt_with_formatting(
+            ciphertext,
+            &key.key,
+            Formatting::Strip,
+        ))
+    }
+}
+
 /// Enumeration for distinguishing encryption and decryption operations.
 ///
 /// This enum is utilized in the `char_shift` function to indicate whether a
@@ -45,6 +117,66 @@ pub fn decrypt(ciphertext: &str, key: &str) -> String {
     encipher(ciphertext, key, Operation::Decrypt)
 }
 
+/// Encrypts the provided plaintext using the Vigenère cipher, laying out the result as requested.
+///
+/// Only the letters are encrypted, so that the key advances on letters only.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+/// * `key` - The key to use for encryption.
+/// * `formatting` - How case, spaces and punctuation appear in the result.
+///
+/// # Returns
+///
+/// A new string that contains the encrypted version of the `plaintext`.
+pub fn encrypt_with_formatting(plaintext: &str, key: &str, formatting: Formatting) -> String {
+    formatting.apply(plaintext, |letters| encrypt(letters, &prepare_string(key)))
+}
+
+/// Decrypts the provided ciphertext using the Vigenère cipher, laying out the result as requested.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted.
+/// * `key` - The key used for encryption.
+/// * `formatting` - How case, spaces and punctuation appear in the result.
+///
+/// # Returns
+///
+/// A new string that contains the decrypted version of the `ciphertext`.
+pub fn decrypt_with_formatting(ciphertext: &str, key: &str, formatting: Formatting) -> String {
+    formatting.apply(ciphertext, |letters| decrypt(letters, &prepare_string(key)))
+}
+
+/// Encrypts the provided plaintext using the Vigenère cipher, recording every step.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted, in uppercase letters.
+/// * `key` - The key to use for encryption.
+///
+/// # Returns
+///
+/// The encrypted text, and one step per letter with its key letter and shift.
+pub fn encrypt_traced(plaintext: &str, key: &str) -> (String, Vec<Step>) {
+    encipher_traced(plaintext, key, Operation::Encrypt)
+}
+
+/// Decrypts the provided ciphertext using the Vigenère cipher, recording every step.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted, in uppercase letters.
+/// * `key` - The key used for decryption.
+///
+/// # Returns
+///
+/// The decrypted text, and one step per letter with its key letter and shift.
+pub fn decrypt_traced(ciphertext: &str, key: &str) -> (String, Vec<Step>) {
+    encipher_traced(ciphertext, key, Operation::Decrypt)
+}
+
 /// Prepares a string by converting it to uppercase and filtering out non-ASCII-alphabetic characters.
 ///
 /// This function serves as a helper to ensure input consistency before encryption or decryption.
@@ -64,6 +196,186 @@ pub fn prepare_string(input_string: &str) -> String {
         .to_uppercase()
 }
 
+/// Encrypts the provided plaintext using the plaintext autokey variant of the Vigenère cipher.
+///
+/// The key is followed by the plaintext itself, so it never repeats.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+/// * `key` - The primer key to use for encryption. An empty key is treated as `A`.
+///
+/// # Returns
+///
+/// A new string that contains the encrypted version of the `plaintext`.
+pub fn encrypt_autokey(plaintext: &str, key: &str) -> String {
+    let key = autokey_primer(key);
+    let shifts = key.chars().chain(plaintext.chars()).map(letter_shift);
+    apply_shifts(plaintext, shifts, Operation::Encrypt)
+}
+
+/// Decrypts the provided ciphertext using the plaintext autokey variant of the Vigenère cipher.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted.
+/// * `key` - The primer key used for encryption. An empty key is treated as `A`.
+///
+/// # Returns
+///
+/// A new string that contains the decrypted version of the `ciphertext`.
+pub fn decrypt_autokey(ciphertext: &str, key: &str) -> String {
+    let key = autokey_primer(key);
+    // Each recovered plaintext letter becomes part of the key stream.
+    let mut key_stream: Vec<char> = key.chars().collect();
+    let mut plaintext = String::with_capacity(ciphertext.len());
+
+    for (idx, c) in ciphertext.chars().enumerate() {
+        let p = shift_char(c, letter_shift(key_stream[idx]), Operation::Decrypt);
+        key_stream.push(p);
+        plaintext.push(p);
+    }
+
+    plaintext
+}
+
+/// Encrypts the provided plaintext using the ciphertext autokey variant of the Vigenère cipher.
+///
+/// The key is followed by the ciphertext produced so far.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+/// * `key` - The primer key to use for encryption. An empty key is treated as `A`.
+///
+/// # Returns
+///
+/// A new string that contains the encrypted version of the `plaintext`.
+pub fn encrypt_ciphertext_autokey(plaintext: &str, key: &str) -> String {
+    let key = autokey_primer(key);
+    // Each produced ciphertext letter becomes part of the key stream.
+    let mut key_stream: Vec<char> = key.chars().collect();
+    let mut ciphertext = String::with_capacity(plaintext.len());
+
+    for (idx, p) in plaintext.chars().enumerate() {
+        let c = shift_char(p, letter_shift(key_stream[idx]), Operation::Encrypt);
+        key_stream.push(c);
+        ciphertext.push(c);
+    }
+
+    ciphertext
+}
+
+/// Decrypts the provided ciphertext using the ciphertext autokey variant of the Vigenère cipher.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted.
+/// * `key` - The primer key used for encryption. An empty key is treated as `A`.
+///
+/// # Returns
+///
+/// A new string that contains the decrypted version of the `ciphertext`.
+pub fn decrypt_ciphertext_autokey(ciphertext: &str, key: &str) -> String {
+    let key = autokey_primer(key);
+    let shifts = key.chars().chain(ciphertext.chars()).map(letter_shift);
+    apply_shifts(ciphertext, shifts, Operation::Decrypt)
+}
+
+/// Encrypts the provided plaintext using the running key variant of the Vigenère cipher.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+/// * `key_text` - A text at least as long as the plaintext (e.g. a passage from a book).
+///   Non-alphabetic characters of the key text are ignored.
+///
+/// # Returns
+///
+/// The encrypted version of the `plaintext`, or an error if the key text is too short.
+pub fn encrypt_running_key(plaintext: &str, key_text: &str) -> Result<String, VigenereError> {
+    running_key(plaintext, key_text, Operation::Encrypt)
+}
+
+/// Decrypts the provided ciphertext using the running key variant of the Vigenère cipher.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted.
+/// * `key_text` - The text used as the key for encryption.
+///
+/// # Returns
+///
+/// The decrypted version of the `ciphertext`, or an error if the key text is too short.
+pub fn decrypt_running_key(ciphertext: &str, key_text: &str) -> Result<String, VigenereError> {
+    running_key(ciphertext, key_text, Operation::Decrypt)
+}
+
+/// Encrypts the provided plaintext using the Gronsfeld variant of the Vigenère cipher.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+/// * `key` - A numeric key, each digit of which is the shift applied to one letter.
+///
+/// # Returns
+///
+/// The encrypted version of the `plaintext`, or an error if the key is empty or not numeric.
+pub fn encrypt_gronsfeld(plaintext: &str, key: &str) -> Result<String, VigenereError> {
+    gronsfeld(plaintext, key, Operation::Encrypt)
+}
+
+/// Decrypts the provided ciphertext using the Gronsfeld variant of the Vigenère cipher.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted.
+/// * `key` - The numeric key used for encryption.
+///
+/// # Returns
+///
+/// The decrypted version of the `ciphertext`, or an error if the key is empty or not numeric.
+pub fn decrypt_gronsfeld(ciphertext: &str, key: &str) -> Result<String, VigenereError> {
+    gronsfeld(ciphertext, key, Operation::Decrypt)
+}
+
+/// Encrypts the provided plaintext using the Trithemius cipher.
+///
+/// The Trithemius cipher is a Vigenère cipher whose key is the whole alphabet,
+/// i.e. the shift increases by one for every letter.
+///
+/// # Arguments
+///
+/// * `plaintext` - The text to be encrypted.
+///
+/// # Returns
+///
+/// A new string that contains the encrypted version of the `plaintext`.
+pub fn encrypt_trithemius(plaintext: &str) -> String {
+    apply_shifts(
+        plaintext,
+        (0..).map(|i| i % ALPHABET_SIZE as isize),
+        Operation::Encrypt,
+    )
+}
+
+/// Decrypts the provided ciphertext using the Trithemius cipher.
+///
+/// # Arguments
+///
+/// * `ciphertext` - The text to be decrypted.
+///
+/// # Returns
+///
+/// A new string that contains the decrypted version of the `ciphertext`.
+pub fn decrypt_trithemius(ciphertext: &str) -> String {
+    apply_shifts(
+        ciphertext,
+        (0..).map(|i| i % ALPHABET_SIZE as isize),
+        Operation::Decrypt,
+    )
+}
+
 /// Transforms the provided text using the Vigenère cipher
 /// and the given key based on the specified operation (encryption or decryption).
 ///
@@ -82,18 +394,107 @@ pub fn prepare_string(input_string: &str) -> String {
 /// A new string that contains the transformed version of the `text` based on the specified operation.
 fn encipher(data: &str, key: &str, op: Operation) -> String {
     let extended_key = extend_key(key, data.len());
+    apply_shifts(data, extended_key.chars().map(letter_shift), op)
+}
 
+/// Encrypts or decrypts the text with a repeating key, recording the shift of every letter.
+fn encipher_traced(data: &str, key: &str, op: Operation) -> (String, Vec<Step>) {
     data.chars()
-        .enumerate()
-        .map(|(idx, c)| {
-            let shift = ALPHABET
-                .find(extended_key.chars().nth(idx).unwrap_or_default())
-                .unwrap_or_default() as isize;
-            shift_char(c, shift, op)
+        .zip(extend_key(key, data.len()).chars())
+        .map(|(c, k)| {
+            let output = shift_char(c, letter_shift(k), op);
+            let shift = match op {
+                Operation::Encrypt => letter_shift(k),
+                Operation::Decrypt => {
+                    (ALPHABET_SIZE as isize - letter_shift(k)) % ALPHABET_SIZE as isize
+                }
+            };
+            let step = Step::new(c, output, "shift")
+                .detail("key", k)
+                .detail("shift", shift);
+            (output, step)
         })
+        .unzip()
+}
+
+/// Shifts every character of the text by the corresponding shift of the key stream.
+///
+/// # Arguments
+///
+/// * `data` - The text to be transformed.
+/// * `shifts` - The key stream, as a sequence of shifts. It must be at least as long as `data`.
+/// * `op` - The operation to be performed (either `Operation::Encrypt` or `Operation::Decrypt`).
+///
+/// # Returns
+///
+/// A new string that contains the transformed version of `data`.
+fn apply_shifts(data: &str, shifts: impl Iterator<Item = isize>, op: Operation) -> String {
+    data.chars()
+        .zip(shifts)
+        .map(|(c, shift)| shift_char(c, shift, op))
         .collect()
 }
 
+/// Transforms the provided text using a running key.
+///
+/// # Arguments
+///
+/// * `data` - The text to be transformed.
+/// * `key_text` - The text used as the key. Non-alphabetic characters are ignored.
+/// * `op` - The operation to be performed (either `Operation::Encrypt` or `Operation::Decrypt`).
+///
+/// # Returns
+///
+/// The transformed version of `data`, or an error if the key text is too short.
+fn running_key(data: &str, key_text: &str, op: Operation) -> Result<String, VigenereError> {
+    let key = prepare_string(key_text);
+    if key.len() < data.len() {
+        return Err(VigenereError::KeyTooShort);
+    }
+
+    Ok(apply_shifts(data, key.chars().map(letter_shift), op))
+}
+
+/// Transforms the provided text using a numeric (Gronsfeld) key.
+///
+/// # Arguments
+///
+/// * `data` - The text to be transformed.
+/// * `key` - The numeric key.
+/// * `op` - The operation to be performed (either `Operation::Encrypt` or `Operation::Decrypt`).
+///
+/// # Returns
+///
+/// The transformed version of `data`, or an error if the key is empty or not numeric.
+fn gronsfeld(data: &str, key: &str, op: Operation) -> Result<String, VigenereError> {
+    let shifts = key
+        .chars()
+        .map(|c| c.to_digit(10).map(|d| d as isize))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(VigenereError::InvalidKey)?;
+    if shifts.is_empty() {
+        return Err(VigenereError::EmptyKey);
+    }
+
+    Ok(apply_shifts(data, shifts.into_iter().cycle(), op))
+}
+
+/// Returns the primer key of the autokey variants, falling back to `A` if the key is empty.
+fn autokey_primer(key: &str) -> &str {
+    if key.is_empty() {
+        "A"
+    } else {
+        key
+    }
+}
+
+/// Returns the shift represented by a key letter (`A` = 0, `B` = 1, ...).
+///
+/// Characters that are not uppercase letters represent a shift of 0.
+fn letter_shift(c: char) -> isize {
+    ALPHABET.find(c).unwrap_or_default() as isize
+}
+
 /// Extends the key to match the desired length by repeating it as necessary.
 ///
 /// This function generates a new key by cycling the provided key until it
@@ -170,7 +571,7 @@ mod tests {
     fn test_vigenere_decrypt() {
         let ciphertext = "RIJVS";
         let key = "KEY";
-        let plaintext = decrypt(&ciphertext, &prepare_string(key));
+        let plaintext = decrypt(ciphertext, &prepare_string(key));
         assert_eq!(plaintext, "HELLO");
     }
 
@@ -181,6 +582,100 @@ mod tests {
         assert_eq!(output, "HELLOWORLD");
     }
 
+    #[test]
+    fn test_vigenere_autokey() {
+        let ciphertext = encrypt_autokey("ATTACKATDAWN", "QUEENLY");
+        assert_eq!(ciphertext, "QNXEPVYTWTWP");
+        assert_eq!(decrypt_autokey(&ciphertext, "QUEENLY"), "ATTACKATDAWN");
+        assert_eq!(decrypt_autokey(&encrypt_autokey("HELLO", ""), ""), "HELLO");
+    }
+
+    #[test]
+    fn test_vigenere_ciphertext_autokey() {
+        let ciphertext = encrypt_ciphertext_autokey("ATTACKATDAWN", "QUEENLY");
+        assert_eq!(ciphertext, "QNXEPVYJQXAC");
+        assert_eq!(
+            decrypt_ciphertext_autokey(&ciphertext, "QUEENLY"),
+            "ATTACKATDAWN"
+        );
+    }
+
+    #[test]
+    fn test_vigenere_running_key() {
+        let book = "Call me Ishmael. Some years ago - never mind how long precisely";
+        let ciphertext = encrypt_running_key("ATTACKATDAWN", book).unwrap();
+        assert_eq!(ciphertext, "CTELOOILKMWR");
+        assert_eq!(
+            decrypt_running_key(&ciphertext, book),
+            Ok("ATTACKATDAWN".to_string())
+        );
+        assert_eq!(
+            encrypt_running_key("ATTACKATDAWN", "Call me"),
+            Err(VigenereError::KeyTooShort)
+        );
+    }
+
+    #[test]
+    fn test_vigenere_gronsfeld() {
+        let ciphertext = encrypt_gronsfeld("HELLO", "31415").unwrap();
+        assert_eq!(ciphertext, "KFPMT");
+        assert_eq!(
+            decrypt_gronsfeld(&ciphertext, "31415"),
+            Ok("HELLO".to_string())
+        );
+        assert_eq!(encrypt_gronsfeld("HELLO", ""), Err(VigenereError::EmptyKey));
+        assert_eq!(
+            encrypt_gronsfeld("HELLO", "KEY"),
+            Err(VigenereError::InvalidKey)
+        );
+    }
+
+    #[test]
+    fn test_vigenere_trithemius() {
+        let ciphertext = encrypt_trithemius("HELLO");
+        assert_eq!(ciphertext, "HFNOS");
+        assert_eq!(decrypt_trithemius(&ciphertext), "HELLO");
+        assert_eq!(
+            encrypt_trithemius(&"A".repeat(28)),
+            format!("{}AB", ALPHABET)
+        );
+    }
+
+    #[test]
+    fn test_vigenere_formatting() {
+        let ciphertext = encrypt_with_formatting("Attack at dawn!", "lemon", Formatting::Preserve);
+        assert_eq!(ciphertext, "Lxfopv ef rnhr!");
+        assert_eq!(
+            decrypt_with_formatting(&ciphertext, "LEMON", Formatting::Preserve),
+            "Attack at dawn!"
+        );
+        assert_eq!(
+            encrypt_with_formatting("Attack at dawn!", "LEMON", Formatting::Group(5)),
+            "LXFOP VEFRN HR"
+        );
+        assert_eq!(
+            decrypt_with_formatting("LXFOP VEFRN HR", "LEMON", Formatting::Strip),
+            "ATTACKATDAWN"
+        );
+    }
+
+    #[test]
+    fn test_vigenere_traced() {
+        let (ciphertext, steps) = encrypt_traced("ATTACK", "LEMON");
+        assert_eq!(ciphertext, encrypt("ATTACK", "LEMON"));
+        assert_eq!(
+            steps[0],
+            Step::new('A', 'L', "shift")
+                .detail("key", 'L')
+                .detail("shift", 11)
+        );
+        assert_eq!(steps[5].details[0], ("key".to_string(), "L".to_string()));
+
+        let (plaintext, steps) = decrypt_traced(&ciphertext, "LEMON");
+        assert_eq!(plaintext, "ATTACK");
+        assert_eq!(steps[1].details[1], ("shift".to_string(), "22".to_string()));
+    }
+
     #[test]
     fn test_vigenere_extend_key() {
         let key = "KEY";